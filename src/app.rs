@@ -1,51 +1,103 @@
 use anyhow::Result;
 use ratatui::{
-    layout::{Constraint, Direction, Layout, Rect},
+    layout::{Constraint, Direction, Layout, Margin, Rect},
     style::{Color, Modifier, Style},
     text::Line,
-    widgets::{Block, Borders, List, ListItem, Padding, Paragraph, Wrap},
+    widgets::{
+        Block, Borders, Clear, List, ListItem, Padding, Paragraph, Scrollbar,
+        ScrollbarOrientation, ScrollbarState, Wrap,
+    },
     Frame,
 };
 
 use crate::commands::CommandHandler;
 use crate::config::Config;
-use crate::formatting::format_messages_for_display;
-use crate::persistence::{Aliases, AppState, LayoutData, PaneState};
-use crate::split_view::{PaneNode, SplitDirection};
-use crate::telegram::TelegramClient;
-use crate::utils::{send_desktop_notification, try_autocomplete};
-use crate::widgets::ChatPane;
+use crate::formatting::{format_messages_for_display, FormatOptions, MessageAlignment};
+use crate::keybindings::Keybindings;
+use crate::persistence::{
+    Aliases, AppState, ChatAliases, ChatSet, Drafts, InputHistory, LayoutData, OfflineQueue, PaneState,
+    QueuedSend, ScrollPositions, LAYOUT_VERSION,
+};
+use crate::split_view::{
+    neighbor_in_direction, swap_panes, swapped_index, FocusDirection, PaneNode, SplitDirection,
+};
+use crate::telegram::{ChatKind, ConnectionState, TelegramClient};
+use crate::theme::Theme;
+use crate::utils::{
+    fuzzy_match, is_quiet_hours, looks_like_username, play_notification_sound,
+    send_desktop_notification, try_autocomplete, NotificationThrottle, TypingThrottle,
+};
+use crate::widgets::{
+    ChatPane, FilterType, MessageData, ReactionPicker, StatusLine, ANONYMOUS_SENDER_ID,
+    DEFAULT_REACTIONS,
+};
 
 pub struct App {
     pub config: Config,
     pub telegram: TelegramClient,
     pub my_user_id: i64,  // Current user's ID for determining outgoing messages
+    pub my_name: String,  // Current user's display name (for /me)
     pub chats: Vec<ChatInfo>,
     pub selected_chat_idx: usize,
     pub panes: Vec<ChatPane>,
     pub focused_pane_idx: usize,
     pub pane_tree: PaneNode,
-    pub input_history: Vec<String>,
+    pub input_history: InputHistory,
     pub history_idx: Option<usize>,
+    pub history_commands_only: bool, // Browsing started on "/...": Up/Down skip plain messages
     pub history_temp: String, // Save current input when browsing history
     pub aliases: Aliases,
+    pub chat_aliases: ChatAliases, // Local chat display names (/rename)
+    pub drafts: Drafts,            // Unsent input of chats not open in a pane
+    pub scroll_positions: ScrollPositions, // Scroll offsets of chats not open in a pane
+    pub pinned_chats: ChatSet,             // Listed above all other chats (/pinchat)
+    pub muted_chats: ChatSet,              // Never trigger desktop notifications (/mute)
     pub focus_on_chat_list: bool,
     pub status_message: Option<String>, // Notification bar at bottom
     pub status_expire: Option<std::time::Instant>,
+    pub shown_connection: ConnectionState, // Connection state as last drawn in the status bar
     pub pane_areas: std::collections::HashMap<usize, Rect>, // Track pane screen positions
     pub chat_list_area: Option<Rect>, // Track chat list area for mouse clicks
     pub needs_redraw: bool,
+    pub resize_pending: bool, // Terminal resized or chat switched; re-clamp scroll offsets after the next draw
+    pub notification_throttle: NotificationThrottle, // Coalesces notification bursts per chat
+    typing_throttle: TypingThrottle, // Rate-limits our outgoing "typing…" updates
+    user_statuses_refreshed: Option<std::time::Instant>, // Last periodic last-seen refresh
+    pub undo_stack: Vec<UndoAction>, // Recent destructive actions, most recent last
+    pub offline_queue: Vec<QueuedSend>, // Messages written while offline, sent on reconnect
 
     // Settings
     pub show_reactions: bool,
     pub show_notifications: bool,
+    pub notification_sound: String, // "off", "system" or path to a sound file
     pub compact_mode: bool,
+    pub group_messages: bool, // Consecutive messages from one sender share a header
     pub show_emojis: bool,
     pub show_line_numbers: bool,
     pub show_timestamps: bool,
     pub show_chat_list: bool,
     pub show_user_colors: bool,
     pub show_borders: bool,
+    pub focus_mode: bool, // Only surface chats/panes with something new
+    pub chat_list_filter: String, // Typed while the chat list has focus; narrows it by fuzzy match
+    chat_list_filter_open: bool,  // "/" pressed on the chat list: filter box shown even while empty
+    pub reaction_picker: Option<ReactionPicker>, // Open reaction popup (/react N, Alt+R)
+    pub keybindings: Keybindings,
+    pub available_reactions: Vec<String>, // Fetched once for the picker; empty until then
+    reply_cache: std::collections::HashMap<(i64, i32), (String, String)>, // (chat_id, msg_id) -> (sender, text) of reply targets
+    reply_misses: std::collections::HashSet<(i64, i32)>, // Reply targets the lookup didn't return (deleted); not asked for again
+    pub clipboard: Option<arboard::Clipboard>, // Opened on first /copy; kept so X11 keeps serving it
+    pub message_alignment: MessageAlignment,
+    pub collapse_lines: usize, // Long messages are cut to this many lines until /expand (0 = off)
+    pub render_markdown: bool, // Style **bold**, *italic* and `code` in message text
+    pub dnd: bool,             // Quiet hours on: desktop notifications held back in the window
+    pub dnd_hours: (u8, u8),   // Quiet window as (start, end) local hours, may cross midnight
+    pub max_panes: usize, // Upper bound on open panes; further splits are refused
+    pub message_fetch_limit: usize, // Messages loaded per chat open/refresh (/limit), at most MAX_FETCH_LIMIT
+    pub away_message: Option<String>, // Away mode auto-reply text (/away)
+    pub away_replied: std::collections::HashSet<i64>, // DMs already auto-replied to this session
+    pub truecolor: bool,  // Terminal supports 24-bit color; otherwise RGB is downgraded
+    pub theme: Theme,     // Already adapted to `truecolor`
     pub user_colors: std::collections::HashMap<i64, Color>, // Map sender_id to color for group chats
 }
 
@@ -55,18 +107,64 @@ pub struct ChatInfo {
     pub name: String,
     pub username: Option<String>,
     pub unread: u32,
-    pub _is_channel: bool,
-    pub is_group: bool,
+    pub kind: ChatKind,
+    pub can_post: bool, // False for broadcast channels where we lack post rights
+    pub muted: bool,    // Notifications silenced (/mute or muted in another client)
+    pub last_message_date: i64, // Unix time of the newest message; orders the chat list
 }
 
+/// A destructive action that /undo can reverse (or, for deletes, approximate)
+pub enum UndoAction {
+    /// Server-side deletes are permanent; undo resends the captured text as a new message
+    DeletedMessage { chat_id: i64, text: String },
+    AliasRemoved { user_id: i64, alias: String },
+    FilterChanged {
+        pane_idx: usize,
+        chat_id: Option<i64>,
+        filter_type: Option<FilterType>,
+        filter_value: Option<String>,
+    },
+    PaneCleared {
+        pane_idx: usize,
+        chat_id: Option<i64>,
+        msg_data: Vec<MessageData>,
+        messages: Vec<StatusLine>,
+    },
+}
+
+const MAX_UNDO: usize = 20;
+
+/// Older messages fetched per PageUp past the top of a pane
+const HISTORY_PAGE_SIZE: usize = 50;
+
+/// Pages of older history /next and /prev load at most to reach a match
+const SEARCH_CONTEXT_PAGES: usize = 20;
+
+/// Lines scrolled per mouse wheel notch
+const MOUSE_SCROLL_LINES: usize = 3;
+
+/// How often last seen statuses in pane headers are re-fetched
+const USER_STATUS_REFRESH: std::time::Duration = std::time::Duration::from_secs(60);
+
 impl App {
     pub async fn new() -> Result<Self> {
         let config = Config::load()?;
         let telegram = TelegramClient::new(&config).await?;
-        let my_user_id = telegram.get_me().await?;
+        let (my_user_id, my_name) = telegram.get_me().await?;
+        let truecolor = crate::utils::detect_truecolor();
+        let (keybindings, keybinding_warnings) =
+            Keybindings::from_settings(&config.settings.keybindings);
+        let default_settings = crate::persistence::AppSettings::default();
         let app_state = AppState::load(&config).unwrap_or_else(|_| AppState {
             settings: crate::persistence::AppSettings::default(),
             aliases: Aliases::default(),
+            chat_aliases: ChatAliases::default(),
+            drafts: Drafts::default(),
+            scroll_positions: ScrollPositions::default(),
+            pinned_chats: ChatSet::default(),
+            muted_chats: ChatSet::default(),
+            input_history: InputHistory::default(),
+            offline_queue: OfflineQueue::default(),
             layout: LayoutData::default(),
         });
 
@@ -99,107 +197,211 @@ impl App {
         // Load panes - create panes for all indices up to total_panes_needed
         let mut panes: Vec<ChatPane> = Vec::new();
         for i in 0..total_panes_needed {
-            if let Some(ps) = app_state.layout.panes.get(i) {
-                // Load saved pane state
-                let mut pane = ChatPane::new();
-                pane.chat_id = ps.chat_id;
-                pane.chat_name = ps.chat_name.clone();
-                pane.scroll_offset = ps.scroll_offset;
-                // Load filter settings
-                if let Some(ref filter_type_str) = ps.filter_type {
-                    pane.filter_type = Some(match filter_type_str.as_str() {
-                        "sender" => crate::widgets::FilterType::Sender,
-                        "media" => crate::widgets::FilterType::Media,
-                        "link" => crate::widgets::FilterType::Link,
-                        _ => {
-                            panes.push(pane);
-                            continue;
-                        }
-                    });
-                }
-                pane.filter_value = ps.filter_value.clone();
-                panes.push(pane);
-            } else {
-                // Create empty pane for missing index
-                panes.push(ChatPane::new());
-            }
+            // Saved view state, or an empty pane for an index the tree references
+            let pane = app_state.layout.panes.get(i).map_or_else(ChatPane::new, PaneState::to_pane);
+            panes.push(pane);
         }
         
-        let focused_pane_idx = if app_state.layout.focused_pane < panes.len() {
-            app_state.layout.focused_pane
-        } else {
-            0
-        };
+        // Drop slots the tree no longer references (older layouts kept closed panes)
+        let mut pane_tree = pane_tree;
+        let remap = crate::split_view::compact_panes(&mut pane_tree, &mut panes);
+        let focused_pane_idx = remap.get(&app_state.layout.focused_pane).copied().unwrap_or(0);
 
         let mut app = Self {
             config,
             telegram,
             my_user_id,
+            my_name,
             chats,
             selected_chat_idx: 0,
             panes,
             focused_pane_idx,
             pane_tree,
-            input_history: Vec::new(),
+            input_history: app_state.input_history,
             history_idx: None,
+            history_commands_only: false,
             history_temp: String::new(),
             aliases: app_state.aliases,
+            chat_aliases: app_state.chat_aliases,
+            drafts: app_state.drafts,
+            scroll_positions: app_state.scroll_positions,
+            pinned_chats: app_state.pinned_chats,
+            muted_chats: app_state.muted_chats,
             focus_on_chat_list: true,
             status_message: None,
             status_expire: None,
+            shown_connection: ConnectionState::Connecting,
             chat_list_area: None,
             pane_areas: std::collections::HashMap::new(),
             needs_redraw: true,
+            resize_pending: false,
+            notification_throttle: NotificationThrottle::new(std::time::Duration::from_secs(10)),
+            typing_throttle: TypingThrottle::new(std::time::Duration::from_secs(4)),
+            user_statuses_refreshed: None,
+            undo_stack: Vec::new(),
+            offline_queue: app_state.offline_queue.sends,
             show_reactions: app_state.settings.show_reactions,
             show_notifications: app_state.settings.show_notifications,
+            notification_sound: app_state.settings.notification_sound.clone(),
             compact_mode: app_state.settings.compact_mode,
+            group_messages: app_state.settings.group_messages,
             show_emojis: app_state.settings.show_emojis,
             show_line_numbers: app_state.settings.show_line_numbers,
             show_timestamps: app_state.settings.show_timestamps,
             show_chat_list: app_state.settings.show_chat_list,
             show_user_colors: app_state.settings.show_user_colors,
             show_borders: app_state.settings.show_borders,
+            focus_mode: app_state.settings.focus_mode,
+            chat_list_filter: String::new(),
+            chat_list_filter_open: false,
+            reaction_picker: None,
+            available_reactions: Vec::new(),
+            reply_cache: std::collections::HashMap::new(),
+            reply_misses: std::collections::HashSet::new(),
+            clipboard: None,
+            message_alignment: MessageAlignment::from_setting(&app_state.settings.message_alignment),
+            collapse_lines: app_state.settings.collapse_lines,
+            render_markdown: app_state.settings.render_markdown,
+            dnd: app_state.settings.dnd,
+            // Out-of-range hours would never match; fall back to the default window
+            dnd_hours: crate::utils::valid_dnd_hours(app_state.settings.dnd_start, app_state.settings.dnd_end)
+                .unwrap_or((default_settings.dnd_start, default_settings.dnd_end)),
+            max_panes: app_state.settings.max_panes.max(1),
+            message_fetch_limit: app_state
+                .settings
+                .message_fetch_limit
+                .clamp(1, crate::config::MAX_FETCH_LIMIT),
+            away_message: app_state.settings.away_message.clone(),
+            away_replied: std::collections::HashSet::new(),
+            truecolor,
+            theme: Theme::from_setting(&app_state.settings.theme).for_terminal(truecolor),
             user_colors: std::collections::HashMap::new(),
+            keybindings,
         };
 
+        if !keybinding_warnings.is_empty() {
+            app.notify_with_duration(&format!("Config: {}", keybinding_warnings.join("; ")), 15);
+        }
+
+        for pane in &mut app.panes {
+            if let Some(draft) = pane.chat_id.and_then(|id| app.drafts.take(id)) {
+                pane.input_cursor = draft.len();
+                pane.input_buffer = draft;
+            }
+        }
+
         // Load messages for all panes that have a saved chat_id
         // This is what we had before - it works better
         app.load_saved_chat_messages().await?;
+        app.restore_saved_searches().await;
 
         Ok(app)
     }
 
+    /// Fetch one page of /search results into a pane. A non-zero `offset_id`
+    /// continues an earlier search, appending older results ("/search more").
+    /// Returns the number of results on this page; the pane is untouched when 0.
+    pub async fn load_search_page(
+        &mut self,
+        pane_idx: usize,
+        chat_id: i64,
+        query: &str,
+        offset_id: i32,
+    ) -> Result<usize> {
+        let results = self
+            .telegram
+            .search_messages(chat_id, query, crate::commands::SEARCH_PAGE_SIZE, offset_id)
+            .await?;
+        if results.is_empty() {
+            return Ok(0);
+        }
+
+        // Convert to MessageData for proper formatting support
+        let mut msg_data: Vec<MessageData> = results
+            .into_iter()
+            .map(|raw| MessageData::from_raw(raw, self.my_user_id))
+            .collect();
+
+        let page_len = msg_data.len();
+        if let Some(pane) = self.panes.get_mut(pane_idx) {
+            // Results come oldest first, so the first one is where the next page starts
+            pane.search_offset_id = msg_data[0].msg_id;
+            if offset_id != 0 {
+                msg_data.append(&mut pane.msg_data);
+            }
+            pane.msg_data = msg_data;
+            pane.format_cache.clear();
+            pane.search_query = Some(query.to_string());
+            pane.search_term = Some(query.to_string());
+            pane.search_matches = pane.msg_data.iter().rev().map(|m| m.msg_id).collect();
+            pane.search_match_idx = None;
+            // Don't clear messages - they may contain status messages
+            pane.chat_name = format!(
+                "{} | Search: '{}' ({} results)",
+                pane.base_chat_name(),
+                query,
+                pane.msg_data.len()
+            );
+            pane.scroll_offset = 0;
+        }
+        self.resolve_reply_previews(pane_idx).await;
+        Ok(page_len)
+    }
+
+    /// Re-run searches that were active when the layout was saved. If one
+    /// fails or finds nothing now, the pane falls back to the live chat.
+    async fn restore_saved_searches(&mut self) {
+        let searches: Vec<(usize, i64, String, usize)> = self
+            .panes
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, pane)| {
+                let query = pane.search_query.clone()?;
+                Some((idx, pane.chat_id?, query, pane.scroll_offset))
+            })
+            .collect();
+
+        for (idx, chat_id, query, scroll_offset) in searches {
+            let found = self.load_search_page(idx, chat_id, &query, 0).await;
+            if let Some(pane) = self.panes.get_mut(idx) {
+                if matches!(found, Ok(n) if n > 0) {
+                    pane.scroll_offset = scroll_offset;
+                } else {
+                    pane.search_query = None;
+                }
+            }
+        }
+    }
+
     /// Refresh messages for a specific pane
     async fn refresh_pane_messages(&mut self, pane_idx: usize) -> Result<()> {
         if let Some(pane) = self.panes.get(pane_idx) {
             if let Some(chat_id) = pane.chat_id {
-                match self.telegram.get_messages(chat_id, 50).await {
+                match self.telegram.get_messages(chat_id, self.message_fetch_limit).await {
                     Ok(raw_messages) => {
                         if !raw_messages.is_empty() {
                             let msg_data: Vec<crate::widgets::MessageData> = raw_messages
-                                .iter()
-                                .map(|(msg_id, sender_id, sender_name, text, reply_to_id, media_type, reactions)| {
-                                    let reply_to_msg_id = *reply_to_id;
-                                    
-                                    crate::widgets::MessageData {
-                                        msg_id: *msg_id,
-                                        sender_id: *sender_id,
-                                        sender_name: sender_name.clone(),
-                                        text: text.clone(),
-                                        is_outgoing: *sender_id == self.my_user_id,
-                                        timestamp: chrono::Utc::now().timestamp(),
-                                        media_type: media_type.clone(),
-                                        media_label: None,
-                                        reactions: reactions.clone(),
-                                        reply_to_msg_id,
-                                        reply_sender: None,
-                                        reply_text: None,
-                                    }
-                                })
+                                .into_iter()
+                                .map(|raw| crate::widgets::MessageData::from_raw(raw, self.my_user_id))
                                 .collect();
                             
                             if let Some(pane) = self.panes.get_mut(pane_idx) {
-                                pane.msg_data = msg_data;
+                                // Keep older history loaded by scrolling up
+                                let oldest_fresh = msg_data.first().map_or(0, |m| m.msg_id);
+                                let (pending, loaded): (Vec<_>, Vec<_>) =
+                                    pane.msg_data.drain(..).partition(|m| m.pending);
+                                let mut kept: Vec<_> = loaded
+                                    .into_iter()
+                                    .filter(|m| m.msg_id < oldest_fresh)
+                                    .collect();
+                                // Echoes the server hasn't returned yet stay at the bottom
+                                let unsent: Vec<_> = pending
+                                    .into_iter()
+                                    .filter(|p| !msg_data.iter().any(|m| m.is_outgoing && m.text == p.text))
+                                    .collect();
+                                kept.extend(msg_data);
+                                kept.extend(unsent);
+                                pane.msg_data = kept;
                                 pane.format_cache.clear(); // Clear cache so messages are re-rendered
                             }
                         }
@@ -210,6 +412,7 @@ impl App {
                 }
             }
         }
+        self.resolve_reply_previews(pane_idx).await;
         Ok(())
     }
 
@@ -218,29 +421,12 @@ impl App {
         for (_idx, pane) in self.panes.iter_mut().enumerate() {
             if let Some(chat_id) = pane.chat_id {
                 // Try to load messages for this chat
-                match self.telegram.get_messages(chat_id, 50).await {
+                match self.telegram.get_messages(chat_id, self.message_fetch_limit).await {
                     Ok(raw_messages) => {
                         if !raw_messages.is_empty() {
                             let msg_data: Vec<crate::widgets::MessageData> = raw_messages
-                                .iter()
-                                .map(|(msg_id, sender_id, sender_name, text, reply_to_id, media_type, reactions)| {
-                                    let reply_to_msg_id = *reply_to_id;
-                                    
-                                    crate::widgets::MessageData {
-                                        msg_id: *msg_id,
-                                        sender_id: *sender_id,
-                                        sender_name: sender_name.clone(),
-                                        text: text.clone(),
-                                        is_outgoing: *sender_id == self.my_user_id,
-                                        timestamp: chrono::Utc::now().timestamp(),
-                                        media_type: media_type.clone(),
-                                        media_label: None,
-                                        reactions: reactions.clone(),
-                                        reply_to_msg_id,
-                                        reply_sender: None,
-                                        reply_text: None,
-                                    }
-                                })
+                                .into_iter()
+                                .map(|raw| crate::widgets::MessageData::from_raw(raw, self.my_user_id))
                                 .collect();
                             
                             pane.msg_data = msg_data;
@@ -258,6 +444,9 @@ impl App {
                 }
             }
         }
+        for pane_idx in 0..self.panes.len() {
+            self.resolve_reply_previews(pane_idx).await;
+        }
         Ok(())
     }
 
@@ -267,6 +456,7 @@ impl App {
         // Check typing indicators for expiry
         for pane in &mut self.panes {
             pane.check_typing_expired();
+            pane.prune_finished_sends();
         }
         // Check status message expiry
         if let Some(expire) = self.status_expire {
@@ -276,16 +466,10 @@ impl App {
             }
         }
 
-        let has_status = self.status_message.is_some();
-        let main_constraints = if has_status {
-            vec![Constraint::Min(0), Constraint::Length(1)]
-        } else {
-            vec![Constraint::Min(0)]
-        };
-
+        // The status bar is always shown so the connection state stays visible
         let outer = Layout::default()
             .direction(Direction::Vertical)
-            .constraints(main_constraints)
+            .constraints([Constraint::Min(0), Constraint::Length(1)])
             .split(f.area());
 
         let (chat_area, pane_area) = if self.show_chat_list {
@@ -309,26 +493,17 @@ impl App {
             self.chat_list_area = None;
         }
 
-        let colors = [
-            Color::Cyan, Color::Yellow, Color::Magenta, Color::Blue,
-            Color::Red, Color::Green, Color::White, Color::LightCyan,
-            Color::LightYellow, Color::LightMagenta, Color::LightBlue,
-            Color::LightRed, Color::LightGreen, Color::DarkGray,
-            Color::Rgb(192, 192, 192),
-            Color::Rgb(255, 165, 0),
-            Color::Rgb(255, 192, 203),
-            Color::Rgb(128, 0, 128),
-            Color::Rgb(0, 255, 255),
-            Color::Rgb(255, 20, 147)
-        ];
-        
+        let colors = &self.theme.user_palette;
         let mut senders_to_color: Vec<i64> = Vec::new();
         for pane in &self.panes {
             if let Some(chat_id) = pane.chat_id {
-                let is_group_chat = self.chats.iter().any(|c| c.id == chat_id && c.is_group);
+                let is_group_chat = self.chats.iter().any(|c| c.id == chat_id && c.kind.is_group());
                 if is_group_chat && !pane.msg_data.is_empty() {
                     for msg in &pane.msg_data {
-                        if !self.user_colors.contains_key(&msg.sender_id) && !senders_to_color.contains(&msg.sender_id) {
+                        if msg.sender_id != ANONYMOUS_SENDER_ID
+                            && !self.user_colors.contains_key(&msg.sender_id)
+                            && !senders_to_color.contains(&msg.sender_id)
+                        {
                             senders_to_color.push(msg.sender_id);
                         }
                     }
@@ -359,14 +534,75 @@ impl App {
             .render(f, pane_area, &self.panes, self.focused_pane_idx, &render_fn, &mut pane_areas);
         self.pane_areas = pane_areas;
 
-        // Draw status bar
-        if has_status {
-            if let Some(ref msg) = self.status_message {
-                let status = Paragraph::new(msg.as_str())
-                    .style(Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD));
-                f.render_widget(status, outer[1]);
-            }
+        // Draw status bar: notification on the left, connection state on the right
+        self.shown_connection = self.telegram.connection_state();
+        let (label, color) = match self.shown_connection {
+            ConnectionState::Connected => ("● Connected", self.theme.header_focused),
+            ConnectionState::Connecting => ("◐ Connecting", self.theme.highlight),
+            ConnectionState::Offline => ("○ Offline", self.theme.alert),
+        };
+        let status_row = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([
+                Constraint::Min(0),
+                Constraint::Length(label.chars().count() as u16 + 1),
+            ])
+            .split(outer[1]);
+        if let Some(ref msg) = self.status_message {
+            let status = Paragraph::new(msg.as_str())
+                .style(Style::default().fg(self.theme.highlight).add_modifier(Modifier::BOLD));
+            f.render_widget(status, status_row[0]);
         }
+        let connection = Paragraph::new(label)
+            .style(Style::default().fg(color))
+            .right_aligned();
+        f.render_widget(connection, status_row[1]);
+
+        if let Some(ref picker) = self.reaction_picker {
+            self.draw_reaction_picker(f, picker);
+        }
+    }
+
+    /// Small centered popup with the reaction grid; the selected cell is reversed
+    fn draw_reaction_picker(&self, f: &mut Frame, picker: &ReactionPicker) {
+        let rows: Vec<Line> = picker
+            .options
+            .chunks(ReactionPicker::COLUMNS)
+            .enumerate()
+            .map(|(row, chunk)| {
+                let spans: Vec<ratatui::text::Span> = chunk
+                    .iter()
+                    .enumerate()
+                    .map(|(col, emoji)| {
+                        let style = if row * ReactionPicker::COLUMNS + col == picker.selected {
+                            Style::default().add_modifier(Modifier::REVERSED)
+                        } else {
+                            Style::default()
+                        };
+                        ratatui::text::Span::styled(format!(" {} ", emoji), style)
+                    })
+                    .collect();
+                Line::from(spans)
+            })
+            .collect();
+
+        let area = f.area();
+        let width = (ReactionPicker::COLUMNS as u16 * 4 + 4).min(area.width);
+        let height = (rows.len() as u16 + 2).min(area.height);
+        let popup = Rect {
+            x: area.x + (area.width - width) / 2,
+            y: area.y + (area.height - height) / 2,
+            width,
+            height,
+        };
+
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title(format!("React to #{}", picker.msg_num))
+            .title_bottom(Line::from("arrows · Enter · Esc").centered())
+            .border_style(Style::default().fg(self.theme.border_focused));
+        f.render_widget(Clear, popup);
+        f.render_widget(Paragraph::new(rows).block(block), popup);
     }
 
     fn draw_chat_list(&self, f: &mut Frame, area: Rect) {
@@ -376,13 +612,13 @@ impl App {
             .and_then(|p| p.chat_id);
         
         let max_width = area.width.saturating_sub(6).max(1) as usize;
-        let (unread_group, active_group, other_group) = self.chat_list_groups();
+        let (pinned_group, unread_group, active_group, other_group) = self.chat_list_groups();
 
         let build_item = |chat: &ChatInfo| -> ListItem {
             // Highlight if this chat is open in the focused pane
             let base_style = if Some(chat.id) == active_chat_id {
                 Style::default()
-                    .fg(Color::Yellow)
+                    .fg(self.theme.highlight)
                     .add_modifier(Modifier::BOLD)
             } else {
                 Style::default()
@@ -395,7 +631,7 @@ impl App {
                 String::new()
             };
 
-            let mut name_part = chat.name.clone();
+            let mut name_part = self.chat_display_name(chat.id, &chat.name).to_string();
             if let Some(ref username) = chat.username {
                 if !username.is_empty() {
                     name_part.push_str(&format!(" {}", username));
@@ -406,12 +642,18 @@ impl App {
             if !unread_marker.is_empty() {
                 spans.push(ratatui::text::Span::styled(
                     unread_marker.to_string(),
-                    Style::default().fg(Color::Red),
+                    Style::default().fg(self.theme.alert),
                 ));
             }
             if !unread_count.is_empty() {
                 spans.push(ratatui::text::Span::styled(unread_count, base_style));
             }
+            if self.pinned_chats.contains(chat.id) {
+                spans.push(ratatui::text::Span::raw("📌 "));
+            }
+            if chat.muted || self.muted_chats.contains(chat.id) {
+                spans.push(ratatui::text::Span::raw("🔇 "));
+            }
             spans.push(ratatui::text::Span::styled(name_part, base_style));
 
             // Truncate spans to fit
@@ -443,10 +685,17 @@ impl App {
         };
 
         let header_style = Style::default()
-            .fg(Color::DarkGray)
+            .fg(self.theme.dim)
             .add_modifier(Modifier::BOLD);
         let mut items: Vec<ListItem> = Vec::new();
 
+        if !pinned_group.is_empty() {
+            items.push(ListItem::new("Pinned").style(header_style));
+            for chat_idx in pinned_group.iter() {
+                items.push(build_item(&self.chats[*chat_idx]));
+            }
+        }
+
         if !unread_group.is_empty() {
             items.push(ListItem::new("Unread").style(header_style));
             for chat_idx in unread_group.iter() {
@@ -468,16 +717,38 @@ impl App {
             }
         }
 
+        if items.is_empty() && !self.chat_list_filter.is_empty() {
+            let hint = if looks_like_username(&self.chat_list_filter) {
+                format!("Enter: open {}", self.chat_list_filter)
+            } else {
+                "No matching chats".to_string()
+            };
+            items.push(ListItem::new(hint).style(Style::default().fg(self.theme.dim)));
+        }
+
         let border_style = if self.focus_on_chat_list {
-            Style::default().fg(Color::Green)
+            Style::default().fg(self.theme.border_focused)
         } else {
             Style::default()
         };
 
+        // Inbox total; opening a chat zeroes its count, so this drops as chats are read
+        let total_unread: u32 = self.chats.iter().map(|c| c.unread).sum();
+        let mut list_title = if self.focus_mode { "Chats [focus]" } else { "Chats" }.to_string();
+        if total_unread > 0 {
+            list_title.push_str(&format!(" ({})", total_unread));
+        }
+        if self.chat_list_filter_open || !self.chat_list_filter.is_empty() {
+            list_title.push_str(&format!(" /{}", self.chat_list_filter));
+        }
         let list_block = if self.show_borders {
             Block::default()
                 .borders(Borders::ALL)
-                .title("Chats")
+                .title(if total_unread > 0 {
+                    Line::styled(list_title, Style::default().add_modifier(Modifier::BOLD))
+                } else {
+                    Line::raw(list_title)
+                })
                 .border_style(border_style)
         } else {
             Block::default()
@@ -502,23 +773,10 @@ impl App {
         let border_overhead = if self.show_borders { 2 } else { 0 };
         let header_height = if self.show_borders { 3 } else { 1 };
         let inner_width = area.width.saturating_sub(if self.show_borders { 2 } else { 0 }).max(1) as usize;
-        let text_lines = if is_focused && inner_width > 0 {
-            let buf = &pane.input_buffer;
-            let mut lines: u16 = 0;
-            for line in buf.split('\n') {
-                // Each logical line wraps based on its length (+ cursor on last segment)
-                let len = line.len();
-                lines += ((len as f64) / (inner_width as f64)).ceil().max(1.0) as u16;
-            }
-            // Account for cursor on the last line
-            let last_line_len = buf.rsplit('\n').next().map_or(buf.len(), |l| l.len()) + 1;
-            if last_line_len > inner_width {
-                let without_cursor = buf.rsplit('\n').next().map_or(buf.len(), |l| l.len());
-                let lines_without = ((without_cursor as f64) / (inner_width as f64)).ceil().max(1.0) as u16;
-                let lines_with = ((last_line_len as f64) / (inner_width as f64)).ceil().max(1.0) as u16;
-                lines += lines_with - lines_without;
-            }
-            lines.max(1)
+        // A long paste may take up to half the pane; the input then scrolls to the cursor
+        let max_text_lines = (area.height / 2).max(1);
+        let text_lines = if is_focused {
+            crate::formatting::input_line_count(&pane.input_buffer, inner_width).min(max_text_lines)
         } else {
             1
         };
@@ -549,23 +807,31 @@ impl App {
             if self.focus_on_chat_list {
                 // Show which pane will receive the next chat from list
                 Style::default()
-                    .fg(Color::Yellow)
+                    .fg(self.theme.header_target)
                     .add_modifier(Modifier::BOLD)
             } else {
                 // Active input pane
                 Style::default()
-                    .fg(Color::Green)
+                    .fg(self.theme.header_focused)
                     .add_modifier(Modifier::BOLD)
             }
         } else {
-            Style::default().fg(Color::Cyan)
+            Style::default().fg(self.theme.header)
         };
 
         let mut header_text = String::new();
         if is_focused && self.focus_on_chat_list {
             header_text.push_str("[TARGET] ");
         }
-        header_text.push_str(&pane.header_text());
+        let chat_name = match pane.chat_id {
+            Some(id) => self.chat_display_name(id, &pane.chat_name),
+            None => &pane.chat_name,
+        };
+        header_text.push_str(&pane.header_text(chat_name));
+        let read_only = pane.chat_id.is_some_and(|id| !self.can_post_in(id));
+        if read_only {
+            header_text.push_str(" [read-only]");
+        }
         
         let header_block = if self.show_borders {
             Block::default().borders(Borders::ALL)
@@ -582,12 +848,12 @@ impl App {
         
         // Check if this is a group chat
         let is_group_chat = if let Some(chat_id) = pane.chat_id {
-            self.chats.iter().any(|c| c.id == chat_id && c.is_group)
+            self.chats.iter().any(|c| c.id == chat_id && c.kind.is_group())
         } else {
             false
         };
         
-        let display_lines = if !pane.msg_data.is_empty() {
+        let formatted = if !pane.msg_data.is_empty() {
             // Use msg_data for rich formatting
             let filter_type = pane
                 .filter_type
@@ -599,98 +865,53 @@ impl App {
                 });
             let filter_value = pane.filter_value.as_deref();
 
-            let mut lines = format_messages_for_display(
-                &pane.msg_data,
-                message_width,
-                self.compact_mode,
-                self.show_emojis,
-                self.show_reactions,
-                self.show_timestamps,
-                self.show_line_numbers,
-                filter_type,
-                filter_value,
-                pane.unread_count_at_load,
-                &self.aliases.map,
-            );
-            
-            // Append any status messages from pane.messages (like "✓ Replied to #5")
-            if !pane.messages.is_empty() {
-                lines.push(String::new()); // Separator
-                lines.extend(pane.messages.clone());
-            }
-            lines
+            let cache_key = crate::widgets::FormatCacheKey {
+                width: message_width.min(u16::MAX as usize) as u16,
+                compact_mode: self.compact_mode,
+                group_messages: self.group_messages,
+                show_emojis: self.show_emojis,
+                show_reactions: self.show_reactions,
+                show_timestamps: self.show_timestamps,
+                show_line_numbers: self.show_line_numbers,
+                msg_count: pane.msg_data.len(),
+                filter_type: filter_type.map(|s| s.to_string()),
+                filter_value: filter_value.map(|s| s.to_string()),
+                first_unread: pane.first_unread_msg_id,
+                alignment: self.message_alignment.as_setting(),
+                log_view: pane.log_view,
+                render_markdown: self.render_markdown,
+                search_term: pane.search_term.clone(),
+            };
+            pane.format_cache.get_or_format(cache_key, || {
+                format_messages_for_display(
+                    &pane.msg_data,
+                    &self.aliases.map,
+                    &pane.expanded_messages,
+                    &FormatOptions {
+                        width: message_width,
+                        compact_mode: self.compact_mode,
+                        show_emojis: self.show_emojis,
+                        show_reactions: self.show_reactions,
+                        show_timestamps: self.show_timestamps,
+                        show_line_numbers: self.show_line_numbers,
+                        filter_type,
+                        filter_value,
+                        first_unread: pane.first_unread_msg_id,
+                        alignment: self.message_alignment,
+                        collapse_lines: self.collapse_lines,
+                        log_view: pane.log_view,
+                        grouped: self.group_messages,
+                        render_markdown: self.render_markdown,
+                        search_term: pane.search_term.as_deref(),
+                    },
+                )
+            })
         } else {
-            // Fallback to plain messages (for status messages, etc.)
-            pane.messages.clone()
-        };
-
-        let wrap_plain_text = |text: &str, max_width: usize| -> Vec<String> {
-            if max_width == 0 || text.len() <= max_width {
-                return vec![text.to_string()];
-            }
-
-            let mut lines = Vec::new();
-            let mut current_line = String::new();
-
-            for word in text.split_whitespace() {
-                if current_line.len() + word.len() + 1 > max_width {
-                    if !current_line.is_empty() {
-                        lines.push(current_line.clone());
-                        current_line.clear();
-                    }
-                    if word.chars().count() > max_width {
-                        let split_at = word
-                            .char_indices()
-                            .nth(max_width)
-                            .map(|(i, _)| i)
-                            .unwrap_or(word.len());
-                        lines.push(word[..split_at].to_string());
-                        current_line = word[split_at..].to_string();
-                    } else {
-                        current_line = word.to_string();
-                    }
-                } else {
-                    if !current_line.is_empty() {
-                        current_line.push(' ');
-                    }
-                    current_line.push_str(word);
-                }
-            }
-            if !current_line.is_empty() {
-                lines.push(current_line);
-            }
-            lines
+            crate::formatting::FormattedMessages::default()
         };
+        let wrap_plain_text = crate::formatting::wrap_plain_text;
 
-        let wrap_message_with_indent =
-            |prefix: &str, sender_name: &str, message_text: &str, max_width: usize| -> Vec<String> {
-                let header = format!("{}{}: ", prefix, sender_name);
-                let indent_len = header.chars().count();
-
-                if max_width == 0 {
-                    return vec![format!("{}{}", header, message_text)];
-                }
-
-                if indent_len >= max_width {
-                    return wrap_plain_text(&format!("{}{}", header, message_text), max_width);
-                }
-
-                let first_width = max_width.saturating_sub(indent_len);
-                let wrapped = wrap_plain_text(message_text, first_width);
-                if wrapped.is_empty() {
-                    return vec![header.trim_end().to_string()];
-                }
-
-                let indent = " ".repeat(indent_len);
-                let mut lines = Vec::with_capacity(wrapped.len());
-                lines.push(format!("{}{}", header, wrapped[0]));
-                for line in wrapped.iter().skip(1) {
-                    lines.push(format!("{}{}", indent, line));
-                }
-                lines
-            };
-
-        let style_name_in_line = |line: &str, sender_name: &str, name_style: Style| -> Line {
+        let style_name_in_line = |line: &str, sender_name: &str, name_style: Style| -> Line<'static> {
             if sender_name.is_empty() {
                 return Line::from(line.to_string());
             }
@@ -711,9 +932,28 @@ impl App {
             }
         };
 
-        let message_lines: Vec<Line> = display_lines
+        // Screen lines of a body, the first after `head` (its header, styled by the caller)
+        let body_lines = |body: &crate::formatting::Body, head: Line<'static>| -> Vec<Line<'static>> {
+            let mut head = Some(head);
+            body.lines
+                .iter()
+                .map(|pieces| {
+                    let mut line = head.take().unwrap_or_default();
+                    line.spans.extend(
+                        pieces
+                            .iter()
+                            .map(|(text, style)| ratatui::text::Span::styled(text.clone(), self.text_style(*style))),
+                    );
+                    line
+                })
+                .collect()
+        };
+
+        let rendered: Vec<Vec<Line>> = formatted
+            .lines
             .iter()
-            .flat_map(|msg| {
+            .zip(&formatted.bodies)
+            .map(|(msg, body)| {
                 if msg.is_empty() {
                     return vec![Line::from("")];
                 }
@@ -725,32 +965,59 @@ impl App {
                         .map(|line| {
                             Line::from(line).style(
                                 Style::default()
-                                    .fg(Color::Red)
+                                    .fg(self.theme.alert)
                                     .add_modifier(Modifier::ITALIC),
                             )
                         })
                         .collect();
                 }
 
-                if msg.starts_with("  ↳ Reply to") {
+                if msg == crate::formatting::UNREAD_DIVIDER {
+                    return vec![Line::from(msg.clone())
+                        .style(Style::default().fg(self.theme.highlight).add_modifier(Modifier::BOLD))
+                        .centered()];
+                }
+
+                // Day separator ("───── March 4 ─────")
+                if msg.starts_with('─') {
+                    return vec![Line::from(msg.clone())
+                        .style(Style::default().fg(self.theme.dim))
+                        .centered()];
+                }
+
+                if msg.starts_with("  ↳ ") {
                     return wrap_plain_text(msg, message_width)
                         .into_iter()
                         .map(|line| {
                             Line::from(line).style(
                                 Style::default()
-                                    .fg(Color::DarkGray)
+                                    .fg(self.theme.dim)
                                     .add_modifier(Modifier::ITALIC),
                             )
                         })
                         .collect();
                 }
 
+                let Some(body) = body else {
+                    return wrap_plain_text(msg, message_width)
+                        .into_iter()
+                        .map(Line::from)
+                        .collect();
+                };
+
+                // Blockquote line ("  ▎ quoted"), the bar repeated on every wrapped line
+                if msg.starts_with("  ▎ ") {
+                    return body_lines(body, Line::from(body.header.clone()))
+                        .into_iter()
+                        .map(|line| line.style(Style::default().fg(self.theme.dim)))
+                        .collect();
+                }
+
                 if msg.contains("[OUT]:") || msg.contains("[IN]:") {
                     let is_outgoing = msg.contains("[OUT]:");
                     let marker = if is_outgoing { "[OUT]:" } else { "[IN]:" };
                     let marker_len = marker.len();
                     if let Some(marker_pos) = msg.find(marker) {
-                        let prefix = &msg[..marker_pos];
                         let after_marker = &msg[marker_pos + marker_len..];
 
                         if let Some(first_colon) = after_marker.find(':') {
@@ -758,86 +1025,128 @@ impl App {
                             let after_id = &after_marker[first_colon + 1..];
                             if let Some(second_colon) = after_id.find(':') {
                                 let sender_name = &after_id[..second_colon];
-                                let message_text = &after_id[second_colon + 1..];
 
                                 if let Ok(sender_id) = sender_id_str.parse::<i64>() {
                                     let base_color = if is_outgoing {
-                                        Color::Green
+                                        self.theme.outgoing
                                     } else {
-                                        Color::Cyan
+                                        self.theme.incoming
                                     };
-                                    let color = if is_group_chat {
+                                    let color = if sender_id == ANONYMOUS_SENDER_ID && !is_outgoing {
+                                        Color::Gray
+                                    } else if is_group_chat {
                                         self.user_colors.get(&sender_id).copied().unwrap_or(base_color)
                                     } else {
                                         base_color
                                     };
-                                    let lines = wrap_message_with_indent(
-                                        prefix,
-                                        sender_name,
-                                        message_text,
-                                        message_width,
-                                    );
-                                    if self.show_user_colors {
-                                        return lines
-                                            .into_iter()
-                                            .enumerate()
-                                            .map(|(idx, line)| {
-                                                if idx == 0 {
-                                                    style_name_in_line(
-                                                        &line,
-                                                        sender_name,
-                                                        Style::default().fg(color),
-                                                    )
-                                                } else {
-                                                    Line::from(line)
-                                                }
-                                            })
-                                            .collect();
-                                    }
-                                    return lines.into_iter().map(Line::from).collect();
+                                    let head = if self.show_user_colors {
+                                        style_name_in_line(&body.header, sender_name, Style::default().fg(color))
+                                    } else {
+                                        Line::from(body.header.clone())
+                                    };
+                                    let right_align = is_outgoing
+                                        && self.message_alignment == MessageAlignment::Align;
+                                    return body_lines(body, head)
+                                        .into_iter()
+                                        .map(|line| if right_align { line.right_aligned() } else { line })
+                                        .collect();
                                 }
                             }
                         }
                     }
                 }
 
-                wrap_plain_text(msg, message_width)
-                    .into_iter()
-                    .map(Line::from)
-                    .collect()
+                body_lines(body, Line::from(body.header.clone()))
             })
             .collect();
 
+        // Display lines wrap into several screen lines; remember where each message lands for /goto
+        let mut line_starts = Vec::with_capacity(rendered.len());
+        let mut next_line = 0;
+        for lines in &rendered {
+            line_starts.push(next_line);
+            next_line += lines.len();
+        }
+        *pane.message_offsets.borrow_mut() = formatted
+            .message_starts
+            .iter()
+            .map(|start| start.and_then(|i| line_starts.get(i).copied()))
+            .collect();
+        pane.unread_divider_offset
+            .set(formatted.unread_divider.and_then(|i| line_starts.get(i).copied()));
+        let mut message_lines: Vec<Line> = rendered.into_iter().flatten().collect();
+
+        // Command output lives below the history, dimmed, behind its own divider
+        if !pane.messages.is_empty() {
+            if !message_lines.is_empty() {
+                message_lines.push(Line::from(""));
+                message_lines.push(
+                    Line::from("── output ──")
+                        .style(Style::default().fg(self.theme.dim))
+                        .centered(),
+                );
+            }
+            for status in &pane.messages {
+                message_lines.extend(wrap_plain_text(&status.text, message_width).into_iter().map(
+                    |line| Line::from(line).style(Style::default().fg(self.theme.dim)),
+                ));
+            }
+        }
+
         let border_lines = if self.show_borders { 2 } else { 1 }; // 1 for spacing above input in borderless
         let available_height = chunks[1].height.saturating_sub(border_lines) as usize;
         let total_lines = message_lines.len();
         
-        let actual_scroll = if pane.scroll_offset == 0 && total_lines > available_height {
-            total_lines.saturating_sub(available_height)
-        } else {
-            pane.scroll_offset
-        };
+        let max_scroll = total_lines.saturating_sub(available_height);
+        let actual_scroll = max_scroll - pane.scroll_offset.min(max_scroll);
+        pane.scroll_view.set((max_scroll, available_height));
 
         let messages_block = if self.show_borders {
             Block::default().borders(Borders::ALL).title("Messages")
         } else {
             Block::default().padding(Padding::left(2))
         };
-        let messages = Paragraph::new(message_lines)
+        let mut messages = Paragraph::new(message_lines)
             .block(messages_block)
             .scroll((actual_scroll as u16, 0));
+        // Focus mode: dim panes that have nothing new
+        if self.focus_mode && !is_focused && pane.unread_count == 0 {
+            messages = messages.style(Style::default().fg(self.theme.dim));
+        }
         f.render_widget(messages, chunks[1]);
 
+        // Scroll position indicator, only when the history doesn't fit
+        if max_scroll > 0 {
+            let mut scrollbar_state = ScrollbarState::new(max_scroll)
+                .position(actual_scroll)
+                .viewport_content_length(available_height);
+            let scrollbar = Scrollbar::new(ScrollbarOrientation::VerticalRight)
+                .begin_symbol(Some("▲"))
+                .end_symbol(Some("▼"));
+            let scrollbar_area = if self.show_borders {
+                chunks[1].inner(Margin { vertical: 1, horizontal: 0 })
+            } else {
+                chunks[1]
+            };
+            f.render_stateful_widget(scrollbar, scrollbar_area, &mut scrollbar_state);
+        }
+
         if has_reply_preview {
             if let Some(ref preview) = pane.reply_preview {
                 let reply_bar = Paragraph::new(preview.as_str())
-                    .style(Style::default().fg(Color::Magenta).add_modifier(Modifier::ITALIC));
+                    .style(Style::default().fg(self.theme.reply_preview).add_modifier(Modifier::ITALIC));
                 f.render_widget(reply_bar, chunks[2]);
             }
         }
 
         let input_chunk = if has_reply_preview { chunks[3] } else { chunks[2] };
-        let input_title = if is_focused && !self.focus_on_chat_list {
+        let input_title = if read_only {
+            "Input (read-only channel)"
+        } else if pane.editing_message.is_some() {
+            "Input (editing last message, Enter to save, Esc to cancel)"
+        } else if pane.chat_id.is_some_and(|id| self.is_saved_messages(id)) {
+            "Input (Saved Messages - only you will see this)"
+        } else if is_focused && !self.focus_on_chat_list {
             "Input (Alt+Enter for newline, Tab to cycle)"
         } else {
             "Input"
@@ -855,17 +1164,125 @@ impl App {
         } else {
             Block::default()
         };
+        let input_scroll = if is_focused {
+            let cursor = pane.input_cursor.min(pane.input_buffer.len());
+            let cursor_row = crate::formatting::input_line_count(&pane.input_buffer[..cursor], inner_width);
+            cursor_row.saturating_sub(text_lines)
+        } else {
+            0
+        };
         let input = Paragraph::new(input_text)
             .block(input_block)
-            .wrap(Wrap { trim: false });
+            .wrap(Wrap { trim: false })
+            .scroll((input_scroll, 0));
         f.render_widget(input, input_chunk);
     }
 
+    /// Terminal style for a styled piece of message text
+    fn text_style(&self, text: crate::formatting::TextStyle) -> Style {
+        let mut style = Style::default();
+        if text.bold {
+            style = style.add_modifier(Modifier::BOLD);
+        }
+        if text.italic {
+            style = style.add_modifier(Modifier::ITALIC);
+        }
+        if text.code {
+            style = style.fg(self.theme.code);
+        }
+        // Search results and /next, /prev keep the piece's own style under the highlight
+        if text.matched {
+            style = style.add_modifier(Modifier::REVERSED);
+        }
+        style
+    }
+
+    /// Record a new message in a chat and move it up the list, keeping the
+    /// highlighted chat selected
+    fn bump_chat_activity(&mut self, chat_id: i64, timestamp: i64) {
+        let normalized_id = crate::utils::normalize_chat_id(chat_id);
+        let Some(chat_info) = self
+            .chats
+            .iter_mut()
+            .find(|c| c.id == chat_id || c.id == normalized_id)
+        else {
+            return;
+        };
+        if timestamp <= chat_info.last_message_date {
+            return;
+        }
+        chat_info.last_message_date = timestamp;
+
+        let selected_id = self
+            .chat_list_order()
+            .get(self.selected_chat_idx)
+            .map(|&idx| self.chats[idx].id);
+        crate::telegram::sort_by_activity(&mut self.chats);
+        if let Some(selected_id) = selected_id {
+            let order = self.chat_list_order();
+            if let Some(pos) = order.iter().position(|&idx| self.chats[idx].id == selected_id) {
+                self.selected_chat_idx = pos;
+            }
+        }
+    }
+
     pub async fn refresh_chats(&mut self) -> Result<()> {
         self.chats = self.telegram.get_dialogs().await?;
         Ok(())
     }
 
+    /// Reconnect as another profile (/profile). Open panes belong to the old
+    /// account, so they are saved as drafts and replaced by one empty pane.
+    pub async fn switch_profile(&mut self, name: &str) -> Result<()> {
+        // Queued messages belong to this account's connection
+        if !self.offline_queue.is_empty() {
+            anyhow::bail!(
+                "{} message(s) still queued offline; switch once they are sent",
+                self.offline_queue.len()
+            );
+        }
+        let mut config = self.config.clone();
+        config.select_profile(name)?;
+        let telegram = TelegramClient::resume(&config).await?;
+        let (my_user_id, my_name) = telegram.get_me().await?;
+        let chats = telegram.get_dialogs().await?;
+        // Each profile keeps its own drafts, aliases, pins and history
+        let state = AppState::load(&config)?;
+
+        self.save_state()?;
+
+        self.telegram.stop_updates().await;
+        self.telegram = telegram;
+        self.config.profile = config.profile;
+        self.my_user_id = my_user_id;
+        self.my_name = my_name;
+        self.chats = chats;
+        self.aliases = state.aliases;
+        self.chat_aliases = state.chat_aliases;
+        self.drafts = state.drafts;
+        self.scroll_positions = state.scroll_positions;
+        self.pinned_chats = state.pinned_chats;
+        self.muted_chats = state.muted_chats;
+        self.input_history = state.input_history;
+        self.history_idx = None;
+        self.offline_queue = state.offline_queue.sends;
+        self.selected_chat_idx = 0;
+        self.panes = vec![ChatPane::new()];
+        self.pane_tree = PaneNode::new_single(0);
+        self.focused_pane_idx = 0;
+        self.focus_on_chat_list = true;
+        self.pane_areas.clear();
+        self.undo_stack.clear();
+        self.reply_cache.clear();
+        self.reply_misses.clear();
+        self.user_colors.clear();
+        self.away_replied.clear();
+        self.available_reactions.clear();
+        self.reaction_picker = None;
+        self.shown_connection = ConnectionState::Connecting;
+        Ok(())
+    }
+
     /// Show a status notification that auto-expires
     pub fn notify(&mut self, message: &str) {
         self.status_message = Some(message.to_string());
@@ -880,30 +1297,49 @@ impl App {
             Some(std::time::Instant::now() + std::time::Duration::from_secs(duration_secs));
     }
 
+    /// Keep the pane's unsent input as its current chat's draft and bring back the
+    /// draft saved for `chat_id`. Call before the pane switches to `chat_id`.
+    fn switch_draft(&mut self, pane_idx: usize, chat_id: i64) {
+        let Some(pane) = self.panes.get_mut(pane_idx) else { return };
+        if pane.chat_id == Some(chat_id) {
+            return;
+        }
+        if let Some(old_chat_id) = pane.chat_id {
+            self.drafts.stash(old_chat_id, std::mem::take(&mut pane.input_buffer));
+        }
+        if pane.input_buffer.is_empty() {
+            pane.input_buffer = self.drafts.take(chat_id).unwrap_or_default();
+        }
+        pane.input_cursor = pane.input_buffer.len();
+    }
+
+    /// Remember the pane's scroll offset for its current chat and return the offset
+    /// saved for `chat_id`. Call before the pane switches to `chat_id`.
+    fn switch_scroll(&mut self, pane_idx: usize, chat_id: i64) -> usize {
+        let Some(pane) = self.panes.get(pane_idx) else { return 0 };
+        if pane.chat_id == Some(chat_id) {
+            return pane.scroll_offset;
+        }
+        if let Some(old_chat_id) = pane.chat_id {
+            self.scroll_positions.remember(old_chat_id, pane.scroll_offset);
+        }
+        // The saved offset may exceed the freshly loaded history; pull it back
+        // inside the range the next draw measures
+        self.resize_pending = true;
+        self.scroll_positions.take(chat_id)
+    }
+
     pub async fn open_chat_in_pane(&mut self, pane_idx: usize, chat_id: i64, chat_name: &str) {
-        let msg_data = match self.telegram.get_messages(chat_id, 50).await {
+        let msg_data = match self.telegram.get_messages(chat_id, self.message_fetch_limit).await {
             Ok(raw_messages) => raw_messages
-                .iter()
-                .map(|(msg_id, sender_id, sender_name, text, reply_to_id, media_type, reactions)| {
-                    crate::widgets::MessageData {
-                        msg_id: *msg_id,
-                        sender_id: *sender_id,
-                        sender_name: sender_name.clone(),
-                        text: text.clone(),
-                        is_outgoing: *sender_id == self.my_user_id,
-                        timestamp: chrono::Utc::now().timestamp(),
-                        media_type: media_type.clone(),
-                        media_label: None,
-                        reactions: reactions.clone(),
-                        reply_to_msg_id: *reply_to_id,
-                        reply_sender: None,
-                        reply_text: None,
-                    }
-                })
+                .into_iter()
+                .map(|raw| crate::widgets::MessageData::from_raw(raw, self.my_user_id))
                 .collect(),
             Err(_) => Vec::new(),
         };
 
+        let scroll_offset = self.switch_scroll(pane_idx, chat_id);
+        self.switch_draft(pane_idx, chat_id);
         if let Some(pane) = self.panes.get_mut(pane_idx) {
             pane.chat_id = Some(chat_id);
             pane.chat_name = chat_name.to_string();
@@ -911,21 +1347,120 @@ impl App {
             pane.messages.clear();
             pane.reply_to_message = None;
             pane.hide_reply_preview();
-            pane.scroll_offset = 0;
+            pane.cancel_edit();
+            pane.search_query = None;
+            pane.clear_search_matches();
+            pane.expanded_messages.clear();
+            pane.peeking = false;
+            pane.history_exhausted = false;
+            pane.scroll_offset = scroll_offset;
             pane.format_cache.clear();
 
             // Set username from chats list if available
-            if let Some(chat_info) = self.chats.iter().find(|c| c.id == chat_id) {
+            let chat_info = self.chats.iter().find(|c| c.id == chat_id);
+            if let Some(chat_info) = chat_info {
                 pane.username = chat_info.username.clone();
             }
+            pane.set_unread_divider(chat_info.map_or(0, |c| c.unread));
         }
 
+        self.resolve_reply_previews(pane_idx).await;
+
         // Mark chat as read
         if let Some(chat_info) = self.chats.iter_mut().find(|c| c.id == chat_id) {
             chat_info.unread = 0;
         }
     }
 
+    /// Open a chat for reading without clearing its unread badge (/peek, Alt+click)
+    pub async fn peek_chat_in_pane(&mut self, pane_idx: usize, chat_id: i64, chat_name: &str) {
+        let unread = self.chats.iter().find(|c| c.id == chat_id).map_or(0, |c| c.unread);
+        self.open_chat_in_pane(pane_idx, chat_id, chat_name).await;
+
+        if let Some(chat_info) = self.chats.iter_mut().find(|c| c.id == chat_id) {
+            chat_info.unread = unread;
+        }
+        if let Some(pane) = self.panes.get_mut(pane_idx) {
+            pane.peeking = true;
+        }
+    }
+
+    /// Leave search results and reload the pane's live chat (/search off, Esc).
+    /// Returns false when the pane had no active search.
+    pub async fn clear_search(&mut self, pane_idx: usize) -> bool {
+        let Some(pane) = self.panes.get(pane_idx).filter(|p| p.search_active()) else {
+            return false;
+        };
+        let Some(chat_id) = pane.chat_id else {
+            return false;
+        };
+        let chat_name = self
+            .chats
+            .iter()
+            .find(|c| c.id == chat_id)
+            .map(|c| c.name.clone())
+            .unwrap_or_else(|| pane.base_chat_name().to_string());
+
+        self.open_chat_in_pane(pane_idx, chat_id, &chat_name).await;
+        true
+    }
+
+    /// Step to the next older (or newer) /search match and show it in the full
+    /// chat, loading older history until it's there (/next, /prev)
+    pub async fn step_search_match(&mut self, pane_idx: usize, older: bool) {
+        let Some(pane) = self.panes.get(pane_idx) else { return };
+        let count = pane.search_matches.len();
+        if count == 0 {
+            self.notify("No search matches; use /search first");
+            return;
+        }
+        let idx = match (pane.search_match_idx, older) {
+            // Matches are newest first: /next starts at the newest, /prev at the oldest
+            (None, true) => 0,
+            (None, false) => count - 1,
+            (Some(i), true) => (i + 1) % count,
+            (Some(i), false) => (i + count - 1) % count,
+        };
+        let msg_id = pane.search_matches[idx];
+
+        // Leave the results list for the live chat; reopening it resets the search state
+        if pane.search_active() {
+            let (term, matches) = (pane.search_term.clone(), pane.search_matches.clone());
+            self.clear_search(pane_idx).await;
+            if let Some(pane) = self.panes.get_mut(pane_idx) {
+                pane.search_term = term;
+                pane.search_matches = matches;
+            }
+        }
+        for _ in 0..SEARCH_CONTEXT_PAGES {
+            match self.panes.get(pane_idx) {
+                Some(pane) if !pane.msg_data.iter().any(|m| m.msg_id == msg_id) && !pane.history_exhausted => {
+                    self.load_older_messages(pane_idx).await;
+                }
+                _ => break,
+            }
+        }
+
+        let Some(pane) = self.panes.get_mut(pane_idx) else { return };
+        pane.search_match_idx = Some(idx);
+        if pane.msg_data.iter().any(|m| m.msg_id == msg_id) {
+            pane.pending_jump = Some(msg_id);
+            self.notify(&format!("Match {}/{}", idx + 1, count));
+        } else {
+            self.notify(&format!("Match {}/{} is too far back to load", idx + 1, count));
+        }
+    }
+
+    /// Scroll panes to messages picked before they were laid out (see `step_search_match`).
+    /// Call after drawing; true if a pane moved and needs another draw.
+    pub fn apply_pending_jumps(&mut self) -> bool {
+        let mut moved = false;
+        for pane in &mut self.panes {
+            moved |= pane.apply_pending_jump();
+        }
+        moved
+    }
+
     pub async fn load_pane_messages_if_needed(&mut self, pane_idx: usize) {
         if let Some(pane) = self.panes.get(pane_idx) {
             if let Some(_chat_id) = pane.chat_id {
@@ -941,6 +1476,9 @@ impl App {
     // =========================================================================
 
     pub fn split_vertical(&mut self) {
+        if !self.can_split() {
+            return;
+        }
         let new_pane = ChatPane::new();
         let new_idx = self.panes.len();
         self.panes.push(new_pane);
@@ -951,6 +1489,9 @@ impl App {
     }
 
     pub fn split_horizontal(&mut self) {
+        if !self.can_split() {
+            return;
+        }
         let new_pane = ChatPane::new();
         let new_idx = self.panes.len();
         self.panes.push(new_pane);
@@ -960,6 +1501,15 @@ impl App {
         self.focus_on_chat_list = false;
     }
 
+    /// Refuse splits once the tree holds `max_panes` panes
+    fn can_split(&mut self) -> bool {
+        if self.pane_tree.count_panes() >= self.max_panes {
+            self.notify(&format!("Maximum of {} panes reached (max_panes setting)", self.max_panes));
+            return false;
+        }
+        true
+    }
+
     fn split_pane_in_tree(
         &mut self,
         target_idx: usize,
@@ -992,6 +1542,46 @@ impl App {
         }
     }
 
+    /// Exchange what two panes show; each keeps its place in the layout.
+    /// Focus and undo entries follow their pane's contents.
+    pub fn swap_panes(&mut self, a: usize, b: usize) -> bool {
+        if !swap_panes(&mut self.panes, a, b) {
+            return false;
+        }
+        self.focused_pane_idx = swapped_index(self.focused_pane_idx, a, b);
+        for action in &mut self.undo_stack {
+            if let UndoAction::FilterChanged { pane_idx, .. } | UndoAction::PaneCleared { pane_idx, .. } =
+                action
+            {
+                *pane_idx = swapped_index(*pane_idx, a, b);
+            }
+        }
+        true
+    }
+
+    /// Alt+S: swap the focused pane with the next one; focus follows the moved chat
+    pub fn swap_with_next_pane(&mut self) {
+        let indices = self.pane_tree.get_pane_indices();
+        let Some(pos) = indices.iter().position(|&idx| idx == self.focused_pane_idx) else { return };
+        if indices.len() < 2 {
+            self.notify("No other pane to swap with");
+            return;
+        }
+        let next = indices[(pos + 1) % indices.len()];
+        if self.swap_panes(self.focused_pane_idx, next) {
+            self.focus_on_chat_list = false;
+        }
+    }
+
+    /// Alt+> / Alt+<: grow or shrink the focused pane against its neighbour
+    pub fn resize_focused_pane(&mut self, grow: bool) {
+        match self.pane_tree.resize_pane(self.focused_pane_idx, grow) {
+            Some(true) => {}
+            Some(false) => self.notify(if grow { "Pane can't grow further" } else { "Pane can't shrink further" }),
+            None => self.notify("No split to resize - pane is not in a split"),
+        }
+    }
+
     pub fn toggle_split_direction(&mut self) {
         // Find the parent split node that directly contains the focused pane
         if Self::toggle_split_direction_recursive(&mut self.pane_tree, self.focused_pane_idx) {
@@ -1003,7 +1593,7 @@ impl App {
     fn toggle_split_direction_recursive(node: &mut PaneNode, target_idx: usize) -> bool {
         match node {
             PaneNode::Single(_) => false,
-            PaneNode::Split { direction, children } => {
+            PaneNode::Split { direction, children, .. } => {
                 // Check if target_idx is directly a child of this split (not nested deeper)
                 let is_direct_child = children.iter().any(|child| {
                     matches!(child.as_ref(), PaneNode::Single(idx) if *idx == target_idx)
@@ -1044,25 +1634,121 @@ impl App {
             if !remaining.is_empty() {
                 self.focused_pane_idx = remaining[0];
             }
+            self.compact_panes();
         } else {
             self.notify("Failed to close pane");
         }
     }
 
-    pub fn clear_pane(&mut self) {
-        if let Some(pane) = self.panes.get_mut(self.focused_pane_idx) {
-            pane.clear();
-        }
+    /// Renumber panes to a contiguous range after a close, dropping the
+    /// closed panes' state and fixing up indices held elsewhere.
+    fn compact_panes(&mut self) {
+        let remap = crate::split_view::compact_panes(&mut self.pane_tree, &mut self.panes);
+        self.focused_pane_idx = remap.get(&self.focused_pane_idx).copied().unwrap_or(0);
+        self.pane_areas.clear();
+
+        self.undo_stack.retain_mut(|action| match action {
+            UndoAction::FilterChanged { pane_idx, .. } | UndoAction::PaneCleared { pane_idx, .. } => {
+                match remap.get(pane_idx) {
+                    Some(&new_idx) => {
+                        *pane_idx = new_idx;
+                        true
+                    }
+                    None => false,
+                }
+            }
+            _ => true,
+        });
     }
 
-    pub fn cycle_focus(&mut self) {
-        let all_panes = self.pane_tree.get_pane_indices();
-        
-        if all_panes.is_empty() {
-            return;
-        }
+    /// True when the connection state differs from what the status bar shows
+    pub fn connection_changed(&self) -> bool {
+        self.telegram.connection_state() != self.shown_connection
+    }
 
-        if self.focus_on_chat_list {
+    /// Put text on the system clipboard. Without one (headless, SSH) it is written to
+    /// a temp file instead, whose path is returned.
+    pub fn copy_to_clipboard(&mut self, text: &str) -> Result<Option<std::path::PathBuf>> {
+        if self.clipboard.is_none() {
+            self.clipboard = arboard::Clipboard::new().ok();
+        }
+        if let Some(clipboard) = self.clipboard.as_mut()
+            && clipboard.set_text(text).is_ok()
+        {
+            return Ok(None);
+        }
+        let path = std::env::temp_dir().join("telegram_copy.txt");
+        std::fs::write(&path, text)?;
+        Ok(Some(path))
+    }
+
+    /// Expire old command output in all panes. Returns true if a redraw is needed.
+    pub fn expire_status_lines(&mut self) -> bool {
+        // Every pane expires its lines, so no short-circuiting
+        let mut expired = false;
+        for pane in &mut self.panes {
+            expired |= pane.expire_status_lines();
+        }
+        expired
+    }
+
+    /// Away mode: answer a DM once per chat per session with the away message
+    fn maybe_send_away_reply(&mut self, chat_id: i64) {
+        let Some(away_message) = self.away_message.clone() else {
+            return;
+        };
+        let is_dm = self
+            .chats
+            .iter()
+            .find(|c| c.id == chat_id)
+            .is_some_and(|c| c.kind == ChatKind::User);
+        // insert() is false when we already replied, which also prevents reply loops
+        if !is_dm || self.is_saved_messages(chat_id) || !self.away_replied.insert(chat_id) {
+            return;
+        }
+
+        let telegram = self.telegram.clone();
+        tokio::spawn(async move {
+            if let Err(e) = telegram.send_message(chat_id, &away_message).await {
+                crate::log_info!("away reply to {} failed: {}", chat_id, e);
+            }
+        });
+    }
+
+    /// Whether any pane has a send/upload still in flight (keeps the indicator fresh)
+    pub fn has_pending_sends(&self) -> bool {
+        self.panes.iter().any(|p| !p.pending_sends.is_empty())
+    }
+
+    pub fn clear_pane(&mut self) {
+        let pane_idx = self.focused_pane_idx;
+        if let Some(pane) = self.panes.get_mut(pane_idx) {
+            let action = UndoAction::PaneCleared {
+                pane_idx,
+                chat_id: pane.chat_id,
+                msg_data: pane.msg_data.clone(),
+                messages: pane.messages.clone(),
+            };
+            pane.clear();
+            self.push_undo(action);
+        }
+    }
+
+    pub fn push_undo(&mut self, action: UndoAction) {
+        self.undo_stack.push(action);
+        if self.undo_stack.len() > MAX_UNDO {
+            self.undo_stack.remove(0);
+        }
+    }
+
+    pub fn cycle_focus(&mut self) {
+        let all_panes = self.pane_tree.get_pane_indices();
+        
+        if all_panes.is_empty() {
+            return;
+        }
+
+        if self.focus_on_chat_list {
             // Going from chat list to first pane
             self.focus_on_chat_list = false;
             self.focused_pane_idx = all_panes[0];
@@ -1122,6 +1808,15 @@ impl App {
         }
     }
 
+    /// Alt+h/j/k/l: focus the pane next to the focused one in `dir`, if there is one
+    pub fn focus_direction(&mut self, dir: FocusDirection) {
+        if let Some(pane_idx) = neighbor_in_direction(&self.pane_areas, self.focused_pane_idx, dir) {
+            self.focused_pane_idx = pane_idx;
+            self.focus_on_chat_list = false;
+            self.mark_pane_chat_read(self.focused_pane_idx);
+        }
+    }
+
     pub fn focus_prev_pane(&mut self) {
         let all_panes = self.pane_tree.get_pane_indices();
         if all_panes.len() < 2 {
@@ -1163,6 +1858,13 @@ impl App {
         self.refresh_all_pane_displays();
     }
 
+    pub fn toggle_grouping(&mut self) {
+        self.group_messages = !self.group_messages;
+        let status = if self.group_messages { "ON" } else { "OFF" };
+        self.notify(&format!("Group messages: {}", status));
+        self.refresh_all_pane_displays();
+    }
+
     pub fn toggle_emojis(&mut self) {
         self.show_emojis = !self.show_emojis;
         let status = if self.show_emojis { "ON" } else { "OFF" };
@@ -1201,7 +1903,15 @@ impl App {
         self.notify(&format!("Borders: {}", if self.show_borders { "ON" } else { "OFF" }));
     }
 
-    fn chat_list_groups(&self) -> (Vec<usize>, Vec<usize>, Vec<usize>) {
+    pub fn toggle_focus_mode(&mut self) {
+        self.focus_mode = !self.focus_mode;
+        self.notify(&format!("Focus mode: {}", if self.focus_mode { "ON" } else { "OFF" }));
+        // Chat list shrinks/grows, keep the selection in range
+        let max_idx = self.chat_list_order().len().saturating_sub(1);
+        self.selected_chat_idx = self.selected_chat_idx.min(max_idx);
+    }
+
+    fn chat_list_groups(&self) -> (Vec<usize>, Vec<usize>, Vec<usize>, Vec<usize>) {
         let mut open_chat_ids = std::collections::HashSet::new();
         for pane in &self.panes {
             if let Some(chat_id) = pane.chat_id {
@@ -1209,12 +1919,22 @@ impl App {
             }
         }
 
+        let mut pinned = Vec::new();
         let mut unread = Vec::new();
         let mut active = Vec::new();
         let mut other = Vec::new();
 
         for (idx, chat) in self.chats.iter().enumerate() {
-            if open_chat_ids.contains(&chat.id) {
+            // Focus mode: only surface chats with unread messages
+            if self.focus_mode && chat.unread == 0 {
+                continue;
+            }
+            if !self.chat_list_filter.is_empty() && !self.chat_matches_filter(chat) {
+                continue;
+            }
+            if self.pinned_chats.contains(chat.id) {
+                pinned.push(idx);
+            } else if open_chat_ids.contains(&chat.id) {
                 active.push(idx);
             } else if chat.unread > 0 {
                 unread.push(idx);
@@ -1223,20 +1943,110 @@ impl App {
             }
         }
 
-        (unread, active, other)
+        (pinned, unread, active, other)
+    }
+
+    fn chat_matches_filter(&self, chat: &ChatInfo) -> bool {
+        fuzzy_match(&self.chat_list_filter, self.chat_display_name(chat.id, &chat.name))
+            || chat.username.as_deref().is_some_and(|u| fuzzy_match(&self.chat_list_filter, u))
+    }
+
+    /// Typing on the chat list narrows it. A leading "/" just opens the filter box.
+    pub fn handle_chat_list_char(&mut self, c: char) {
+        if c == '/' && self.chat_list_filter.is_empty() {
+            self.chat_list_filter_open = true;
+            return;
+        }
+        self.chat_list_filter.push(c);
+        self.selected_chat_idx = 0;
+    }
+
+    /// Backspace on an empty filter closes the box
+    pub fn handle_chat_list_backspace(&mut self) {
+        if self.chat_list_filter.pop().is_none() {
+            self.chat_list_filter_open = false;
+        }
+        self.selected_chat_idx = 0;
+    }
+
+    /// Esc on the chat list drops the filter. Returns false if there was none.
+    pub fn clear_chat_list_filter(&mut self) -> bool {
+        if self.chat_list_filter.is_empty() && !self.chat_list_filter_open {
+            return false;
+        }
+        self.chat_list_filter.clear();
+        self.chat_list_filter_open = false;
+        self.selected_chat_idx = 0;
+        true
+    }
+
+    /// Enter on a filter like "@name" that matches no dialog: resolve the
+    /// username and open it in the focused pane, as /new does
+    async fn open_username_from_filter(&mut self) {
+        let username = self.chat_list_filter.clone();
+        self.notify(&format!("Looking up {}...", username));
+        match self.telegram.resolve_username(&username).await {
+            Ok(Some((chat_id, chat_name, _is_group))) => {
+                self.open_chat_in_pane(self.focused_pane_idx, chat_id, &chat_name).await;
+                self.clear_chat_list_filter();
+                self.focus_on_chat_list = false;
+            }
+            Ok(None) => self.notify(&format!("User '{}' not found", username)),
+            Err(e) => self.notify(&format!("Lookup failed: {}", e)),
+        }
     }
 
     fn chat_list_order(&self) -> Vec<usize> {
-        let (unread, active, other) = self.chat_list_groups();
+        let (pinned, unread, active, other) = self.chat_list_groups();
         let mut ordered = Vec::with_capacity(self.chats.len());
+        ordered.extend(pinned);
         ordered.extend(unread);
         ordered.extend(active);
         ordered.extend(other);
         ordered
     }
 
+    /// Local /rename override for a chat, falling back to its real name.
+    /// The self chat shows your own name, so it is labeled "Saved Messages" instead.
+    pub fn chat_display_name<'a>(&'a self, chat_id: i64, name: &'a str) -> &'a str {
+        match self.chat_aliases.get(chat_id) {
+            Some(alias) => alias,
+            None if self.is_saved_messages(chat_id) => "🔖 Saved Messages",
+            None => name,
+        }
+    }
+
+    /// Whether the chat is the self chat (Saved Messages), where every message is from me
+    pub fn is_saved_messages(&self, chat_id: i64) -> bool {
+        chat_id == self.my_user_id
+    }
+
+    /// Whether we are allowed to post in the given chat (unknown chats are assumed writable)
+    pub fn can_post_in(&self, chat_id: i64) -> bool {
+        self.chats
+            .iter()
+            .find(|c| c.id == chat_id)
+            .is_none_or(|c| c.can_post)
+    }
+
+    /// Mirror a chat opened in a pane as read on Telegram, up to its newest message.
+    /// Runs in the background; a failure only means the badge lingers elsewhere.
+    fn send_read_receipt(&self, pane_idx: usize) {
+        let Some(pane) = self.panes.get(pane_idx).filter(|p| !p.peeking) else {
+            return;
+        };
+        let newest = pane.msg_data.iter().map(|m| m.msg_id).max().filter(|&id| id > 0);
+        let (Some(chat_id), Some(max_id)) = (pane.chat_id, newest) else {
+            return;
+        };
+        let telegram = self.telegram.clone();
+        tokio::spawn(async move {
+            let _ = telegram.mark_read(chat_id, max_id).await;
+        });
+    }
+
     fn mark_pane_chat_read(&mut self, pane_idx: usize) {
-        let chat_id = match self.panes.get(pane_idx).and_then(|p| p.chat_id) {
+        let chat_id = match self.panes.get(pane_idx).filter(|p| !p.peeking).and_then(|p| p.chat_id) {
             Some(chat_id) => chat_id,
             None => return,
         };
@@ -1246,27 +2056,43 @@ impl App {
         }
 
         if let Some(pane) = self.panes.get_mut(pane_idx) {
-            pane.unread_count_at_load = 0;
+            pane.unread_count = 0;
         }
     }
 
+    /// Pane drawn at screen position (x, y), if any
+    fn pane_at(&self, x: u16, y: u16) -> Option<usize> {
+        self.pane_areas
+            .iter()
+            .find(|(_, area)| x >= area.x && x < area.x + area.width && y >= area.y && y < area.y + area.height)
+            .map(|(&pane_idx, _)| pane_idx)
+    }
 
     /// Handle mouse click to select pane or open chat
     pub fn handle_mouse_click(&mut self, x: u16, y: u16) {
-        // Check if clicking on a pane
-        for (&pane_idx, &area) in &self.pane_areas {
-            if x >= area.x && x < area.x + area.width && y >= area.y && y < area.y + area.height {
-                // Clicked on this pane - make it active
-                self.focused_pane_idx = pane_idx;
-                self.focus_on_chat_list = false;
-                self.mark_pane_chat_read(self.focused_pane_idx);
-                return;
-            }
+        // Clicked on a pane - make it active
+        if let Some(pane_idx) = self.pane_at(x, y) {
+            self.focused_pane_idx = pane_idx;
+            self.focus_on_chat_list = false;
+            self.mark_pane_chat_read(self.focused_pane_idx);
+        }
+    }
+
+    /// Mouse wheel: scroll the pane under the cursor without focusing it
+    pub fn handle_mouse_scroll(&mut self, x: u16, y: u16, up: bool) {
+        let Some(pane) = self.pane_at(x, y).and_then(|idx| self.panes.get_mut(idx)) else {
+            return;
+        };
+        if up {
+            pane.scroll_up(MOUSE_SCROLL_LINES);
+        } else {
+            pane.scroll_down(MOUSE_SCROLL_LINES);
         }
     }
 
     /// Handle mouse click on chat list
-    pub async fn handle_chat_list_click(&mut self, y: u16, list_area: Rect) -> Result<()> {
+    /// With `peek` set (Alt+click) the chat is opened without marking it read.
+    pub async fn handle_chat_list_click(&mut self, y: u16, list_area: Rect, peek: bool) -> Result<()> {
         // Calculate which chat was clicked based on Y position
         // Each chat item is 1 line, starting at list_area.y + border_offset (after top border if present)
         let border_offset = if self.show_borders { 1 } else { 0 };
@@ -1276,10 +2102,17 @@ impl App {
         
         let relative_y = (y - list_area.y - border_offset) as usize;
         let ordered_chats = self.chat_list_order();
-        let (unread_group, active_group, other_group) = self.chat_list_groups();
+        let (pinned_group, unread_group, active_group, other_group) = self.chat_list_groups();
 
         let mut row_map: Vec<Option<usize>> = Vec::new();
         let mut ordered_idx = 0usize;
+        if !pinned_group.is_empty() {
+            row_map.push(None);
+            for _ in pinned_group.iter() {
+                row_map.push(Some(ordered_idx));
+                ordered_idx += 1;
+            }
+        }
         if !unread_group.is_empty() {
             row_map.push(None);
             for _ in unread_group.iter() {
@@ -1309,44 +2142,46 @@ impl App {
                 let chat_id = chat.id;
                 let chat_name = chat.name.clone();
                 let chat_username = chat.username.clone();
-                let raw_messages = self.telegram.get_messages(chat_id, 50).await?;
+                if peek {
+                    self.peek_chat_in_pane(self.focused_pane_idx, chat_id, &chat_name).await;
+                    if let Some(list_idx) = row_map[relative_y] {
+                        self.selected_chat_idx = list_idx;
+                    }
+                    return Ok(());
+                }
+                let raw_messages = self.telegram.get_messages(chat_id, self.message_fetch_limit).await?;
 
                 let msg_data: Vec<crate::widgets::MessageData> = raw_messages
-                    .iter()
-                    .map(|(msg_id, sender_id, sender_name, text, reply_to_id, media_type, reactions)| {
-                        let reply_to_msg_id = *reply_to_id;
-                        
-                        crate::widgets::MessageData {
-                            msg_id: *msg_id,
-                            sender_id: *sender_id,
-                            sender_name: sender_name.clone(),
-                            text: text.clone(),
-                            is_outgoing: *sender_id == self.my_user_id,
-                            timestamp: chrono::Utc::now().timestamp(),
-                            media_type: media_type.clone(),
-                            media_label: None,
-                            reactions: reactions.clone(),
-                            reply_to_msg_id,
-                            reply_sender: None,
-                            reply_text: None,
-                        }
-                    })
+                    .into_iter()
+                    .map(|raw| crate::widgets::MessageData::from_raw(raw, self.my_user_id))
                     .collect();
 
+                let scroll_offset = self.switch_scroll(self.focused_pane_idx, chat_id);
+                self.switch_draft(self.focused_pane_idx, chat_id);
                 if let Some(pane) = self.panes.get_mut(self.focused_pane_idx) {
                     pane.chat_id = Some(chat_id);
                     pane.chat_name = chat_name;
                     pane.username = chat_username;
                     pane.msg_data = msg_data;
+                    pane.format_cache.clear();
                     pane.messages.clear(); // Clear status messages when switching chats
                     pane.reply_to_message = None;
                     pane.hide_reply_preview();
-                    pane.scroll_offset = 0;
+                    pane.cancel_edit();
+                    pane.search_query = None;
+                    pane.clear_search_matches();
+                    pane.expanded_messages.clear();
+                    pane.peeking = false;
+                    pane.history_exhausted = false;
+                    pane.scroll_offset = scroll_offset;
 
                     if let Some(chat_info) = self.chats.iter_mut().find(|c| c.id == chat_id) {
+                        pane.set_unread_divider(chat_info.unread);
                         chat_info.unread = 0;
                     }
                 }
+                self.resolve_reply_previews(self.focused_pane_idx).await;
+                self.send_read_receipt(self.focused_pane_idx);
                 if let Some(list_idx) = row_map[relative_y] {
                     self.selected_chat_idx = list_idx;
                 }
@@ -1356,7 +2191,7 @@ impl App {
     }
 
     /// Refresh all pane message displays (after toggling display settings)
-    fn refresh_all_pane_displays(&mut self) {
+    pub fn refresh_all_pane_displays(&mut self) {
         // Clear format caches so they re-render with new settings
         for pane in &mut self.panes {
             pane.format_cache.clear();
@@ -1376,24 +2211,31 @@ impl App {
                 self.selected_chat_idx -= 1;
             }
         } else {
-            // Browse input history
-            if !self.input_history.is_empty() {
-                if let Some(pane) = self.panes.get_mut(self.focused_pane_idx) {
-                    match self.history_idx {
-                        None => {
-                            // Save current input and start browsing
-                            self.history_temp = pane.input_buffer.clone();
-                            self.history_idx = Some(self.input_history.len() - 1);
-                            pane.input_buffer = self.input_history[self.input_history.len() - 1].clone();
-                            pane.input_cursor = pane.input_buffer.len();
-                    }
-                        Some(idx) if idx > 0 => {
-                            self.history_idx = Some(idx - 1);
-                            pane.input_buffer = self.input_history[idx - 1].clone();
-                            pane.input_cursor = pane.input_buffer.len();
-                        }
-                        _ => {}
+            if let Some(pane) = self.panes.get_mut(self.focused_pane_idx) {
+                // Up on an empty input edits my last message; Up again falls through to history
+                if self.history_idx.is_none() && pane.editing_message.is_none()
+                    && pane.input_buffer.is_empty() && pane.start_edit_last()
+                {
+                    return;
+                }
+                if pane.edit_unchanged() {
+                    pane.cancel_edit();
+                }
+            }
+
+            // Browse input history; starting from "/..." only visits earlier commands
+            if let Some(pane) = self.panes.get_mut(self.focused_pane_idx) {
+                if self.history_idx.is_none() {
+                    self.history_commands_only = pane.input_buffer.starts_with('/');
+                }
+                if let Some(idx) = self.input_history.older(self.history_idx, self.history_commands_only) {
+                    if self.history_idx.is_none() {
+                        // Save current input and start browsing
+                        self.history_temp = pane.input_buffer.clone();
                     }
+                    self.history_idx = Some(idx);
+                    pane.input_buffer = self.input_history.get(idx).unwrap_or_default().to_string();
+                    pane.input_cursor = pane.input_buffer.len();
                 }
             }
         }
@@ -1409,9 +2251,9 @@ impl App {
             // Browse input history
             if let Some(pane) = self.panes.get_mut(self.focused_pane_idx) {
                 if let Some(idx) = self.history_idx {
-                    if idx + 1 < self.input_history.len() {
-                        self.history_idx = Some(idx + 1);
-                        pane.input_buffer = self.input_history[idx + 1].clone();
+                    if let Some(next) = self.input_history.newer(idx, self.history_commands_only) {
+                        self.history_idx = Some(next);
+                        pane.input_buffer = self.input_history.get(next).unwrap_or_default().to_string();
                         pane.input_cursor = pane.input_buffer.len();
                     } else {
                         // Back to current input
@@ -1424,22 +2266,286 @@ impl App {
         }
     }
 
-    pub fn handle_page_up(&mut self) {
+    pub async fn handle_page_up(&mut self) {
         if !self.focus_on_chat_list {
             if let Some(pane) = self.panes.get_mut(self.focused_pane_idx) {
-                pane.scroll_up();
+                let page = pane.page_height();
+                pane.scroll_up(page);
+                if pane.at_top() {
+                    self.load_older_messages(self.focused_pane_idx).await;
+                }
             }
         }
     }
 
+    /// Fill in `reply_sender`/`reply_text` for replies whose target isn't loaded in
+    /// the pane, from the cache or with one lookup for all missing targets
+    async fn resolve_reply_previews(&mut self, pane_idx: usize) {
+        let Some(pane) = self.panes.get(pane_idx) else { return };
+        let Some(chat_id) = pane.chat_id else { return };
+        let mut missing: Vec<i32> = pane
+            .msg_data
+            .iter()
+            .filter(|m| m.reply_sender.is_none())
+            .filter_map(|m| m.reply_to_msg_id)
+            .filter(|id| !pane.msg_data.iter().any(|m| m.msg_id == *id))
+            .filter(|id| !self.reply_cache.contains_key(&(chat_id, *id)))
+            .filter(|id| !self.reply_misses.contains(&(chat_id, *id)))
+            .collect();
+        missing.sort_unstable();
+        missing.dedup();
+
+        if !missing.is_empty()
+            && let Ok(found) = self.telegram.get_messages_by_id(chat_id, &missing).await
+        {
+            for (msg_id, sender, text) in found {
+                self.reply_cache.insert((chat_id, msg_id), (sender, text));
+            }
+            for msg_id in missing {
+                if !self.reply_cache.contains_key(&(chat_id, msg_id)) {
+                    self.reply_misses.insert((chat_id, msg_id));
+                }
+            }
+        }
+
+        let Some(pane) = self.panes.get_mut(pane_idx) else { return };
+        let mut filled = false;
+        for msg in pane.msg_data.iter_mut().filter(|m| m.reply_sender.is_none()) {
+            let Some(reply_to) = msg.reply_to_msg_id else { continue };
+            if let Some((sender, text)) = self.reply_cache.get(&(chat_id, reply_to)) {
+                msg.reply_sender = Some(sender.clone());
+                msg.reply_text = Some(text.clone());
+                filled = true;
+            }
+        }
+        if filled {
+            pane.format_cache.clear();
+        }
+    }
+
+    /// Prepend the batch of messages before the oldest loaded one. The scroll offset
+    /// counts lines from the bottom, so the view stays put while history grows above it.
+    pub async fn load_older_messages(&mut self, pane_idx: usize) {
+        let Some(pane) = self.panes.get(pane_idx) else { return };
+        if pane.history_exhausted || pane.search_active() {
+            return;
+        }
+        let (Some(chat_id), Some(before_id)) = (pane.chat_id, pane.oldest_msg_id()) else {
+            return;
+        };
+
+        let older = match self.telegram.get_messages_before(chat_id, before_id, HISTORY_PAGE_SIZE).await {
+            Ok(older) => older,
+            Err(e) => {
+                self.notify(&format!("Failed to load older messages: {}", e));
+                return;
+            }
+        };
+
+        let my_user_id = self.my_user_id;
+        let Some(pane) = self.panes.get_mut(pane_idx) else { return };
+        if older.is_empty() {
+            pane.history_exhausted = true;
+            self.notify("Start of conversation");
+            return;
+        }
+        let count = older.len();
+        let mut msg_data: Vec<crate::widgets::MessageData> = older
+            .into_iter()
+            .map(|raw| crate::widgets::MessageData::from_raw(raw, my_user_id))
+            .collect();
+        msg_data.append(&mut pane.msg_data);
+        pane.msg_data = msg_data;
+        pane.format_cache.clear();
+        self.resolve_reply_previews(pane_idx).await;
+        self.notify(&format!("Loaded {} older messages", count));
+    }
+
     pub fn handle_page_down(&mut self) {
         if !self.focus_on_chat_list {
             if let Some(pane) = self.panes.get_mut(self.focused_pane_idx) {
-                pane.scroll_down();
+                let page = pane.page_height();
+                pane.scroll_down(page);
+            }
+        }
+    }
+
+    /// Open the reaction picker for message #`msg_num` of a pane
+    pub async fn open_reaction_picker(&mut self, pane_idx: usize, msg_num: usize) {
+        let target = self.panes.get(pane_idx).and_then(|pane| {
+            let msg = pane.msg_data.get(msg_num.checked_sub(1)?)?;
+            Some((pane.chat_id?, msg.msg_id))
+        });
+        let Some((chat_id, msg_id)) = target else {
+            self.notify(&format!("Message #{} not found", msg_num));
+            return;
+        };
+
+        if self.available_reactions.is_empty() {
+            self.available_reactions = match self.telegram.get_available_reactions().await {
+                Ok(reactions) if !reactions.is_empty() => reactions,
+                _ => DEFAULT_REACTIONS.iter().map(|r| r.to_string()).collect(),
+            };
+        }
+
+        self.reaction_picker = Some(ReactionPicker {
+            pane_idx,
+            chat_id,
+            msg_id,
+            msg_num,
+            options: self.available_reactions.clone(),
+            selected: 0,
+        });
+    }
+
+    /// Alt+R: react to the message being replied to, or else the newest one
+    pub async fn open_reaction_picker_for_focused(&mut self) {
+        let pane_idx = self.focused_pane_idx;
+        let msg_num = self.panes.get(pane_idx).map_or(0, |pane| {
+            pane.reply_to_message
+                .and_then(|id| pane.msg_data.iter().position(|m| m.msg_id == id))
+                .map_or(pane.msg_data.len(), |pos| pos + 1)
+        });
+        if msg_num == 0 {
+            self.notify("No message to react to");
+            return;
+        }
+        self.open_reaction_picker(pane_idx, msg_num).await;
+    }
+
+    /// Enter in the picker: send the selected reaction and show it right away
+    pub async fn apply_reaction_picker(&mut self) {
+        let Some(picker) = self.reaction_picker.take() else {
+            return;
+        };
+        let Some(emoji) = picker.current().map(str::to_string) else {
+            return;
+        };
+        self.send_reaction(picker.pane_idx, picker.chat_id, picker.msg_id, picker.msg_num, Some(&emoji))
+            .await;
+    }
+
+    /// React to a message, or clear our reaction when `emoji` is None. The pane's
+    /// counts change before the request goes out; the next refresh reconciles them.
+    pub async fn send_reaction(
+        &mut self,
+        pane_idx: usize,
+        chat_id: i64,
+        msg_id: i32,
+        msg_num: usize,
+        emoji: Option<&str>,
+    ) {
+        let previous = match self.panes.get_mut(pane_idx) {
+            Some(pane) => pane.set_my_reaction(msg_id, emoji),
+            None => return,
+        };
+        match self.telegram.send_reaction(chat_id, msg_id, emoji.unwrap_or("")).await {
+            Ok(()) => match emoji {
+                Some(emoji) => self.notify(&format!("Reacted {} to #{}", emoji, msg_num)),
+                None => self.notify(&format!("Removed your reaction from #{}", msg_num)),
+            },
+            Err(e) => {
+                if let Some(pane) = self.panes.get_mut(pane_idx) {
+                    pane.set_my_reaction(msg_id, previous.as_deref());
+                }
+                self.notify(&format!("Reaction failed: {}", e));
+            }
+        }
+    }
+
+    /// Vote in a poll (/vote), showing the new percentages before Telegram confirms
+    pub async fn vote_poll(&mut self, chat_id: i64, msg_id: i32, msg_num: usize, option: usize) {
+        let previous = self.telegram.cached_poll(chat_id, msg_id).await;
+        if let Some(poll) = &previous {
+            let mut voted = poll.clone();
+            if voted.apply_vote(option) {
+                self.set_message_text(chat_id, msg_id, &voted.render());
+            }
+        }
+        match self.telegram.vote_poll(chat_id, msg_id, option).await {
+            Ok(()) => self.notify(&format!("Voted for option {} in #{}", option + 1, msg_num)),
+            Err(e) => {
+                if let Some(poll) = previous {
+                    self.set_message_text(chat_id, msg_id, &poll.render());
+                }
+                self.notify(&format!("Vote failed: {}", e));
+            }
+        }
+    }
+
+    /// Replace the shown text of a message in every pane showing its chat
+    fn set_message_text(&mut self, chat_id: i64, msg_id: i32, text: &str) {
+        for pane in self.panes.iter_mut().filter(|p| p.chat_id == Some(chat_id)) {
+            if let Some(msg) = pane.msg_data.iter_mut().find(|m| m.msg_id == msg_id) {
+                msg.text = text.to_string();
+                pane.format_cache.clear();
+            }
+        }
+    }
+
+    /// Terminal resized: drop width-dependent caches so text rewraps at the new
+    /// size. Scroll bounds depend on the new wrapping, so they're clamped once
+    /// the next draw has measured it (see `clamp_scroll_after_resize`).
+    pub fn handle_resize(&mut self) {
+        for pane in &mut self.panes {
+            pane.format_cache.clear();
+        }
+        self.pane_areas.clear();
+        self.resize_pending = true;
+        self.needs_redraw = true;
+    }
+
+    /// After the first draw following a resize, pull panes back inside their
+    /// new scroll range so none is left scrolled past the end.
+    pub fn clamp_scroll_after_resize(&mut self) {
+        if !self.resize_pending {
+            return;
+        }
+        self.resize_pending = false;
+        for pane in &mut self.panes {
+            pane.clamp_scroll();
+        }
+    }
+
+    /// Ctrl+Up/Down: scroll the focused pane by a single line
+    pub fn handle_scroll_line(&mut self, up: bool) {
+        if !self.focus_on_chat_list
+            && let Some(pane) = self.panes.get_mut(self.focused_pane_idx)
+        {
+            if up {
+                pane.scroll_up(1);
+            } else {
+                pane.scroll_down(1);
             }
         }
     }
 
+    /// Ctrl+Home/End: scroll the focused pane to the oldest loaded or the newest message.
+    /// Offset 0 is the bottom, which keeps following new messages.
+    pub fn scroll_to_edge(&mut self, top: bool) {
+        if self.focus_on_chat_list {
+            return;
+        }
+        if let Some(pane) = self.panes.get_mut(self.focused_pane_idx) {
+            pane.scroll_offset = if top { pane.scroll_view.get().0 } else { 0 };
+        }
+    }
+
+    /// The focused pane's input is empty and no message is being edited
+    fn input_idle(&self) -> bool {
+        self.panes
+            .get(self.focused_pane_idx)
+            .is_some_and(|p| p.input_buffer.is_empty() && p.editing_message.is_none())
+    }
+
+    /// Alt+U: scroll the focused pane back to where the unread messages begin
+    pub fn jump_to_unread_divider(&mut self) {
+        let Some(pane) = self.panes.get_mut(self.focused_pane_idx) else { return };
+        if !pane.scroll_to_unread_divider() {
+            self.notify("No unread messages in this chat");
+        }
+    }
+
     /// Handle Tab key: try autocomplete first, then cycle focus
     pub fn handle_tab(&mut self) {
         let is_empty = self.panes.get(self.focused_pane_idx)
@@ -1464,11 +2570,33 @@ impl App {
         }
     }
 
+    /// Handle Shift+Tab: cycle focus backwards through chat list and panes.
+    /// With a draft in the input, stay among panes so the chat list isn't hit mid-edit.
+    pub fn handle_backtab(&mut self) {
+        let input_empty = self
+            .panes
+            .get(self.focused_pane_idx)
+            .is_none_or(|p| p.input_buffer.is_empty());
+
+        if self.focus_on_chat_list || input_empty {
+            self.cycle_focus_reverse();
+        } else {
+            self.focus_prev_pane();
+        }
+    }
+
     pub async fn handle_enter(&mut self) -> Result<()> {
         let input_empty = self.panes.get(self.focused_pane_idx)
             .map_or(true, |p| p.input_buffer.is_empty());
         
         if input_empty {
+            if self.focus_on_chat_list
+                && looks_like_username(&self.chat_list_filter)
+                && self.chat_list_order().is_empty()
+            {
+                self.open_username_from_filter().await;
+                return Ok(());
+            }
             if self.focus_on_chat_list && !self.chats.is_empty() {
                 let ordered_chats = self.chat_list_order();
                 if let Some(&chat_idx) = ordered_chats.get(self.selected_chat_idx) {
@@ -1476,71 +2604,67 @@ impl App {
                     let chat_id = chat.id;
                     let chat_name = chat.name.clone();
                     let chat_username = chat.username.clone();
-                    let raw_messages = self.telegram.get_messages(chat_id, 50).await?;
+                    let raw_messages = self.telegram.get_messages(chat_id, self.message_fetch_limit).await?;
 
                     // Convert to MessageData for proper formatting support
                     let msg_data: Vec<crate::widgets::MessageData> = raw_messages
-                        .iter()
-                        .map(|(msg_id, sender_id, sender_name, text, reply_to_id, media_type, reactions)| {
-                            let reply_to_msg_id = *reply_to_id;
-                            
-                            crate::widgets::MessageData {
-                                msg_id: *msg_id,
-                                sender_id: *sender_id,
-                                sender_name: sender_name.clone(),
-                                text: text.clone(),
-                                is_outgoing: *sender_id == self.my_user_id,
-                                timestamp: chrono::Utc::now().timestamp(),
-                                media_type: media_type.clone(),
-                                media_label: None,
-                                reactions: reactions.clone(),
-                                reply_to_msg_id,
-                                reply_sender: None,
-                                reply_text: None,
-                            }
-                        })
+                        .into_iter()
+                        .map(|raw| crate::widgets::MessageData::from_raw(raw, self.my_user_id))
                         .collect();
 
+                    let scroll_offset = self.switch_scroll(self.focused_pane_idx, chat_id);
+                    self.switch_draft(self.focused_pane_idx, chat_id);
                     if let Some(pane) = self.panes.get_mut(self.focused_pane_idx) {
-                        
                         pane.chat_id = Some(chat_id);
                         pane.chat_name = chat_name;
                         pane.username = chat_username;
                         pane.msg_data = msg_data;
+                        pane.format_cache.clear();
                         pane.messages.clear(); // Clear status messages when switching chats
                         pane.reply_to_message = None;
                         pane.hide_reply_preview();
-                        // Don't set scroll_offset yet - let it be calculated during render
-                        pane.scroll_offset = 0;
+                        pane.cancel_edit();
+                        pane.search_query = None;
+                        pane.clear_search_matches();
+                        pane.expanded_messages.clear();
+                        pane.peeking = false;
+                        pane.history_exhausted = false;
+                        pane.scroll_offset = scroll_offset;
 
                         // Mark chat as read
                         if let Some(chat_info) =
                             self.chats.iter_mut().find(|c| c.id == chat_id)
                         {
-                            pane.unread_count_at_load = chat_info.unread;
+                            pane.set_unread_divider(chat_info.unread);
                             chat_info.unread = 0;
                         }
                     }
+                    self.resolve_reply_previews(self.focused_pane_idx).await;
+                    self.send_read_receipt(self.focused_pane_idx);
                     self.focus_on_chat_list = false;
+                    self.clear_chat_list_filter();
                 }
             }
         } else if !self.focus_on_chat_list {
             // Get input from active pane
-            let (input_text, _chat_id, _reply_to_id) = if let Some(pane) = self.panes.get(self.focused_pane_idx) {
+            let (input_text, current_chat_id, _reply_to_id) = if let Some(pane) = self.panes.get(self.focused_pane_idx) {
                 (pane.input_buffer.clone(), pane.chat_id, pane.reply_to_message)
             } else {
                 return Ok(());
             };
 
-            // Save to history (no duplicates)
-            if self.input_history.last().map_or(true, |last| last != &input_text) {
-                self.input_history.push(input_text.clone());
-                if self.input_history.len() > 100 {
-                    self.input_history.remove(0);
-                }
+            // "Edit last" mode: Enter edits the loaded message instead of sending a new one
+            let editing = self.panes.get(self.focused_pane_idx).and_then(|p| p.editing_message);
+            if let (Some(msg_id), Some(chat_id)) = (editing, current_chat_id) {
+                self.submit_edit(chat_id, msg_id, input_text).await;
+                return Ok(());
             }
+
+            // Save to history (no duplicates)
+            self.input_history.push(input_text.clone());
             self.history_idx = None;
             self.history_temp.clear();
+            self.stop_typing();
 
             // Try command handling
             if input_text.starts_with('/') {
@@ -1555,75 +2679,71 @@ impl App {
                 }
             }
 
+            // Don't create phantom messages in channels we can't post to
+            if current_chat_id.is_some_and(|id| !self.can_post_in(id)) {
+                self.notify("Read-only channel: you don't have permission to post here");
+                return Ok(());
+            }
+
             // Handle reply mode or normal send
-            if let Some(pane) = self.panes.get_mut(self.focused_pane_idx) {
-                if let (Some(chat_id), Some(reply_to_id)) =
-                    (pane.chat_id, pane.reply_to_message)
-                {
-                    // FIRST: Add message DIRECTLY to pane IMMEDIATELY - no waiting!
-                    let new_msg = crate::widgets::MessageData {
-                        msg_id: 0, // Temporary ID
-                        sender_id: self.my_user_id,
-                        sender_name: "You".to_string(),
-                        text: input_text.clone(),
-                        is_outgoing: true,
-                        timestamp: chrono::Utc::now().timestamp(),
-                        media_type: None,
-                        media_label: None,
-                        reactions: std::collections::HashMap::new(),
-                        reply_to_msg_id: Some(reply_to_id),
-                        reply_sender: None,
-                        reply_text: None,
-                    };
-                    pane.msg_data.push(new_msg);
-                    pane.format_cache.clear();
-                    
-                    pane.reply_to_message = None;
-                    pane.hide_reply_preview();
-                    pane.input_buffer.clear();
-                    pane.input_cursor = 0;
-                    
-                    // THEN: Send message in background - don't wait!
-                    let telegram = self.telegram.clone();
-                    let chat_id_copy = chat_id;
-                    let reply_to_id_copy = reply_to_id;
-                    let input_text_copy = input_text.clone();
-                    tokio::spawn(async move {
-                        let _ = telegram.reply_to_message(chat_id_copy, reply_to_id_copy, &input_text_copy).await;
-                    });
-                } else if let Some(chat_id) = pane.chat_id {
-                    // FIRST: Add message DIRECTLY to pane IMMEDIATELY - no waiting!
-                    let new_msg = crate::widgets::MessageData {
-                        msg_id: 0, // Temporary ID
-                        sender_id: self.my_user_id,
-                        sender_name: "You".to_string(),
-                        text: input_text.clone(),
-                        is_outgoing: true,
-                        timestamp: chrono::Utc::now().timestamp(),
-                        media_type: None,
-                        media_label: None,
-                        reactions: std::collections::HashMap::new(),
-                        reply_to_msg_id: None,
-                        reply_sender: None,
-                        reply_text: None,
-                    };
-                    pane.msg_data.push(new_msg);
+            let offline = self.telegram.connection_state() == ConnectionState::Offline;
+            let Some(pane) = self.panes.get_mut(self.focused_pane_idx) else { return Ok(()) };
+            let Some(chat_id) = pane.chat_id else { return Ok(()) };
+            let reply_to = pane.reply_to_message.take();
+
+            // FIRST: Add message DIRECTLY to pane IMMEDIATELY - no waiting!
+            pane.push_optimistic(self.my_user_id, input_text.clone(), reply_to);
+            pane.messages.clear(); // Conversation resumed; drop old command output
+            pane.peeking = false; // Replying ends a peek
+            if reply_to.is_some() {
+                pane.hide_reply_preview();
+            }
+            pane.input_buffer.clear();
+            pane.input_cursor = 0;
+
+            if offline {
+                self.queue_offline(QueuedSend { chat_id, text: input_text, reply_to });
+                return Ok(());
+            }
+
+            // THEN: Send message in background - don't wait!
+            let telegram = self.telegram.clone();
+            let input_text_copy = input_text.clone();
+            let handle = tokio::spawn(async move {
+                telegram.send_or_queue(chat_id, input_text_copy, reply_to).await;
+            });
+            pane.track_send("Sending message".to_string(), input_text, handle, None);
+        }
+        Ok(())
+    }
+
+    /// Hold a message until the connection is back; its local echo stays pending meanwhile
+    fn queue_offline(&mut self, send: QueuedSend) {
+        self.offline_queue.push(send);
+        self.notify("Offline: message queued, it will be sent on reconnect");
+    }
+
+    /// Send the edit started with Up-on-empty-input and leave edit mode
+    async fn submit_edit(&mut self, chat_id: i64, msg_id: i32, new_text: String) {
+        match self.telegram.edit_message(chat_id, msg_id, &new_text).await {
+            Ok(_) => {
+                if let Some(pane) = self.panes.get_mut(self.focused_pane_idx) {
+                    if let Some(msg) = pane.msg_data.iter_mut().find(|m| m.msg_id == msg_id) {
+                        msg.text = new_text;
+                        msg.quote_ranges.clear(); // Edits are sent as plain text
+                    }
                     pane.format_cache.clear();
-                    
+                    pane.editing_message = None;
                     pane.input_buffer.clear();
                     pane.input_cursor = 0;
-                    
-                    // THEN: Send message in background - don't wait!
-                    let telegram = self.telegram.clone();
-                    let chat_id_copy = chat_id;
-                    let input_text_copy = input_text.clone();
-                    tokio::spawn(async move {
-                        let _ = telegram.send_message(chat_id_copy, &input_text_copy).await;
-                    });
                 }
+                self.notify("Message edited");
+            }
+            Err(e) => {
+                // Keep edit mode so the text isn't lost
+                self.notify(&format!("Edit failed: {}", e));
             }
         }
-        Ok(())
     }
 
     pub fn handle_char(&mut self, c: char) {
@@ -1632,6 +2752,40 @@ impl App {
             pane.input_cursor += c.len_utf8();
         }
         self.history_idx = None;
+        self.report_typing();
+    }
+
+    /// Let the chat see we're typing (commands and edits don't count), throttled
+    /// so a burst of keystrokes sends one update
+    fn report_typing(&mut self) {
+        let Some(pane) = self.panes.get(self.focused_pane_idx) else { return };
+        let Some(chat_id) = pane.chat_id else { return };
+        if pane.input_buffer.starts_with('/') || pane.editing_message.is_some() || pane.peeking {
+            return;
+        }
+        if self.typing_throttle.on_keystroke(chat_id, std::time::Instant::now()) {
+            let telegram = self.telegram.clone();
+            tokio::spawn(async move {
+                let _ = telegram.set_typing(chat_id, true).await;
+            });
+        }
+    }
+
+    /// Clear our typing status once the message is sent or the input is emptied
+    fn stop_typing(&mut self) {
+        if let Some(chat_id) = self.typing_throttle.stop() {
+            let telegram = self.telegram.clone();
+            tokio::spawn(async move {
+                let _ = telegram.set_typing(chat_id, false).await;
+            });
+        }
+    }
+
+    /// Stop typing when the focused pane's input has been emptied
+    fn stop_typing_if_empty(&mut self) {
+        if self.panes.get(self.focused_pane_idx).is_some_and(|p| p.input_buffer.is_empty()) {
+            self.stop_typing();
+        }
     }
 
     pub fn handle_backspace(&mut self) {
@@ -1647,6 +2801,7 @@ impl App {
             }
         }
         self.history_idx = None;
+        self.stop_typing_if_empty();
     }
 
     pub fn handle_delete(&mut self) {
@@ -1655,6 +2810,8 @@ impl App {
                 pane.input_buffer.remove(pane.input_cursor);
             }
         }
+        self.history_idx = None;
+        self.stop_typing_if_empty();
     }
 
     pub fn handle_input_left(&mut self) {
@@ -1681,15 +2838,82 @@ impl App {
         }
     }
 
+    /// Insert pasted text at the cursor, newlines included, without sending it.
+    /// On the chat list only the first line goes into the filter.
+    pub fn handle_paste(&mut self, text: &str) {
+        let text = text.replace("\r\n", "\n").replace('\r', "\n");
+        if self.focus_on_chat_list {
+            for c in text.lines().next().unwrap_or_default().chars() {
+                self.handle_chat_list_char(c);
+            }
+            return;
+        }
+        if let Some(pane) = self.panes.get_mut(self.focused_pane_idx) {
+            pane.input_buffer.insert_str(pane.input_cursor, &text);
+            pane.input_cursor += text.len();
+        }
+        self.history_idx = None;
+        self.report_typing();
+    }
+
+    /// Ctrl+Left: move the cursor to the start of the previous word
+    pub fn handle_input_word_left(&mut self) {
+        if let Some(pane) = self.panes.get_mut(self.focused_pane_idx) {
+            pane.input_cursor = crate::formatting::prev_word_boundary(&pane.input_buffer, pane.input_cursor);
+        }
+    }
+
+    /// Ctrl+Right: move the cursor to the end of the next word
+    pub fn handle_input_word_right(&mut self) {
+        if let Some(pane) = self.panes.get_mut(self.focused_pane_idx) {
+            pane.input_cursor = crate::formatting::next_word_boundary(&pane.input_buffer, pane.input_cursor);
+        }
+    }
+
+    /// Alt+Backspace: delete the word before the cursor
+    pub fn handle_delete_word(&mut self) {
+        if let Some(pane) = self.panes.get_mut(self.focused_pane_idx) {
+            let start = crate::formatting::prev_word_boundary(&pane.input_buffer, pane.input_cursor);
+            pane.input_buffer.replace_range(start..pane.input_cursor, "");
+            pane.input_cursor = start;
+        }
+        self.history_idx = None;
+        self.stop_typing_if_empty();
+    }
+
+    /// Home: jump to the first chat, or to the start of the current input line
+    /// (with nothing typed, to the oldest loaded message)
     pub fn handle_home(&mut self) {
+        if self.focus_on_chat_list {
+            self.selected_chat_idx = 0;
+            return;
+        }
+        if self.input_idle() {
+            self.scroll_to_edge(true);
+            return;
+        }
         if let Some(pane) = self.panes.get_mut(self.focused_pane_idx) {
-            pane.input_cursor = 0;
+            pane.input_cursor = pane.input_buffer[..pane.input_cursor]
+                .rfind('\n')
+                .map_or(0, |i| i + 1);
         }
     }
 
+    /// End: jump to the last chat, or to the end of the current input line
+    /// (with nothing typed, to the newest message)
     pub fn handle_end(&mut self) {
+        if self.focus_on_chat_list {
+            self.selected_chat_idx = self.chat_list_order().len().saturating_sub(1);
+            return;
+        }
+        if self.input_idle() {
+            self.scroll_to_edge(false);
+            return;
+        }
         if let Some(pane) = self.panes.get_mut(self.focused_pane_idx) {
-            pane.input_cursor = pane.input_buffer.len();
+            pane.input_cursor = pane.input_buffer[pane.input_cursor..]
+                .find('\n')
+                .map_or(pane.input_buffer.len(), |i| pane.input_cursor + i);
         }
     }
 
@@ -1697,6 +2921,16 @@ impl App {
     // New message handling
     // =========================================================================
 
+    /// Fire a desktop notification, plus the configured sound, unless it's quiet hours
+    fn desktop_notify(&self, title: &str, message: &str) {
+        let (start, end) = self.dnd_hours;
+        if self.dnd && is_quiet_hours(chrono::Local::now(), start, end) {
+            return;
+        }
+        send_desktop_notification(title, message);
+        play_notification_sound(&self.notification_sound);
+    }
+
     pub async fn process_telegram_events(&mut self) -> Result<bool> {
         // Process incoming updates
         let updates = self.telegram.poll_updates().await?;
@@ -1706,13 +2940,25 @@ impl App {
             match update {
                 crate::telegram::TelegramUpdate::NewMessage {
                     chat_id,
-                    _sender_name: _,
+                    msg_id,
+                    sender_id,
+                    sender_name,
                     text,
+                    quote_ranges,
                     is_outgoing,
+                    reply_to_id,
+                    media_type,
+                    media_label,
+                    timestamp,
                 } => {
                     // Normalize chat_id
                     let normalized_id = crate::utils::normalize_chat_id(chat_id);
 
+                    if !is_outgoing {
+                        self.maybe_send_away_reply(chat_id);
+                    }
+                    self.bump_chat_activity(chat_id, timestamp);
+
                     // Check if any pane has this chat open
                     let matching_panes: Vec<usize> = self
                         .panes
@@ -1725,51 +2971,40 @@ impl App {
                         .collect();
 
                 if !matching_panes.is_empty() {
-                    // Always reload messages to get the real message ID and full data from Telegram
-                    // This replaces the optimistic local echo (msg_id: 0) with the real message
-                    let target_id = if self
-                        .panes
-                        .iter()
-                        .any(|p| p.chat_id == Some(chat_id))
-                    {
-                        chat_id
-                    } else {
-                        normalized_id
+                    let message = crate::widgets::MessageData {
+                        msg_id,
+                        sender_id,
+                        sender_name,
+                        text: text.clone(),
+                        quote_ranges,
+                        is_outgoing,
+                        timestamp,
+                        media_type,
+                        media_label,
+                        reactions: std::collections::HashMap::new(),
+                        reply_to_msg_id: reply_to_id,
+                        reply_sender: None,
+                        reply_text: None,
+                        send_failed: false,
+                        pending: false,
                     };
 
-                    if let Ok(raw_messages) =
-                        self.telegram.get_messages(target_id, 50).await
-                    {
-                        // Convert to MessageData for proper formatting support
-                        let msg_data: Vec<crate::widgets::MessageData> = raw_messages
-                            .iter()
-                            .map(|(msg_id, sender_id, sender_name, text, reply_to_id, media_type, reactions)| {
-                                let reply_to_msg_id = *reply_to_id;
-                                
-                                crate::widgets::MessageData {
-                                    msg_id: *msg_id,
-                                    sender_id: *sender_id,
-                                    sender_name: sender_name.clone(),
-                                    text: text.clone(),
-                                    is_outgoing: *sender_id == self.my_user_id,
-                                    timestamp: chrono::Utc::now().timestamp(),
-                                    media_type: media_type.clone(),
-                                    media_label: None,
-                                    reactions: reactions.clone(),
-                                    reply_to_msg_id,
-                                    reply_sender: None,
-                                    reply_text: None,
-                                }
-                            })
-                            .collect();
-
-                        for idx in &matching_panes {
-                            if let Some(pane) = self.panes.get_mut(*idx) {
-                                pane.msg_data = msg_data.clone();
-                                pane.format_cache.clear(); // Clear cache so messages are re-rendered
-                                // Don't clear messages - they may contain status messages
-                            }
+                    for idx in matching_panes {
+                        let is_active = idx == self.focused_pane_idx && !self.focus_on_chat_list;
+                        let Some(pane) = self.panes.get_mut(idx) else { continue };
+                        if !is_active && !is_outgoing {
+                            pane.unread_count += 1;
+                        }
+                        // Search results stay put until the search is cleared
+                        if pane.search_active() {
+                            continue;
                         }
+                        // Replies to messages we don't have need the surrounding history
+                        if reply_to_id.is_some_and(|id| !pane.msg_data.iter().any(|m| m.msg_id == id)) {
+                            let _ = self.refresh_pane_messages(idx).await;
+                            continue;
+                        }
+                        pane.append_incoming(message.clone());
                     }
                 } else {
                         // Increment unread for chats not in view
@@ -1791,15 +3026,57 @@ impl App {
                                 text.clone()
                             };
 
-                            // Desktop notification
-                            if self.show_notifications && !is_outgoing {
-                                send_desktop_notification(&chat_name, &preview);
+                            // Desktop notification (bursts are coalesced into a summary later)
+                            if self.show_notifications
+                                && !is_outgoing
+                                && !chat_info.muted
+                                && !self.muted_chats.contains(chat_info.id)
+                                && self.notification_throttle.on_message(chat_info.id, std::time::Instant::now())
+                            {
+                                self.desktop_notify(&chat_name, &preview);
                             }
 
                             self.notify(&format!("{}: {}", chat_name, preview));
                         }
                     }
                 }
+                crate::telegram::TelegramUpdate::SendFailed {
+                    chat_id,
+                    text,
+                    error,
+                } => {
+                    // Mark the optimistic local echo as failed instead of leaving it looking sent
+                    for pane in &mut self.panes {
+                        if pane.chat_id != Some(chat_id) {
+                            continue;
+                        }
+                        if let Some(msg) = pane
+                            .msg_data
+                            .iter_mut()
+                            .rev()
+                            .find(|m| m.pending && !m.send_failed && m.text == text)
+                        {
+                            msg.send_failed = true;
+                            pane.format_cache.clear();
+                        }
+                    }
+                    self.notify(&format!("✗ Send failed: {}", error));
+                }
+                crate::telegram::TelegramUpdate::SendQueued {
+                    chat_id,
+                    text,
+                    reply_to,
+                } => {
+                    self.queue_offline(QueuedSend { chat_id, text, reply_to });
+                }
+                crate::telegram::TelegramUpdate::UserStatus { chat_id, status } => {
+                    // Panes switched to another chat since the fetch started are left alone
+                    for pane in &mut self.panes {
+                        if pane.user_status_chat == Some(chat_id) {
+                            pane.user_status = status;
+                        }
+                    }
+                }
                 crate::telegram::TelegramUpdate::UserTyping {
                     chat_id,
                     user_name,
@@ -1816,7 +3093,87 @@ impl App {
             }
         }
 
-        Ok(had_updates)
+        self.flush_offline_queue();
+        let statuses_changed = self.refresh_user_statuses();
+
+        // Emit one summary per chat for messages held back by the throttle
+        for (chat_id, count) in self.notification_throttle.take_due(std::time::Instant::now()) {
+            if !self.show_notifications {
+                continue;
+            }
+            if self.muted_chats.contains(chat_id) {
+                continue;
+            }
+            let chat = self.chats.iter().find(|c| c.id == chat_id && !c.muted);
+            if let Some(chat_name) = chat.map(|c| c.name.clone()) {
+                let noun = if count == 1 { "message" } else { "messages" };
+                self.desktop_notify(&chat_name, &format!("{} new {}", count, noun));
+            }
+        }
+
+        Ok(had_updates || statuses_changed)
+    }
+
+    /// Send messages queued while offline, in the order they were written, once
+    /// the connection is back. Sends that fail again are re-queued by `send_or_queue`.
+    fn flush_offline_queue(&mut self) {
+        if self.offline_queue.is_empty()
+            || self.telegram.connection_state() != ConnectionState::Connected
+        {
+            return;
+        }
+        let queued = std::mem::take(&mut self.offline_queue);
+        self.notify(&format!("Reconnected: sending {} queued message(s)", queued.len()));
+        let telegram = self.telegram.clone();
+        tokio::spawn(async move {
+            for send in queued {
+                telegram.send_or_queue(send.chat_id, send.text, send.reply_to).await;
+            }
+        });
+    }
+
+    /// Fetch last seen statuses for panes showing 1-on-1 chats: right away for a
+    /// newly opened chat, otherwise every `USER_STATUS_REFRESH`. The lookups run in
+    /// the background and arrive as `UserStatus` updates, so a slow one doesn't
+    /// hold up the event loop. Returns true if any header changed.
+    fn refresh_user_statuses(&mut self) -> bool {
+        let due = self
+            .user_statuses_refreshed
+            .is_none_or(|at| at.elapsed() >= USER_STATUS_REFRESH);
+        if due {
+            self.user_statuses_refreshed = Some(std::time::Instant::now());
+        }
+
+        let mut changed = false;
+        let mut to_fetch = std::collections::HashSet::new();
+        for pane in &mut self.panes {
+            let Some(chat_id) = pane.chat_id else { continue };
+            let is_user_chat = chat_id != self.my_user_id
+                && self.chats.iter().any(|c| c.id == chat_id && c.kind == ChatKind::User);
+            let fresh = pane.user_status_chat == Some(chat_id);
+            if !is_user_chat || (fresh && !due) {
+                continue;
+            }
+
+            // A newly opened chat drops the previous chat's status until its own arrives
+            if !fresh {
+                pane.user_status = None;
+                pane.user_status_chat = Some(chat_id);
+                changed = true;
+            }
+            to_fetch.insert(chat_id);
+        }
+
+        for chat_id in to_fetch {
+            let telegram = self.telegram.clone();
+            tokio::spawn(async move {
+                let status = telegram.get_user_status(chat_id).await.ok();
+                telegram
+                    .queue_update(crate::telegram::TelegramUpdate::UserStatus { chat_id, status })
+                    .await;
+            });
+        }
+        changed
     }
 
     // =========================================================================
@@ -1825,41 +3182,57 @@ impl App {
 
     pub fn save_state(&self) -> Result<()> {
         let layout = LayoutData {
-            panes: self
-                .panes
-                .iter()
-                .map(|p| {
-                    let filter_type_str = p.filter_type.as_ref().map(|ft| match ft {
-                        crate::widgets::FilterType::Sender => "sender".to_string(),
-                        crate::widgets::FilterType::Media => "media".to_string(),
-                        crate::widgets::FilterType::Link => "link".to_string(),
-                    });
-                    PaneState {
-                        chat_id: p.chat_id,
-                        chat_name: p.chat_name.clone(),
-                        scroll_offset: p.scroll_offset,
-                        filter_type: filter_type_str,
-                        filter_value: p.filter_value.clone(),
-                    }
-                })
-                .collect(),
+            version: LAYOUT_VERSION,
+            panes: self.panes.iter().map(PaneState::from_pane).collect(),
             focused_pane: self.focused_pane_idx,
             pane_tree: Some(self.pane_tree.clone()),
         };
         layout.save(&self.config)?;
 
         self.aliases.save(&self.config)?;
+        self.chat_aliases.save(&self.config)?;
+
+        // Drafts of chats open in panes live in the panes' input buffers
+        let mut drafts = self.drafts.clone();
+        for pane in &self.panes {
+            if let Some(chat_id) = pane.chat_id {
+                drafts.stash(chat_id, pane.input_buffer.clone());
+            }
+        }
+        drafts.save(&self.config)?;
+
+        let mut scroll_positions = self.scroll_positions.clone();
+        for pane in &self.panes {
+            if let Some(chat_id) = pane.chat_id {
+                scroll_positions.remember(chat_id, pane.scroll_offset);
+            }
+        }
+        scroll_positions.save(&self.config)?;
+        self.input_history.save(&self.config)?;
+        OfflineQueue { sends: self.offline_queue.clone() }.save(&self.config)?;
 
         let mut config = self.config.clone();
         config.settings.show_reactions = self.show_reactions;
         config.settings.show_notifications = self.show_notifications;
+        config.settings.notification_sound = self.notification_sound.clone();
         config.settings.compact_mode = self.compact_mode;
+        config.settings.group_messages = self.group_messages;
         config.settings.show_emojis = self.show_emojis;
         config.settings.show_line_numbers = self.show_line_numbers;
         config.settings.show_timestamps = self.show_timestamps;
         config.settings.show_user_colors = self.show_user_colors;
         config.settings.show_borders = self.show_borders;
         config.settings.show_chat_list = self.show_chat_list;
+        config.settings.focus_mode = self.focus_mode;
+        config.settings.message_alignment = self.message_alignment.as_setting().to_string();
+        config.settings.collapse_lines = self.collapse_lines;
+        config.settings.max_panes = self.max_panes;
+        config.settings.message_fetch_limit = self.message_fetch_limit;
+        config.settings.away_message = self.away_message.clone();
+        config.settings.theme = self.theme.name.to_string();
+        config.settings.render_markdown = self.render_markdown;
+        config.settings.dnd = self.dnd;
+        (config.settings.dnd_start, config.settings.dnd_end) = self.dnd_hours;
         config.save()?;
 
         Ok(())
@@ -1,12 +1,17 @@
 use anyhow::Result;
 
-use crate::app::App;
-use crate::widgets::FilterType;
+use crate::app::{App, UndoAction};
+use crate::config::MAX_FETCH_LIMIT;
+use crate::formatting::{export_messages, ExportFormat};
+use crate::telegram::ChatKind;
+use crate::theme::{Theme, THEME_NAMES};
+use crate::widgets::{FilterType, MessageData};
 
 pub struct Command {
     pub name: String,
     pub args: Vec<String>,
-    pub _full_text: String,
+    pub full_text: String,
+    arg_starts: Vec<usize>, // Byte offset of each arg in full_text
 }
 
 impl Command {
@@ -15,20 +20,120 @@ impl Command {
             return None;
         }
 
-        let parts: Vec<&str> = text.split_whitespace().collect();
-        if parts.is_empty() {
-            return None;
-        }
-
-        let name = parts[0][1..].to_string();
-        let args = parts[1..].iter().map(|s| s.to_string()).collect();
+        let name_end = text.find(char::is_whitespace).unwrap_or(text.len());
+        let name = text[1..name_end].to_string();
+        let (arg_starts, args) = tokenize(text, name_end).into_iter().unzip();
 
         Some(Command {
             name,
             args,
-            _full_text: text.to_string(),
+            full_text: text.to_string(),
+            arg_starts,
         })
     }
+
+    /// The typed text from arg `n` on, quotes and spacing untouched, for
+    /// commands that take free text (/reply N text, /me, ...)
+    pub fn text_from(&self, n: usize) -> &str {
+        self.arg_starts
+            .get(n)
+            .map_or("", |&start| self.full_text[start..].trim_end())
+    }
+}
+
+/// Split `text[from..]` into (byte offset, arg) pairs. Whitespace separates
+/// args unless inside '...' or "..."; a quote only opens at the start of an
+/// arg, so "don't" stays one word. Backslash escapes a quote, a backslash or
+/// whitespace. An unclosed quote is kept as a literal character.
+fn tokenize(text: &str, from: usize) -> Vec<(usize, String)> {
+    let mut literal_quotes = Vec::new();
+    loop {
+        match tokenize_with(text, from, &literal_quotes) {
+            Ok(args) => return args,
+            Err(unclosed) => literal_quotes.push(unclosed),
+        }
+    }
+}
+
+/// One tokenizing pass; Err(offset) of a quote that is never closed
+fn tokenize_with(text: &str, from: usize, literal_quotes: &[usize]) -> Result<Vec<(usize, String)>, usize> {
+    let mut args = Vec::new();
+    let mut current: Option<(usize, String)> = None;
+    let mut quote: Option<(char, usize)> = None;
+    let mut chars = text[from..].char_indices().map(|(i, c)| (i + from, c)).peekable();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\\' if chars.peek().is_some_and(|&(_, next)| matches!(next, '"' | '\'' | '\\') || next.is_whitespace()) => {
+                let (_, next) = chars.next().unwrap_or((i, c));
+                current.get_or_insert_with(|| (i, String::new())).1.push(next);
+            }
+            c if quote.is_some_and(|(q, _)| q == c) => quote = None,
+            '"' | '\'' if quote.is_none() && current.is_none() && !literal_quotes.contains(&i) => {
+                quote = Some((c, i));
+                current = Some((i, String::new())); // "" is an (empty) arg too
+            }
+            c if c.is_whitespace() && quote.is_none() => args.extend(current.take()),
+            c => current.get_or_insert_with(|| (i, String::new())).1.push(c),
+        }
+    }
+    if let Some((_, start)) = quote {
+        return Err(start);
+    }
+    args.extend(current);
+    Ok(args)
+}
+
+/// Results fetched per /search page
+pub const SEARCH_PAGE_SIZE: usize = 100;
+
+/// Most members /members loads; large groups are truncated
+const MEMBERS_LIMIT: usize = 200;
+
+/// /export pages in older history until the pane holds this many messages
+const EXPORT_LIMIT: usize = 1000;
+
+/// Parse a message number argument, accepting both "5" and "#5"
+fn parse_msg_num(arg: &str) -> Option<i32> {
+    arg.trim_start_matches('#').parse().ok()
+}
+
+/// Message #`msg_num` as numbered on screen (1-based position in the pane)
+fn message_at(msg_data: &[MessageData], msg_num: i32) -> Option<&MessageData> {
+    msg_data.get(usize::try_from(msg_num).ok()?.checked_sub(1)?)
+}
+
+/// Launch a file or URL with the desktop's default handler
+fn open_with_system(target: &str) {
+    #[cfg(target_os = "macos")]
+    {
+        let _ = std::process::Command::new("open").arg(target).spawn();
+    }
+    #[cfg(target_os = "linux")]
+    {
+        let _ = std::process::Command::new("xdg-open").arg(target).spawn();
+    }
+}
+
+/// "N" or "N-M" as an inclusive range of message numbers
+fn parse_msg_range(arg: &str) -> Option<(usize, usize)> {
+    let (start, end) = match arg.split_once('-') {
+        Some((start, end)) => (parse_msg_num(start)?, parse_msg_num(end)?),
+        None => {
+            let n = parse_msg_num(arg)?;
+            (n, n)
+        }
+    };
+    if start < 1 || end < start {
+        return None;
+    }
+    Some((start as usize, end as usize))
+}
+
+/// "22-7" as (start, end) hours for /dnd
+fn parse_dnd_window(arg: &str) -> Option<(u8, u8)> {
+    let (start, end) = arg.split_once('-')?;
+    let hour = |s: &str| s.trim().parse::<u8>().ok();
+    crate::utils::valid_dnd_hours(hour(start)?, hour(end)?)
 }
 
 pub struct CommandHandler;
@@ -61,12 +166,76 @@ impl CommandHandler {
                 Self::handle_alias(app, &cmd, pane_idx).await?;
                 Ok(true)
             }
+            "rename" => {
+                Self::handle_rename(app, &cmd, pane_idx)?;
+                Ok(true)
+            }
+            "pinchat" => {
+                Self::handle_pin_chat(app, pane_idx, true)?;
+                Ok(true)
+            }
+            "unpinchat" => {
+                Self::handle_pin_chat(app, pane_idx, false)?;
+                Ok(true)
+            }
             "unalias" => {
                 Self::handle_unalias(app, &cmd, pane_idx).await?;
                 Ok(true)
             }
             "filter" => {
+                let before = app.panes.get(pane_idx).map(|p| {
+                    (p.chat_id, p.filter_type.clone(), p.filter_value.clone())
+                });
                 Self::handle_filter(app, &cmd, pane_idx).await?;
+                let changed = before.filter(|(_, filter_type, filter_value)| {
+                    app.panes.get(pane_idx).is_some_and(|p| {
+                        p.filter_type != *filter_type || p.filter_value != *filter_value
+                    })
+                });
+                if let Some((chat_id, filter_type, filter_value)) = changed {
+                    app.push_undo(UndoAction::FilterChanged {
+                        pane_idx,
+                        chat_id,
+                        filter_type,
+                        filter_value,
+                    });
+                }
+                Ok(true)
+            }
+            "away" => {
+                Self::handle_away(app, &cmd);
+                Ok(true)
+            }
+            "dnd" => {
+                Self::handle_dnd(app, &cmd);
+                Ok(true)
+            }
+            "limit" => {
+                Self::handle_limit(app, &cmd);
+                Ok(true)
+            }
+            "theme" => {
+                Self::handle_theme(app, &cmd);
+                Ok(true)
+            }
+            "profile" => {
+                Self::handle_profile(app, &cmd).await;
+                Ok(true)
+            }
+            "export" => {
+                Self::handle_export(app, &cmd, pane_idx).await;
+                Ok(true)
+            }
+            "expand" => {
+                Self::handle_expand(app, &cmd, pane_idx);
+                Ok(true)
+            }
+            "undo" => {
+                Self::handle_undo(app, pane_idx).await?;
+                Ok(true)
+            }
+            "next" | "prev" => {
+                app.step_search_match(pane_idx, cmd.name == "next").await;
                 Ok(true)
             }
             "search" | "s" => {
@@ -97,6 +266,74 @@ impl CommandHandler {
                 Self::handle_forward(app, &cmd, pane_idx).await?;
                 Ok(true)
             }
+            "pins" | "pin-list" => {
+                Self::handle_pins(app, &cmd, pane_idx).await?;
+                Ok(true)
+            }
+            "me" => {
+                Self::handle_me(app, &cmd, pane_idx).await?;
+                Ok(true)
+            }
+            "peek" => {
+                Self::handle_peek(app, &cmd, pane_idx).await;
+                Ok(true)
+            }
+            "poll" => {
+                Self::handle_poll(app, &cmd, pane_idx).await;
+                Ok(true)
+            }
+            "vote" => {
+                Self::handle_vote(app, &cmd, pane_idx).await;
+                Ok(true)
+            }
+            "react" => {
+                Self::handle_react(app, &cmd, pane_idx).await;
+                Ok(true)
+            }
+            "log" => {
+                Self::handle_log_view(app, pane_idx);
+                Ok(true)
+            }
+            "copy" => {
+                Self::handle_copy(app, &cmd, pane_idx);
+                Ok(true)
+            }
+            "open" => {
+                Self::handle_open(app, &cmd, pane_idx);
+                Ok(true)
+            }
+            "goto" => {
+                Self::handle_goto(app, &cmd, pane_idx);
+                Ok(true)
+            }
+            "upload" => {
+                Self::handle_upload(app, &cmd, pane_idx);
+                Ok(true)
+            }
+            "seen" => {
+                Self::handle_seen(app, &cmd, pane_idx).await;
+                Ok(true)
+            }
+            "mute" => {
+                Self::handle_mute(app, pane_idx, true).await;
+                Ok(true)
+            }
+            "unmute" => {
+                Self::handle_mute(app, pane_idx, false).await;
+                Ok(true)
+            }
+            "unread" => {
+                Self::handle_unread(app, pane_idx).await;
+                Ok(true)
+            }
+            "debug" => {
+                Self::handle_debug(app, pane_idx);
+                Ok(true)
+            }
+            "cancel" => {
+                Self::handle_cancel(app, pane_idx);
+                Ok(true)
+            }
             _ => Ok(false),
         }
     }
@@ -107,49 +344,56 @@ impl CommandHandler {
             return Ok(());
         }
 
-        let msg_num: i32 = match cmd.args[0].trim_start_matches('#').parse() {
-            Ok(n) => n,
-            Err(_) => {
+        let msg_num = match parse_msg_num(&cmd.args[0]) {
+            Some(n) => n,
+            None => {
                 app.notify("Usage: /reply N [text]");
                 return Ok(());
             }
         };
 
-        if let Some(pane) = app.panes.get_mut(pane_idx) {
-            if cmd.args.len() > 1 {
-                // Reply with inline text
-                let text = cmd.args[1..].join(" ");
-                if let Some(chat_id) = pane.chat_id {
-                    match app
-                        .telegram
-                        .reply_to_message(chat_id, msg_num, &text)
-                        .await
-                    {
-                        Ok(_) => pane.add_message(format!("✓ Replied to #{}", msg_num)),
-                        Err(e) => pane.add_message(format!("✗ Reply failed: {}", e)),
-                    }
-                }
-            } else {
-                // Set reply mode with preview - find actual message ID from msg_data
-                if let Some(msg_data) = pane.msg_data.get((msg_num - 1) as usize) {
-                    let actual_msg_id = msg_data.msg_id;
-                    pane.reply_to_message = Some(actual_msg_id);
-                    
-                    // Get first line of message for preview (max 60 chars)
-                    let first_line = msg_data.text.lines().next().unwrap_or(&msg_data.text);
-                    let preview_text = if first_line.chars().count() > 60 {
-                        let truncate_at = first_line.char_indices().nth(60).map(|(i, _)| i).unwrap_or(first_line.len());
-                        format!("{}...", &first_line[..truncate_at])
-                    } else {
-                        first_line.to_string()
-                    };
-                    
-                    pane.show_reply_preview(format!("Reply to #{}: {}", msg_num, preview_text));
-                    app.notify(&format!("Replying to message #{}. Type your reply.", msg_num));
-                } else {
-                    pane.add_message(format!("✗ Message #{} not found", msg_num));
+        let Some(pane) = app.panes.get_mut(pane_idx) else {
+            return Ok(());
+        };
+        // Find actual message ID from msg_data
+        let Some(msg_data) = pane.msg_data.get((msg_num - 1) as usize) else {
+            pane.add_message(format!("✗ Message #{} not found", msg_num));
+            return Ok(());
+        };
+        if msg_data.pending {
+            app.notify(&format!("Message #{} is still sending; try again in a moment", msg_num));
+            return Ok(());
+        }
+        let actual_msg_id = msg_data.msg_id;
+
+        if cmd.args.len() > 1 {
+            // Reply with inline text
+            let text = cmd.text_from(1).to_string();
+            if let Some(chat_id) = pane.chat_id {
+                match app
+                    .telegram
+                    .reply_to_message(chat_id, actual_msg_id, &text)
+                    .await
+                {
+                    Ok(_) => pane.add_message(format!("✓ Replied to #{}", msg_num)),
+                    Err(e) => pane.add_message(format!("✗ Reply failed: {}", e)),
                 }
             }
+        } else {
+            // Set reply mode with preview
+            pane.reply_to_message = Some(actual_msg_id);
+
+            // Get first line of message for preview (max 60 chars)
+            let first_line = msg_data.text.lines().next().unwrap_or(&msg_data.text);
+            let preview_text = if first_line.chars().count() > 60 {
+                let truncate_at = first_line.char_indices().nth(60).map(|(i, _)| i).unwrap_or(first_line.len());
+                format!("{}...", &first_line[..truncate_at])
+            } else {
+                first_line.to_string()
+            };
+
+            pane.show_reply_preview(format!("Reply to #{}: {}", msg_num, preview_text));
+            app.notify(&format!("Replying to message #{}. Type your reply.", msg_num));
         }
 
         Ok(())
@@ -163,9 +407,9 @@ impl CommandHandler {
             return Ok(());
         }
 
-        let msg_num: i32 = match cmd.args[0].trim_start_matches('#').parse() {
-            Ok(n) => n,
-            Err(_) => {
+        let msg_num = match parse_msg_num(&cmd.args[0]) {
+            Some(n) => n,
+            None => {
                 app.notify("Usage: /media N");
                 return Ok(());
             }
@@ -208,14 +452,7 @@ impl CommandHandler {
                 .await
             {
                 Ok(path) => {
-                    #[cfg(target_os = "macos")]
-                    {
-                        let _ = std::process::Command::new("open").arg(&path).spawn();
-                    }
-                    #[cfg(target_os = "linux")]
-                    {
-                        let _ = std::process::Command::new("xdg-open").arg(&path).spawn();
-                    }
+                    open_with_system(&path);
                     app.notify_with_duration(
                         &format!(
                             "✓ {}",
@@ -242,24 +479,44 @@ impl CommandHandler {
             return Ok(());
         }
 
-        let msg_num: i32 = match cmd.args[0].trim_start_matches('#').parse() {
-            Ok(n) => n,
-            Err(_) => {
+        let msg_num = match parse_msg_num(&cmd.args[0]) {
+            Some(n) => n,
+            None => {
                 app.notify("Usage: /edit N new_text");
                 return Ok(());
             }
         };
 
-        let new_text = cmd.args[1..].join(" ");
+        let new_text = cmd.text_from(1).to_string();
 
         if let Some(pane) = app.panes.get_mut(pane_idx) {
             if let Some(chat_id) = pane.chat_id {
+                let msg_id = match message_at(&pane.msg_data, msg_num) {
+                    None => {
+                        app.notify(&format!("Message #{} not found", msg_num));
+                        return Ok(());
+                    }
+                    Some(msg) if !msg.is_outgoing => {
+                        app.notify(&format!("#{} isn't your message; you can only edit your own", msg_num));
+                        return Ok(());
+                    }
+                    Some(msg) if msg.pending => {
+                        app.notify(&format!("Message #{} is still sending; try again in a moment", msg_num));
+                        return Ok(());
+                    }
+                    Some(msg) => msg.msg_id,
+                };
                 match app
                     .telegram
-                    .edit_message(chat_id, msg_num, &new_text)
+                    .edit_message(chat_id, msg_id, &new_text)
                     .await
                 {
                     Ok(_) => {
+                        if let Some(msg) = pane.msg_data.iter_mut().find(|m| m.msg_id == msg_id) {
+                            msg.text = new_text;
+                            msg.quote_ranges.clear(); // Edits are sent as plain text
+                            pane.format_cache.clear();
+                        }
                         pane.add_message(format!("✓ Edited message #{}", msg_num));
                         app.notify("Message edited");
                     }
@@ -276,24 +533,50 @@ impl CommandHandler {
 
     async fn handle_delete(app: &mut App, cmd: &Command, pane_idx: usize) -> Result<()> {
         if cmd.args.is_empty() {
-            app.notify("Usage: /delete N");
+            app.notify("Usage: /delete N [me]");
             return Ok(());
         }
 
-        let msg_num: i32 = match cmd.args[0].trim_start_matches('#').parse() {
-            Ok(n) => n,
-            Err(_) => {
-                app.notify("Usage: /delete N");
+        let msg_num = match parse_msg_num(&cmd.args[0]) {
+            Some(n) => n,
+            None => {
+                app.notify("Usage: /delete N [me]");
+                return Ok(());
+            }
+        };
+        // "me" removes it only from our own history; the default deletes for everyone
+        let revoke = match cmd.args.get(1).map(String::as_str) {
+            None => true,
+            Some("me") => false,
+            Some(_) => {
+                app.notify("Usage: /delete N [me]");
                 return Ok(());
             }
         };
 
         if let Some(pane) = app.panes.get_mut(pane_idx) {
             if let Some(chat_id) = pane.chat_id {
-                match app.telegram.delete_message(chat_id, msg_num).await {
+                let (msg_id, deleted_text) = match message_at(&pane.msg_data, msg_num) {
+                    None => {
+                        app.notify(&format!("Message #{} not found", msg_num));
+                        return Ok(());
+                    }
+                    Some(msg) if msg.pending => {
+                        app.notify(&format!("Message #{} is still sending; try again in a moment", msg_num));
+                        return Ok(());
+                    }
+                    Some(msg) => (msg.msg_id, Some(msg.text.clone()).filter(|t| !t.is_empty())),
+                };
+                match app.telegram.delete_message(chat_id, msg_id, revoke).await {
                     Ok(_) => {
-                        pane.add_message(format!("✓ Deleted message #{}", msg_num));
-                        app.notify("Message deleted");
+                        pane.msg_data.retain(|m| m.msg_id != msg_id);
+                        pane.format_cache.clear();
+                        let scope = if revoke { "for everyone" } else { "for you" };
+                        pane.add_message(format!("✓ Deleted message #{} {}", msg_num, scope));
+                        app.notify(&format!("Message deleted {}", scope));
+                        if let Some(text) = deleted_text {
+                            app.push_undo(UndoAction::DeletedMessage { chat_id, text });
+                        }
                     }
                     Err(e) => {
                         pane.add_message(format!("✗ Delete failed: {}", e));
@@ -312,9 +595,9 @@ impl CommandHandler {
             return Ok(());
         }
 
-        let msg_num: i32 = match cmd.args[0].trim_start_matches('#').parse() {
-            Ok(n) => n,
-            Err(_) => {
+        let msg_num = match parse_msg_num(&cmd.args[0]) {
+            Some(n) => n,
+            None => {
                 app.notify("Usage: /alias N name");
                 return Ok(());
             }
@@ -333,6 +616,7 @@ impl CommandHandler {
                     app.aliases.save(&app.config)?;
                     pane.add_message(format!("✓ Alias set: {}", alias));
                     app.notify(&format!("Alias set: {}", alias));
+                    app.refresh_all_pane_displays();
                 } else {
                     pane.add_message("✗ Could not find message sender".to_string());
                     app.notify("Could not find message sender");
@@ -343,15 +627,85 @@ impl CommandHandler {
         Ok(())
     }
 
+    /// Set or clear (no args) a local display name for the pane's chat
+    fn handle_rename(app: &mut App, cmd: &Command, pane_idx: usize) -> Result<()> {
+        let Some(chat_id) = app.panes.get(pane_idx).and_then(|p| p.chat_id) else {
+            app.notify("No chat in this pane");
+            return Ok(());
+        };
+
+        if cmd.args.is_empty() {
+            if app.chat_aliases.remove(&chat_id).is_some() {
+                app.chat_aliases.save(&app.config)?;
+                app.notify("Chat name reset");
+            } else {
+                app.notify("Usage: /rename name (no name resets)");
+            }
+            return Ok(());
+        }
+
+        let name = cmd.args.join(" ");
+        app.chat_aliases.insert(chat_id, name.clone());
+        app.chat_aliases.save(&app.config)?;
+        app.notify(&format!("Chat renamed: {}", name));
+        Ok(())
+    }
+
+    /// Pin the pane's chat to the top of the chat list, or unpin it
+    fn handle_pin_chat(app: &mut App, pane_idx: usize, pinned: bool) -> Result<()> {
+        let Some(chat_id) = app.panes.get(pane_idx).and_then(|p| p.chat_id) else {
+            app.notify("No chat in this pane");
+            return Ok(());
+        };
+        let name = app
+            .chats
+            .iter()
+            .find(|c| c.id == chat_id)
+            .map_or("chat".to_string(), |c| app.chat_display_name(c.id, &c.name).to_string());
+
+        if !app.pinned_chats.set(chat_id, pinned) {
+            let state = if pinned { "already pinned" } else { "not pinned" };
+            app.notify(&format!("{} is {}", name, state));
+            return Ok(());
+        }
+        app.pinned_chats.save(&app.config.pinned_chats_path())?;
+        let verb = if pinned { "Pinned" } else { "Unpinned" };
+        app.notify(&format!("{} {}", verb, name));
+        Ok(())
+    }
+
+    /// Open a chat by name without clearing its unread badge
+    async fn handle_peek(app: &mut App, cmd: &Command, pane_idx: usize) {
+        if cmd.args.is_empty() {
+            app.notify("Usage: /peek chat name");
+            return;
+        }
+
+        let query = cmd.args.join(" ").to_lowercase();
+        let found = app
+            .chats
+            .iter()
+            .find(|c| c.name.to_lowercase() == query)
+            .or_else(|| app.chats.iter().find(|c| c.name.to_lowercase().contains(&query)))
+            .map(|c| (c.id, c.name.clone()));
+
+        match found {
+            Some((chat_id, chat_name)) => {
+                app.peek_chat_in_pane(pane_idx, chat_id, &chat_name).await;
+            }
+            None => app.notify(&format!("No chat matching '{}'", cmd.args.join(" "))),
+        }
+    }
+
     async fn handle_unalias(app: &mut App, cmd: &Command, pane_idx: usize) -> Result<()> {
         if cmd.args.is_empty() {
             app.notify("Usage: /unalias N");
             return Ok(());
         }
 
-        let msg_num: i32 = match cmd.args[0].trim_start_matches('#').parse() {
-            Ok(n) => n,
-            Err(_) => {
+        let msg_num = match parse_msg_num(&cmd.args[0]) {
+            Some(n) => n,
+            None => {
                 app.notify("Usage: /unalias N");
                 return Ok(());
             }
@@ -364,10 +718,12 @@ impl CommandHandler {
                     .get_message_sender(chat_id, msg_num)
                     .await?;
                 if let Some(sender_id) = sender_id {
-                    if app.aliases.remove(&sender_id).is_some() {
+                    if let Some(alias) = app.aliases.remove(&sender_id) {
                         app.aliases.save(&app.config)?;
                         pane.add_message("✓ Alias removed".to_string());
                         app.notify("Alias removed");
+                        app.push_undo(UndoAction::AliasRemoved { user_id: sender_id, alias });
+                        app.refresh_all_pane_displays();
                     } else {
                         pane.add_message("✗ No alias found".to_string());
                         app.notify("No alias set for this user");
@@ -382,6 +738,247 @@ impl CommandHandler {
         Ok(())
     }
 
+    /// `/away <message>` turns on DM auto-replies, `/away off` turns them off
+    fn handle_away(app: &mut App, cmd: &Command) {
+        match cmd.args.first().map(String::as_str) {
+            None => match &app.away_message {
+                Some(message) => app.notify(&format!("Away: \"{}\" (/away off to disable)", message)),
+                None => app.notify("Usage: /away <message> | /away off"),
+            },
+            Some("off") => {
+                app.away_message = None;
+                app.notify("Away mode off");
+            }
+            Some(_) => {
+                let message = cmd.text_from(0).to_string();
+                app.notify(&format!("Away mode on: \"{}\"", message));
+                app.away_message = Some(message);
+                app.away_replied.clear();
+            }
+        }
+    }
+
+    /// Toggle quiet hours, or set the window as `start-end` hours (turning it on)
+    fn handle_dnd(app: &mut App, cmd: &Command) {
+        let (start, end) = app.dnd_hours;
+        match cmd.args.first().map(String::as_str) {
+            None => {
+                let state = if app.dnd { "on" } else { "off" };
+                app.notify(&format!("Quiet hours {} ({}:00-{}:00)", state, start, end));
+            }
+            Some("on") => {
+                app.dnd = true;
+                app.notify(&format!("Quiet hours on: no desktop notifications {}:00-{}:00", start, end));
+            }
+            Some("off") => {
+                app.dnd = false;
+                app.notify("Quiet hours off");
+            }
+            Some(window) => {
+                let Some(hours) = parse_dnd_window(window) else {
+                    app.notify("Usage: /dnd on|off | /dnd <start>-<end> (hours 0-23, e.g. 22-7)");
+                    return;
+                };
+                app.dnd = true;
+                app.dnd_hours = hours;
+                app.notify(&format!("Quiet hours on: no desktop notifications {}:00-{}:00", hours.0, hours.1));
+            }
+        }
+    }
+
+    /// Show or set how many messages are loaded when a chat is opened
+    fn handle_limit(app: &mut App, cmd: &Command) {
+        let Some(arg) = cmd.args.first() else {
+            app.notify(&format!("Message fetch limit: {}", app.message_fetch_limit));
+            return;
+        };
+        let Some(limit) = arg.parse::<usize>().ok().filter(|n| *n > 0) else {
+            app.notify(&format!("Usage: /limit <1-{}>", MAX_FETCH_LIMIT));
+            return;
+        };
+        app.message_fetch_limit = limit.min(MAX_FETCH_LIMIT);
+        if limit > MAX_FETCH_LIMIT {
+            app.notify(&format!("Message fetch limit capped at {}", MAX_FETCH_LIMIT));
+        } else {
+            app.notify(&format!("Message fetch limit set to {}", limit));
+        }
+    }
+
+    /// Write the pane's messages to a file in the config dir, after paging in
+    /// older history (up to EXPORT_LIMIT messages)
+    async fn handle_export(app: &mut App, cmd: &Command, pane_idx: usize) {
+        let format = match cmd.args.first() {
+            None => ExportFormat::Txt,
+            Some(arg) => match ExportFormat::from_arg(arg) {
+                Some(format) => format,
+                None => {
+                    app.notify("Usage: /export [txt|json|md]");
+                    return;
+                }
+            },
+        };
+        let Some(chat_id) = app.panes.get(pane_idx).and_then(|p| p.chat_id) else {
+            app.notify("No chat open");
+            return;
+        };
+
+        loop {
+            let Some(pane) = app.panes.get(pane_idx) else { return };
+            if pane.msg_data.len() >= EXPORT_LIMIT || pane.history_exhausted || pane.search_active() {
+                break;
+            }
+            let before = pane.msg_data.len();
+            app.load_older_messages(pane_idx).await;
+            if app.panes.get(pane_idx).is_none_or(|p| p.msg_data.len() == before) {
+                break;
+            }
+        }
+
+        let Some(pane) = app.panes.get(pane_idx) else { return };
+        let count = pane.msg_data.iter().filter(|m| !m.pending && !m.send_failed).count();
+        let path = app.config.config_dir.join(format!(
+            "telegram_export_{}_{}.{}",
+            chat_id,
+            chrono::Local::now().format("%Y%m%d_%H%M%S"),
+            format.extension()
+        ));
+        let written = export_messages(&pane.msg_data, format)
+            .and_then(|content| std::fs::write(&path, content).map_err(Into::into));
+        match written {
+            Ok(()) => app.notify(&format!("Exported {} messages to {}", count, path.display())),
+            Err(e) => app.notify(&format!("Export failed: {}", e)),
+        }
+    }
+
+    /// Switch the color theme; without a name, list the built-in ones
+    fn handle_theme(app: &mut App, cmd: &Command) {
+        let Some(name) = cmd.args.first() else {
+            app.notify(&format!(
+                "Theme: {} (available: {})",
+                app.theme.name,
+                THEME_NAMES.join(", ")
+            ));
+            return;
+        };
+        let Some(theme) = Theme::by_name(name) else {
+            app.notify(&format!("Unknown theme \"{}\" (available: {})", name, THEME_NAMES.join(", ")));
+            return;
+        };
+        app.theme = theme.for_terminal(app.truecolor);
+        // Sender colors were picked from the old palette
+        app.user_colors.clear();
+        app.notify(&format!("Theme: {}", app.theme.name));
+    }
+
+    /// Reconnect as another configured account; panes are reset since their chats belong to the old one
+    async fn handle_profile(app: &mut App, cmd: &Command) {
+        let Some(name) = cmd.args.first() else {
+            app.notify(&format!(
+                "Profile: {} (available: {})",
+                app.config.profile,
+                app.config.profile_names().join(", ")
+            ));
+            return;
+        };
+        if *name == app.config.profile {
+            app.notify(&format!("Already using profile {}", name));
+            return;
+        }
+        match app.switch_profile(name).await {
+            Ok(()) => app.notify(&format!("Profile: {} ({})", name, app.my_name)),
+            Err(e) => app.notify(&format!("Can't switch profile: {}", e)),
+        }
+    }
+
+    /// Toggle full display of a collapsed long message
+    fn handle_expand(app: &mut App, cmd: &Command, pane_idx: usize) {
+        let Some(msg_num) = cmd.args.first().and_then(|a| parse_msg_num(a)) else {
+            app.notify("Usage: /expand N");
+            return;
+        };
+        let Some(pane) = app.panes.get_mut(pane_idx) else {
+            return;
+        };
+        let Some(msg_id) = usize::try_from(msg_num - 1)
+            .ok()
+            .and_then(|i| pane.msg_data.get(i))
+            .map(|m| m.msg_id)
+        else {
+            app.notify(&format!("No message #{}", msg_num));
+            return;
+        };
+
+        let expanded = pane.expanded_messages.insert(msg_id);
+        if !expanded {
+            pane.expanded_messages.remove(&msg_id);
+        }
+        pane.format_cache.clear();
+        app.notify(&format!(
+            "Message #{} {}",
+            msg_num,
+            if expanded { "expanded" } else { "collapsed" }
+        ));
+    }
+
+    /// Reverse the most recent destructive action. Deleted messages can't be
+    /// restored server-side, so their captured text is resent as a new message.
+    async fn handle_undo(app: &mut App, pane_idx: usize) -> Result<()> {
+        let Some(action) = app.undo_stack.pop() else {
+            app.notify("Nothing to undo");
+            return Ok(());
+        };
+
+        match action {
+            UndoAction::DeletedMessage { chat_id, text } => {
+                match app.telegram.send_message(chat_id, &text).await {
+                    Ok(_) => {
+                        if let Some(pane) = app.panes.get_mut(pane_idx) {
+                            pane.add_message("✓ Deleted message resent as a new message".to_string());
+                        }
+                        app.notify("Undo: resent deleted message");
+                    }
+                    Err(e) => app.notify(&format!("Undo failed: {}", e)),
+                }
+            }
+            UndoAction::AliasRemoved { user_id, alias } => {
+                app.aliases.insert(user_id, alias.clone());
+                app.aliases.save(&app.config)?;
+                app.refresh_all_pane_displays();
+                app.notify(&format!("Undo: alias restored ({})", alias));
+            }
+            UndoAction::FilterChanged {
+                pane_idx,
+                chat_id,
+                filter_type,
+                filter_value,
+            } => match app.panes.get_mut(pane_idx).filter(|p| p.chat_id == chat_id) {
+                Some(pane) => {
+                    pane.filter_type = filter_type;
+                    pane.filter_value = filter_value;
+                    pane.format_cache.clear();
+                    app.notify("Undo: filter restored");
+                }
+                None => app.notify("Undo: pane no longer shows that chat"),
+            },
+            UndoAction::PaneCleared {
+                pane_idx,
+                chat_id,
+                msg_data,
+                messages,
+            } => match app.panes.get_mut(pane_idx).filter(|p| p.chat_id == chat_id) {
+                Some(pane) => {
+                    pane.msg_data = msg_data;
+                    pane.messages = messages;
+                    pane.format_cache.clear();
+                    app.notify("Undo: pane contents restored");
+                }
+                None => app.notify("Undo: pane no longer shows that chat"),
+            },
+        }
+
+        Ok(())
+    }
+
     async fn handle_filter(app: &mut App, cmd: &Command, pane_idx: usize) -> Result<()> {
         if cmd.args.is_empty() {
             if let Some(pane) = app.panes.get(pane_idx) {
@@ -461,74 +1058,55 @@ impl CommandHandler {
         Ok(())
     }
 
+    /// `/search <query>` shows the newest matches; `/search more` pages to older ones
     async fn handle_search(app: &mut App, cmd: &Command, pane_idx: usize) -> Result<()> {
         if cmd.args.is_empty() {
-            app.notify("Usage: /search <query> or /s <query>");
+            app.notify("Usage: /search <query> | /search more | /search off");
             return Ok(());
         }
 
-        let query = cmd.args.join(" ");
-
-        if let Some(pane) = app.panes.get(pane_idx) {
-            if pane.chat_id.is_none() {
-                app.notify("Select a chat first");
-                return Ok(());
+        if cmd.args.len() == 1 && cmd.args[0] == "off" {
+            if app.clear_search(pane_idx).await {
+                app.notify("Search cleared");
+            } else {
+                app.notify("No active search");
             }
+            return Ok(());
         }
 
-        let chat_id = app.panes.get(pane_idx).and_then(|p| p.chat_id);
-        if let Some(chat_id) = chat_id {
-            app.notify(&format!("Searching for '{}'...", query));
+        let Some(pane) = app.panes.get(pane_idx) else {
+            return Ok(());
+        };
+        let Some(chat_id) = pane.chat_id else {
+            app.notify("Select a chat first");
+            return Ok(());
+        };
 
-            match app.telegram.search_messages(chat_id, &query, 100).await {
-                Ok(results) => {
-                    let count = results.len();
-                    if count == 0 {
-                        app.notify("No results found");
-                    } else {
-                        // Convert to MessageData for proper formatting support
-                        let msg_data: Vec<crate::widgets::MessageData> = results
-                            .iter()
-                            .map(|(msg_id, sender_id, sender_name, text, reply_to_id, reactions)| {
-                                let reply_to_msg_id = *reply_to_id;
-                                
-                                crate::widgets::MessageData {
-                                    msg_id: *msg_id,
-                                    sender_id: *sender_id,
-                                    sender_name: sender_name.clone(),
-                                    text: text.clone(),
-                                    is_outgoing: *sender_id == app.my_user_id,
-                                    timestamp: chrono::Utc::now().timestamp(),
-                                    media_type: None,
-                                    media_label: None,
-                                    reactions: reactions.clone(),
-                                    reply_to_msg_id,
-                                    reply_sender: None,
-                                    reply_text: None,
-                                }
-                            })
-                            .collect();
+        let more = cmd.args.len() == 1 && cmd.args[0] == "more" && pane.search_query.is_some();
+        let (query, offset_id) = if more {
+            (pane.search_query.clone().unwrap_or_default(), pane.search_offset_id)
+        } else {
+            (cmd.args.join(" "), 0)
+        };
 
-                        if let Some(pane) = app.panes.get_mut(pane_idx) {
-                            pane.msg_data = msg_data;
-                            // Don't clear messages - they may contain status messages
-                            pane.chat_name = format!(
-                                "{} | Search: '{}' ({} results)",
-                                pane.chat_name.split(" | Search:").next().unwrap_or(&pane.chat_name),
-                                query,
-                                count
-                            );
-                            pane.scroll_offset = 0;
-                        }
-                        app.notify(&format!("Found {} results", count));
-                    }
-                }
-                Err(e) => {
-                    app.notify(&format!("Search failed: {}", e));
-                }
+        app.notify(&format!("Searching for '{}'...", query));
+
+        let page_len = match app.load_search_page(pane_idx, chat_id, &query, offset_id).await {
+            Ok(page_len) => page_len,
+            Err(e) => {
+                app.notify(&format!("Search failed: {}", e));
+                return Ok(());
             }
+        };
+
+        if page_len == 0 {
+            app.notify(if more { "No more results" } else { "No results found" });
+            return Ok(());
         }
 
+        let hint = if page_len >= SEARCH_PAGE_SIZE { " (/search more for older)" } else { "" };
+        app.notify(&format!("Found {} results{}", page_len, hint));
+
         Ok(())
     }
 
@@ -538,15 +1116,20 @@ impl CommandHandler {
             return Ok(());
         }
 
-        let username = &cmd.args[0];
-        app.notify(&format!("Looking up {}...", username));
+        // Accept both "/new foo" and "/new @foo"
+        let username = cmd.args[0].trim_start_matches('@');
+        if username.is_empty() {
+            app.notify("Usage: /new @username");
+            return Ok(());
+        }
+        app.notify(&format!("Looking up @{}...", username));
 
         match app.telegram.resolve_username(username).await {
             Ok(Some((chat_id, chat_name, _is_group))) => {
                 app.open_chat_in_pane(pane_idx, chat_id, &chat_name).await;
             }
             Ok(None) => {
-                app.notify(&format!("User '{}' not found", username));
+                app.notify(&format!("User @{} not found", username));
             }
             Err(e) => {
                 app.notify(&format!("Lookup failed: {}", e));
@@ -557,15 +1140,18 @@ impl CommandHandler {
     }
 
     async fn handle_new_group(app: &mut App, cmd: &Command, pane_idx: usize) -> Result<()> {
-        if cmd.args.is_empty() {
-            app.notify("Usage: /newgroup <name>");
+        // Words starting with @ are initial members, the rest is the title
+        let (members, title_words): (Vec<String>, Vec<String>) =
+            cmd.args.iter().cloned().partition(|arg| arg.starts_with('@'));
+        if title_words.is_empty() {
+            app.notify("Usage: /newgroup <name> [@user ...]");
             return Ok(());
         }
 
-        let group_name = cmd.args.join(" ");
+        let group_name = title_words.join(" ");
         app.notify(&format!("Creating group '{}'...", group_name));
 
-        match app.telegram.create_group(&group_name, vec![]).await {
+        match app.telegram.create_group(&group_name, &members).await {
             Ok(chat_id) => {
                 // Refresh chat list and open the new group
                 let _ = app.refresh_chats().await;
@@ -605,6 +1191,7 @@ impl CommandHandler {
             Ok(_) => {
                 if let Some(pane) = app.panes.get_mut(pane_idx) {
                     pane.add_message(format!("✓ Added {} to group", username));
+                    pane.members_cache = None;
                 }
                 app.notify(&format!("{} added to group", username));
             }
@@ -642,11 +1229,12 @@ impl CommandHandler {
         app.notify(&format!("Removing {}...", username));
 
         match app.telegram.remove_member(chat_id, username).await {
-            Ok(_) => {
+            Ok(name) => {
                 if let Some(pane) = app.panes.get_mut(pane_idx) {
-                    pane.add_message(format!("✓ Removed {} from group", username));
+                    pane.add_message(format!("✓ Removed {} ({}) from group", name, username));
+                    pane.members_cache = None;
                 }
-                app.notify(&format!("{} removed from group", username));
+                app.notify(&format!("{} removed from group", name));
             }
             Err(e) => {
                 app.notify(&format!("Failed to remove {}: {}", username, e));
@@ -656,7 +1244,8 @@ impl CommandHandler {
         Ok(())
     }
 
-    async fn handle_members(app: &mut App, _cmd: &Command, pane_idx: usize) -> Result<()> {
+    /// List the group's members; results are cached per pane, "/members refresh" reloads
+    async fn handle_members(app: &mut App, cmd: &Command, pane_idx: usize) -> Result<()> {
         let chat_id = if let Some(pane) = app.panes.get(pane_idx) {
             match pane.chat_id {
                 Some(id) => id,
@@ -669,36 +1258,452 @@ impl CommandHandler {
             return Ok(());
         };
 
-        app.notify("Loading members...");
+        if app.chats.iter().any(|c| c.id == chat_id && c.kind == ChatKind::User) {
+            app.notify("Not a group");
+            return Ok(());
+        }
+
+        let refresh = cmd.args.first().is_some_and(|a| a == "refresh");
+        let cached = app.panes[pane_idx].cached_members().map(<[_]>::to_vec);
+        let members = match cached.filter(|_| !refresh) {
+            Some(members) => members,
+            None => {
+                app.notify("Loading members...");
+                match app.telegram.get_participants(chat_id, MEMBERS_LIMIT).await {
+                    Ok(members) => {
+                        app.panes[pane_idx].members_cache = Some((chat_id, members.clone()));
+                        members
+                    }
+                    Err(e) => {
+                        app.notify(&format!("Failed to load members: {}", e));
+                        return Ok(());
+                    }
+                }
+            }
+        };
+
+        let pane = &mut app.panes[pane_idx];
+        pane.add_message(format!("--- Members ({}) ---", members.len()));
+        for (i, member) in members.iter().enumerate() {
+            match &member.username {
+                Some(username) => pane.add_message(format!("#{} {} @{}", i + 1, member.name, username)),
+                None => pane.add_message(format!("#{} {}", i + 1, member.name)),
+            }
+        }
+        pane.add_message("---".to_string());
+        app.notify(&format!("{} members", members.len()));
+
+        Ok(())
+    }
+
+    /// React to message #N: with an emoji it is sent directly, otherwise the picker opens
+    async fn handle_react(app: &mut App, cmd: &Command, pane_idx: usize) {
+        let Some(msg_num) = cmd.args.first().and_then(|a| parse_msg_num(a)).filter(|n| *n > 0) else {
+            app.notify("Usage: /react N [emoji]");
+            return;
+        };
+        let msg_num = msg_num as usize;
+
+        let target = app.panes.get(pane_idx).and_then(|pane| {
+            let msg_id = pane.msg_data.get(msg_num - 1)?.msg_id;
+            Some((pane.chat_id?, msg_id, pane.my_reaction(msg_id).is_some()))
+        });
+        let Some((chat_id, msg_id, reacted)) = target else {
+            app.notify(&format!("Message #{} not found", msg_num));
+            return;
+        };
+        match cmd.args.get(1) {
+            Some(emoji) => app.send_reaction(pane_idx, chat_id, msg_id, msg_num, Some(emoji)).await,
+            // Without an emoji, take back our reaction or pick one
+            None if reacted => app.send_reaction(pane_idx, chat_id, msg_id, msg_num, None).await,
+            None => app.open_reaction_picker(pane_idx, msg_num).await,
+        }
+    }
+
+    /// Send a poll: /poll "Question?" "Option A" "Option B"
+    async fn handle_poll(app: &mut App, cmd: &Command, pane_idx: usize) {
+        let Some((question, options)) = cmd.args.split_first() else {
+            app.notify("Usage: /poll \"Question?\" \"Option A\" \"Option B\" ...");
+            return;
+        };
+        let Some(chat_id) = app.panes.get(pane_idx).and_then(|p| p.chat_id) else {
+            app.notify("No chat selected");
+            return;
+        };
+        if !app.can_post_in(chat_id) {
+            app.notify("Read-only channel: you don't have permission to post here");
+            return;
+        }
+
+        match app.telegram.create_poll(chat_id, question, options).await {
+            Ok(()) => app.notify(&format!("Sent poll with {} options", options.len())),
+            Err(e) => app.notify(&format!("Poll failed: {}", e)),
+        }
+    }
+
+    /// Vote for option M of the poll in message #N
+    async fn handle_vote(app: &mut App, cmd: &Command, pane_idx: usize) {
+        let msg_num = cmd.args.first().and_then(|a| parse_msg_num(a)).filter(|n| *n > 0);
+        let option = cmd.args.get(1).and_then(|a| a.parse::<usize>().ok()).filter(|n| *n > 0);
+        let (Some(msg_num), Some(option)) = (msg_num, option) else {
+            app.notify("Usage: /vote N option");
+            return;
+        };
+        let msg_num = msg_num as usize;
+
+        let target = app.panes.get(pane_idx).and_then(|pane| {
+            let msg = pane.msg_data.get(msg_num - 1)?;
+            Some((pane.chat_id?, msg.msg_id, msg.media_type.as_deref() == Some("poll")))
+        });
+        match target {
+            Some((chat_id, msg_id, true)) => app.vote_poll(chat_id, msg_id, msg_num, option - 1).await,
+            Some(_) => app.notify(&format!("Message #{} is not a poll", msg_num)),
+            None => app.notify(&format!("Message #{} not found", msg_num)),
+        }
+    }
+
+    /// Copy message #N (or #N-M, one per line) to the clipboard
+    fn handle_copy(app: &mut App, cmd: &Command, pane_idx: usize) {
+        let Some((start, end)) = cmd.args.first().and_then(|a| parse_msg_range(a)) else {
+            app.notify("Usage: /copy N or /copy N-M");
+            return;
+        };
+        let Some(pane) = app.panes.get(pane_idx) else { return };
+        let Some(messages) = pane.msg_data.get(start - 1..end) else {
+            app.notify(&format!("Message #{} not found", end.min(pane.msg_data.len() + 1)));
+            return;
+        };
+        let text = messages
+            .iter()
+            .map(|m| match &m.media_type {
+                Some(media) if m.text.is_empty() => format!("[{}]", media),
+                _ => m.text.clone(),
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let label = if start == end { format!("#{}", start) } else { format!("#{}-{}", start, end) };
+        match app.copy_to_clipboard(&text) {
+            Ok(None) => app.notify(&format!("Copied {} to clipboard", label)),
+            Ok(Some(path)) => app.notify(&format!("No clipboard; saved {} to {}", label, path.display())),
+            Err(e) => app.notify(&format!("Copy failed: {}", e)),
+        }
+    }
+
+    /// Open the link in message #N; with several links, list them or open the Kth
+    fn handle_open(app: &mut App, cmd: &Command, pane_idx: usize) {
+        let Some(msg_num) = cmd.args.first().and_then(|a| parse_msg_num(a)).filter(|n| *n > 0) else {
+            app.notify("Usage: /open N [link number]");
+            return;
+        };
+        let Some(pane) = app.panes.get_mut(pane_idx) else { return };
+        let Some(msg) = pane.msg_data.get(msg_num as usize - 1) else {
+            app.notify(&format!("Message #{} not found", msg_num));
+            return;
+        };
+        let urls = crate::formatting::extract_urls(&msg.text);
+
+        let choice = match cmd.args.get(1) {
+            Some(arg) => match arg.parse::<usize>() {
+                Ok(k) if (1..=urls.len()).contains(&k) => Some(k - 1),
+                _ => {
+                    app.notify(&format!("#{} has {} link(s)", msg_num, urls.len()));
+                    return;
+                }
+            },
+            None if urls.len() == 1 => Some(0),
+            None => None,
+        };
+
+        match (choice, urls.len()) {
+            (_, 0) => app.notify(&format!("No links in #{}", msg_num)),
+            (Some(k), _) => {
+                open_with_system(&urls[k]);
+                app.notify(&format!("Opening {}", urls[k]));
+            }
+            (None, count) => {
+                pane.add_message(format!("--- Links in #{} ---", msg_num));
+                for (i, url) in urls.iter().enumerate() {
+                    pane.add_message(format!("{}. {}", i + 1, url));
+                }
+                app.notify(&format!("{} links; /open {} K opens link K", count, msg_num));
+            }
+        }
+    }
+
+    /// Scroll the pane so message #N is at the top of the view
+    fn handle_goto(app: &mut App, cmd: &Command, pane_idx: usize) {
+        let Some(msg_num) = cmd.args.first().and_then(|a| parse_msg_num(a)).filter(|n| *n > 0) else {
+            app.notify("Usage: /goto N");
+            return;
+        };
+        let msg_num = msg_num as usize;
+        let Some(pane) = app.panes.get_mut(pane_idx) else { return };
+        if msg_num > pane.msg_data.len() {
+            app.notify(&format!("Message #{} not found", msg_num));
+        } else if !pane.scroll_to_message(msg_num) {
+            app.notify(&format!("Message #{} is hidden by the current filter", msg_num));
+        }
+    }
+
+    /// Send a local file in the background, showing a placeholder until it's delivered
+    fn handle_upload(app: &mut App, cmd: &Command, pane_idx: usize) {
+        let Some(raw_path) = cmd.args.first() else {
+            app.notify("Usage: /upload <path> [caption]");
+            return;
+        };
+        let path = crate::utils::expand_tilde(raw_path);
+        if !path.is_file() {
+            app.notify(&format!("File not found: {}", path.display()));
+            return;
+        }
+        let caption = (cmd.args.len() > 1).then(|| cmd.text_from(1).to_string());
+        let Some(chat_id) = app.panes.get(pane_idx).and_then(|p| p.chat_id) else {
+            app.notify("No chat selected");
+            return;
+        };
+        if !app.can_post_in(chat_id) {
+            app.notify("Read-only channel: you don't have permission to post here");
+            return;
+        }
+
+        let file_name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_else(|| raw_path.clone());
+        let echo_text = match &caption {
+            Some(caption) => format!("📎 {}\n{}", file_name, caption),
+            None => format!("📎 {}", file_name),
+        };
+        let my_user_id = app.my_user_id;
+        let telegram = app.telegram.clone();
+        let Some(pane) = app.panes.get_mut(pane_idx) else { return };
+        pane.push_optimistic(my_user_id, echo_text.clone(), None);
+        pane.messages.clear();
+
+        let failed_text = echo_text.clone();
+        let progress = std::sync::Arc::new(std::sync::atomic::AtomicU8::new(0));
+        let upload_progress = progress.clone();
+        let handle = tokio::spawn(async move {
+            if let Err(e) = telegram.upload_file(chat_id, &path, caption.as_deref(), upload_progress).await {
+                telegram
+                    .queue_update(crate::telegram::TelegramUpdate::SendFailed {
+                        chat_id,
+                        text: failed_text,
+                        error: e.to_string(),
+                    })
+                    .await;
+            }
+        });
+        pane.track_send(format!("Uploading {}", file_name), echo_text, handle, Some(progress));
+    }
+
+    /// Toggle the pane between normal display and one-line-per-message log view
+    fn handle_log_view(app: &mut App, pane_idx: usize) {
+        let Some(pane) = app.panes.get_mut(pane_idx) else {
+            return;
+        };
+        pane.log_view = !pane.log_view;
+        pane.format_cache.clear();
+        let status = if pane.log_view { "ON" } else { "OFF" };
+        app.notify(&format!("Log view: {}", status));
+    }
+
+    /// Report when a user was last online (Telegram may only give a rough bucket)
+    async fn handle_seen(app: &mut App, cmd: &Command, pane_idx: usize) {
+        if cmd.args.is_empty() {
+            app.notify("Usage: /seen @username");
+            return;
+        }
+
+        let username = &cmd.args[0];
+        match app.telegram.lookup_user_status(username).await {
+            Ok((name, status)) => {
+                let status = status.describe(chrono::Utc::now().timestamp());
+                if let Some(pane) = app.panes.get_mut(pane_idx) {
+                    pane.add_message(format!("{} ({}): {}", name, username, status));
+                }
+                app.notify(&format!("{}: {}", name, status));
+            }
+            Err(e) => {
+                app.notify(&format!("Failed to get status of {}: {}", username, e));
+            }
+        }
+    }
+
+    async fn handle_pins(app: &mut App, _cmd: &Command, pane_idx: usize) -> Result<()> {
+        let chat_id = if let Some(pane) = app.panes.get(pane_idx) {
+            match pane.chat_id {
+                Some(id) => id,
+                None => {
+                    app.notify("No chat selected");
+                    return Ok(());
+                }
+            }
+        } else {
+            return Ok(());
+        };
+
+        app.notify("Loading pinned messages...");
 
-        match app.telegram.get_members(chat_id).await {
-            Ok(members) => {
+        match app.telegram.get_pinned_messages(chat_id).await {
+            Ok(pinned) => {
+                if pinned.is_empty() {
+                    app.notify("No pinned messages");
+                    return Ok(());
+                }
                 if let Some(pane) = app.panes.get_mut(pane_idx) {
-                    pane.add_message(format!("--- Members ({}) ---", members.len()));
-                    for (id, name, role) in &members {
-                        pane.add_message(format!("  {} (id:{}) - {}", name, id, role));
+                    pane.add_message(format!("--- Pinned ({}) ---", pinned.len()));
+                    for (msg_id, sender_name, text) in &pinned {
+                        let first_line = text.lines().next().unwrap_or("");
+                        // Show the display number if the message is in the loaded history
+                        let position = match pane.msg_data.iter().position(|m| m.msg_id == *msg_id) {
+                            Some(idx) => format!("#{}", idx + 1),
+                            None => format!("id:{}", msg_id),
+                        };
+                        pane.add_message(format!("  {} {}: {}", position, sender_name, first_line));
                     }
                     pane.add_message("---".to_string());
                 }
-                app.notify(&format!("{} members", members.len()));
+                app.notify(&format!("{} pinned messages", pinned.len()));
             }
             Err(e) => {
-                app.notify(&format!("Failed to load members: {}", e));
+                app.notify(&format!("Failed to load pinned messages: {}", e));
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn handle_me(app: &mut App, cmd: &Command, pane_idx: usize) -> Result<()> {
+        if cmd.args.is_empty() {
+            app.notify("Usage: /me <action>");
+            return Ok(());
+        }
+
+        let chat_id = match app.panes.get(pane_idx).and_then(|p| p.chat_id) {
+            Some(id) => id,
+            None => {
+                app.notify("No chat selected");
+                return Ok(());
             }
+        };
+        if !app.can_post_in(chat_id) {
+            app.notify("Read-only channel: you don't have permission to post here");
+            return Ok(());
+        }
+
+        // Telegram has no native /me, so send "* Name action" in italics
+        let text = format!("* {} {}", app.my_name, cmd.text_from(0));
+        let entities = vec![grammers_tl_types::enums::MessageEntity::Italic(
+            grammers_tl_types::types::MessageEntityItalic {
+                offset: 0,
+                length: text.encode_utf16().count() as i32,
+            },
+        )];
+
+        if let Err(e) = app.telegram.send_formatted_message(chat_id, &text, entities).await {
+            if let Some(pane) = app.panes.get_mut(pane_idx) {
+                pane.add_message(format!("✗ Send failed: {}", e));
+            }
+            app.notify(&format!("Send failed: {}", e));
         }
 
         Ok(())
     }
 
+    /// Silence or restore notifications for the pane's chat
+    async fn handle_mute(app: &mut App, pane_idx: usize, muted: bool) {
+        let Some(chat_id) = app.panes.get(pane_idx).and_then(|p| p.chat_id) else {
+            app.notify("No chat selected");
+            return;
+        };
+        // The local set applies even when Telegram can't be updated
+        app.muted_chats.set(chat_id, muted);
+        if let Err(e) = app.muted_chats.save(&app.config.muted_chats_path()) {
+            app.notify(&format!("Failed to save muted chats: {}", e));
+            return;
+        }
+        let synced = app.telegram.set_mute(chat_id, muted).await;
+        let name = match app.chats.iter_mut().find(|c| c.id == chat_id) {
+            Some(chat_info) => {
+                if synced.is_ok() {
+                    chat_info.muted = muted;
+                }
+                chat_info.name.clone()
+            }
+            None => "chat".to_string(),
+        };
+        let verb = if muted { "Muted" } else { "Unmuted" };
+        match synced {
+            Ok(()) => app.notify(&format!("{} {}", verb, name)),
+            Err(e) => app.notify(&format!("{} {} locally (Telegram not updated: {})", verb, name, e)),
+        }
+    }
+
+    /// Flag the pane's chat as unread on Telegram, to come back to it later
+    async fn handle_unread(app: &mut App, pane_idx: usize) {
+        let Some(chat_id) = app.panes.get(pane_idx).and_then(|p| p.chat_id) else {
+            app.notify("No chat selected");
+            return;
+        };
+        match app.telegram.mark_unread(chat_id).await {
+            Ok(()) => {
+                if let Some(chat_info) = app.chats.iter_mut().find(|c| c.id == chat_id) {
+                    chat_info.unread = chat_info.unread.max(1);
+                }
+                app.notify("Marked as unread");
+            }
+            Err(e) => app.notify(&format!("Failed to mark as unread: {}", e)),
+        }
+    }
+
+    /// Print diagnostic info (ids, paths, versions) into the pane. Read-only.
+    fn handle_debug(app: &mut App, pane_idx: usize) {
+        let config_dir = app.config.config_dir.display().to_string();
+        let my_user_id = app.my_user_id;
+        if let Some(pane) = app.panes.get_mut(pane_idx) {
+            pane.add_message("--- Debug info (diagnostic) ---".to_string());
+            match pane.chat_id {
+                Some(chat_id) => {
+                    pane.add_message(format!("  chat: {}", pane.chat_name));
+                    pane.add_message(format!("  chat_id (raw): {}", chat_id));
+                    pane.add_message(format!(
+                        "  chat_id (normalized): {}",
+                        crate::utils::normalize_chat_id(chat_id)
+                    ));
+                }
+                None => pane.add_message("  chat_id: none (no chat open)".to_string()),
+            }
+            pane.add_message(format!("  user_id: {}", my_user_id));
+            pane.add_message(format!("  config dir: {}", config_dir));
+            pane.add_message(format!(
+                "  version: {} {} (grammers-client {})",
+                env!("CARGO_PKG_NAME"),
+                env!("CARGO_PKG_VERSION"),
+                crate::telegram::GRAMMERS_VERSION
+            ));
+            pane.add_message("---".to_string());
+        }
+    }
+
+    fn handle_cancel(app: &mut App, pane_idx: usize) {
+        let cancelled = app.panes.get_mut(pane_idx).and_then(|p| p.cancel_last_send());
+        match cancelled {
+            Some(label) => app.notify(&format!("Cancelled: {}", label)),
+            None => app.notify("Nothing to cancel"),
+        }
+    }
+
     async fn handle_forward(app: &mut App, cmd: &Command, pane_idx: usize) -> Result<()> {
         if cmd.args.len() < 2 {
             app.notify("Usage: /forward N @username or /fwd N @username");
             return Ok(());
         }
 
-        let msg_num: i32 = match cmd.args[0].trim_start_matches('#').parse() {
-            Ok(n) => n,
-            Err(_) => {
+        let msg_num = match parse_msg_num(&cmd.args[0]) {
+            Some(n) => n,
+            None => {
                 app.notify("Usage: /forward N @username");
                 return Ok(());
             }
@@ -731,13 +1736,13 @@ impl CommandHandler {
 
         // Resolve target
         match app.telegram.resolve_username(target).await {
-            Ok(Some((to_chat_id, _name, _is_group))) => {
+            Ok(Some((to_chat_id, name, _is_group))) => {
                 match app.telegram.forward_message(from_chat_id, message_id, to_chat_id).await {
                     Ok(_) => {
                         if let Some(pane) = app.panes.get_mut(pane_idx) {
-                            pane.add_message(format!("✓ Forwarded #{} to {}", msg_num, target));
+                            pane.add_message(format!("✓ Forwarded #{} to {}", msg_num, name));
                         }
-                        app.notify(&format!("Forwarded to {}", target));
+                        app.notify(&format!("Forwarded #{} to {}", msg_num, name));
                     }
                     Err(e) => {
                         app.notify(&format!("Forward failed: {}", e));
@@ -745,7 +1750,7 @@ impl CommandHandler {
                 }
             }
             Ok(None) => {
-                app.notify(&format!("User '{}' not found", target));
+                app.notify(&format!("User {} not found", target));
             }
             Err(e) => {
                 app.notify(&format!("Lookup failed: {}", e));
@@ -755,3 +1760,127 @@ impl CommandHandler {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_message_at_uses_display_position() {
+        let msg = |msg_id| MessageData {
+            is_outgoing: true,
+            ..MessageData::test(msg_id, 1, "Alice", &format!("message {}", msg_id))
+        };
+        let data = vec![msg(1040), msg(1041), msg(1057)];
+
+        assert_eq!(message_at(&data, 1).map(|m| m.msg_id), Some(1040));
+        assert_eq!(message_at(&data, 3).map(|m| m.msg_id), Some(1057));
+        assert!(message_at(&data, 0).is_none());
+        assert!(message_at(&data, 4).is_none());
+        assert!(message_at(&data, -1).is_none());
+    }
+
+    #[test]
+    fn test_parse_basic() {
+        let cmd = Command::parse("/reply 3 hello there").unwrap();
+        assert_eq!(cmd.name, "reply");
+        assert_eq!(cmd.args, vec!["3", "hello", "there"]);
+    }
+
+    #[test]
+    fn test_parse_no_args() {
+        let cmd = Command::parse("/pins").unwrap();
+        assert_eq!(cmd.name, "pins");
+        assert!(cmd.args.is_empty());
+    }
+
+    #[test]
+    fn test_parse_whitespace() {
+        // Extra and trailing whitespace never produces empty args
+        let cmd = Command::parse("/filter   photo  \t").unwrap();
+        assert_eq!(cmd.name, "filter");
+        assert_eq!(cmd.args, vec!["photo"]);
+
+        // Leading whitespace means it's not a command
+        assert!(Command::parse(" /reply 3").is_none());
+        assert!(Command::parse("hello /reply").is_none());
+    }
+
+    #[test]
+    fn test_parse_bare_slash() {
+        let cmd = Command::parse("/").unwrap();
+        assert_eq!(cmd.name, "");
+        assert!(cmd.args.is_empty());
+    }
+
+    #[test]
+    fn test_parse_unicode_args() {
+        let cmd = Command::parse("/alias 2 Björn 🚀").unwrap();
+        assert_eq!(cmd.name, "alias");
+        assert_eq!(cmd.args, vec!["2", "Björn", "🚀"]);
+
+        let cmd = Command::parse("/é ü").unwrap();
+        assert_eq!(cmd.name, "é");
+        assert_eq!(cmd.args, vec!["ü"]);
+    }
+
+    #[test]
+    fn test_parse_quoted() {
+        let cmd = Command::parse(r#"/poll "a b" "c d""#).unwrap();
+        assert_eq!(cmd.name, "poll");
+        assert_eq!(cmd.args, vec!["a b", "c d"]);
+
+        let cmd = Command::parse(r#"/poll "it's" "" "say \"hi\"" x\ y"#).unwrap();
+        assert_eq!(cmd.args, vec!["it's", "", "say \"hi\"", "x y"]);
+
+        // Quotes inside a word, and other backslashes, are plain text
+        let cmd = Command::parse(r#"/me doesn't \o/"#).unwrap();
+        assert_eq!(cmd.args, vec!["doesn't", "\\o/"]);
+    }
+
+    #[test]
+    fn test_parse_unterminated_quote() {
+        // An unclosed quote is literal; later quotes still pair up
+        let cmd = Command::parse(r#"/poll "Lunch? A 'B C'"#).unwrap();
+        assert_eq!(cmd.args, vec!["\"Lunch?", "A", "B C"]);
+
+        let cmd = Command::parse("/away back at 5 o'clock 'ish").unwrap();
+        assert_eq!(cmd.args.last().map(String::as_str), Some("'ish"));
+    }
+
+    #[test]
+    fn test_text_from() {
+        let cmd = Command::parse("/reply 3  he said \"hi\"  ").unwrap();
+        assert_eq!(cmd.text_from(1), "he said \"hi\"");
+        assert_eq!(cmd.text_from(0), "3  he said \"hi\"");
+        assert_eq!(cmd.text_from(9), "");
+    }
+
+    #[test]
+    fn test_parse_msg_num() {
+        assert_eq!(parse_msg_num("5"), Some(5));
+        assert_eq!(parse_msg_num("#5"), Some(5));
+        assert_eq!(parse_msg_num("#"), None);
+        assert_eq!(parse_msg_num("abc"), None);
+        assert_eq!(parse_msg_num(""), None);
+    }
+
+    #[test]
+    fn test_parse_msg_range() {
+        assert_eq!(parse_msg_range("3"), Some((3, 3)));
+        assert_eq!(parse_msg_range("3-5"), Some((3, 5)));
+        assert_eq!(parse_msg_range("#3-#5"), Some((3, 5)));
+        assert_eq!(parse_msg_range("5-3"), None);
+        assert_eq!(parse_msg_range("0"), None);
+        assert_eq!(parse_msg_range("3-"), None);
+    }
+
+    #[test]
+    fn test_parse_dnd_window() {
+        assert_eq!(parse_dnd_window("22-7"), Some((22, 7)));
+        assert_eq!(parse_dnd_window("0-23"), Some((0, 23)));
+        assert_eq!(parse_dnd_window("22-24"), None);
+        assert_eq!(parse_dnd_window("22"), None);
+        assert_eq!(parse_dnd_window("on"), None);
+    }
+}
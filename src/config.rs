@@ -1,5 +1,6 @@
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fs;
 use std::path::PathBuf;
 
@@ -11,9 +12,35 @@ pub struct Config {
     
     #[serde(default)]
     pub settings: Settings,
+
+    #[serde(default)]
+    pub profiles: HashMap<String, Profile>, // Extra accounts, picked with --profile / /profile
     
     #[serde(skip)]
     pub config_dir: PathBuf,
+
+    #[serde(skip, default = "default_profile")]
+    pub profile: String, // Active profile; "default" uses the top-level credentials
+}
+
+/// Name of the profile backed by the top-level credentials and the original session file
+pub const DEFAULT_PROFILE: &str = "default";
+
+/// Upper bound for `message_fetch_limit`, whether set in the file or with /limit
+pub const MAX_FETCH_LIMIT: usize = 200;
+
+/// A named account. Unset API credentials fall back to the top-level ones; the
+/// phone number does not, since it identifies the account.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Profile {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_id: Option<i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_hash: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub phone_number: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_file: Option<String>, // Relative to the config directory
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -26,6 +53,9 @@ pub struct Settings {
     
     #[serde(default)]
     pub compact_mode: bool,
+
+    #[serde(default)]
+    pub group_messages: bool, // Consecutive messages from one sender share a header
     
     #[serde(default = "default_true")]
     pub show_emojis: bool,
@@ -44,6 +74,45 @@ pub struct Settings {
 
     #[serde(default = "default_true")]
     pub show_chat_list: bool,
+
+    #[serde(default)]
+    pub focus_mode: bool,
+
+    #[serde(default = "default_message_alignment")]
+    pub message_alignment: String, // "color", "prefix" or "align"
+
+    #[serde(default = "default_notification_sound")]
+    pub notification_sound: String, // "off", "system" or a path to a sound file
+
+    #[serde(default = "default_collapse_lines")]
+    pub collapse_lines: usize, // Collapse messages longer than this many lines (0 = never)
+
+    #[serde(default = "default_max_panes")]
+    pub max_panes: usize, // Splits beyond this many panes are refused
+
+    #[serde(default = "default_message_fetch_limit")]
+    pub message_fetch_limit: usize, // Messages loaded when a chat is opened or refreshed (/limit)
+
+    #[serde(default)]
+    pub away_message: Option<String>, // Auto-reply to DMs while set (/away)
+
+    #[serde(default = "default_theme")]
+    pub theme: String, // Built-in color theme, see theme.rs
+
+    #[serde(default = "default_true")]
+    pub render_markdown: bool, // Style **bold**, *italic* and `code` in messages
+
+    #[serde(default)]
+    pub dnd: bool, // Quiet hours: no desktop notifications between dnd_start and dnd_end (/dnd)
+
+    #[serde(default = "default_dnd_start")]
+    pub dnd_start: u8, // Local hour the quiet window starts
+
+    #[serde(default = "default_dnd_end")]
+    pub dnd_end: u8, // Local hour it ends; may be earlier than dnd_start to cross midnight
+
+    #[serde(default)]
+    pub keybindings: HashMap<String, String>, // Action name -> key spec ("ctrl+q"), see keybindings.rs
 }
 
 impl Default for Settings {
@@ -52,12 +121,26 @@ impl Default for Settings {
             show_reactions: true,
             show_notifications: true,
             compact_mode: false,
+            group_messages: false,
             show_emojis: true,
             show_line_numbers: false,
             show_timestamps: true,
             show_user_colors: true,
             show_borders: true,
             show_chat_list: true,
+            focus_mode: false,
+            message_alignment: default_message_alignment(),
+            notification_sound: default_notification_sound(),
+            collapse_lines: default_collapse_lines(),
+            max_panes: default_max_panes(),
+            message_fetch_limit: default_message_fetch_limit(),
+            away_message: None,
+            theme: default_theme(),
+            render_markdown: true,
+            dnd: false,
+            dnd_start: default_dnd_start(),
+            dnd_end: default_dnd_end(),
+            keybindings: HashMap::new(),
         }
     }
 }
@@ -66,19 +149,145 @@ fn default_true() -> bool {
     true
 }
 
+fn default_message_alignment() -> String {
+    "color".to_string()
+}
+
+fn default_notification_sound() -> String {
+    "off".to_string()
+}
+
+fn default_collapse_lines() -> usize {
+    15
+}
+
+fn default_max_panes() -> usize {
+    8
+}
+
+fn default_message_fetch_limit() -> usize {
+    50
+}
+
+fn default_theme() -> String {
+    "default".to_string()
+}
+
+fn default_dnd_start() -> u8 {
+    22
+}
+
+fn default_dnd_end() -> u8 {
+    7
+}
+
+fn default_profile() -> String {
+    DEFAULT_PROFILE.to_string()
+}
+
+/// Credentials given outside the config file, for non-interactive setups. Each
+/// value comes from a CLI flag, else from the environment.
+#[derive(Debug, Default, PartialEq)]
+pub struct CredentialOverrides {
+    pub api_id: Option<i32>,
+    pub api_hash: Option<String>,
+    pub phone: Option<String>,
+    pub profile: Option<String>,
+}
+
+impl CredentialOverrides {
+    /// Read `--api-id`, `--api-hash`, `--phone` and `--profile` (as `--flag value` or
+    /// `--flag=value`) and TELEGRAM_API_ID, TELEGRAM_API_HASH, TELEGRAM_PHONE and TELEGRAM_PROFILE
+    pub fn from_sources(args: &[String], env: impl Fn(&str) -> Option<String>) -> Result<Self> {
+        let flag = |name: &str| -> Result<Option<String>> {
+            let prefix = format!("{}=", name);
+            for (i, arg) in args.iter().enumerate() {
+                if let Some(value) = arg.strip_prefix(&prefix) {
+                    return Ok(Some(value.to_string()));
+                }
+                if arg == name {
+                    return match args.get(i + 1) {
+                        Some(value) => Ok(Some(value.clone())),
+                        None => anyhow::bail!("{} needs a value", name),
+                    };
+                }
+            }
+            Ok(None)
+        };
+        let value = |name: &str, var: &str| -> Result<Option<String>> {
+            Ok(flag(name)?.or_else(|| env(var)).filter(|v| !v.trim().is_empty()))
+        };
+
+        let api_id = value("--api-id", "TELEGRAM_API_ID")?;
+        let api_hash = value("--api-hash", "TELEGRAM_API_HASH")?;
+        let phone = value("--phone", "TELEGRAM_PHONE")?;
+        let profile = value("--profile", "TELEGRAM_PROFILE")?;
+        Ok(Self {
+            api_id: api_id.map(|v| validate_api_id(v.trim())).transpose()?,
+            api_hash: api_hash.map(|v| validate_api_hash(v.trim())).transpose()?,
+            phone: phone.map(|v| validate_phone(v.trim())).transpose()?,
+            profile: profile.map(|v| validate_profile_name(v.trim())).transpose()?,
+        })
+    }
+
+    fn from_env_and_args() -> Result<Self> {
+        let args: Vec<String> = std::env::args().skip(1).collect();
+        Self::from_sources(&args, |var| std::env::var(var).ok())
+    }
+
+    /// Apply to a loaded config: select the profile (adding it if it is new) and
+    /// store the credentials in it. True if anything that is saved changed.
+    fn apply(&self, config: &mut Config) -> bool {
+        if let Some(profile) = &self.profile {
+            config.profile = profile.clone();
+        }
+        if config.profile == DEFAULT_PROFILE {
+            let before = (config.api_id, config.api_hash.clone(), config.phone_number.clone());
+            if let Some(api_id) = self.api_id {
+                config.api_id = api_id;
+            }
+            if let Some(api_hash) = &self.api_hash {
+                config.api_hash = api_hash.clone();
+            }
+            if let Some(phone) = &self.phone {
+                config.phone_number = Some(phone.clone());
+            }
+            return before != (config.api_id, config.api_hash.clone(), config.phone_number.clone());
+        }
+
+        let is_new = !config.profiles.contains_key(&config.profile);
+        let profile = config.profiles.entry(config.profile.clone()).or_default();
+        let before = profile.clone();
+        if let Some(api_id) = self.api_id {
+            profile.api_id = Some(api_id);
+        }
+        if let Some(api_hash) = &self.api_hash {
+            profile.api_hash = Some(api_hash.clone());
+        }
+        if let Some(phone) = &self.phone {
+            profile.phone_number = Some(phone.clone());
+        }
+        is_new || before != *profile
+    }
+}
+
 impl Config {
     pub fn load() -> Result<Self> {
         let config_dir = Self::get_config_dir();
         let config_path = config_dir.join("telegram_config.json");
+        let overrides = CredentialOverrides::from_env_and_args()?;
 
         if config_path.exists() {
             let content = fs::read_to_string(&config_path)?;
             let mut config: Config = serde_json::from_str(&content)?;
             config.config_dir = config_dir;
+            if overrides.apply(&mut config) {
+                config.save()?;
+            }
             Ok(config)
         } else {
             // Create new config
-            let config = Self::create_new(config_dir)?;
+            let config = Self::create_new(config_dir, overrides)?;
             Ok(config)
         }
     }
@@ -90,32 +299,36 @@ impl Config {
         Ok(())
     }
 
-    fn create_new(config_dir: PathBuf) -> Result<Self> {
+    /// First run: credentials not given by flags or the environment are asked for
+    fn create_new(config_dir: PathBuf, overrides: CredentialOverrides) -> Result<Self> {
         fs::create_dir_all(&config_dir)?;
 
-        println!("=== Telegram Client Setup ===");
-        println!("Get your API credentials from https://my.telegram.org");
-        
-        print!("Enter API ID: ");
-        use std::io::{self, Write};
-        io::stdout().flush()?;
-        let mut api_id_str = String::new();
-        io::stdin().read_line(&mut api_id_str)?;
-        let api_id: i32 = api_id_str.trim().parse()?;
+        if overrides.api_id.is_none() || overrides.api_hash.is_none() {
+            println!("=== Telegram Client Setup ===");
+            println!("Get your API credentials from https://my.telegram.org");
+        }
 
-        print!("Enter API Hash: ");
-        io::stdout().flush()?;
-        let mut api_hash = String::new();
-        io::stdin().read_line(&mut api_hash)?;
-        let api_hash = api_hash.trim().to_string();
+        let api_id = match overrides.api_id {
+            Some(api_id) => api_id,
+            None => prompt_until_valid("Enter API ID: ", validate_api_id)?,
+        };
+        let api_hash = match overrides.api_hash {
+            Some(api_hash) => api_hash,
+            None => prompt_until_valid("Enter API Hash: ", validate_api_hash)?,
+        };
 
-        let config = Config {
+        let mut config = Config {
             api_id,
             api_hash,
             phone_number: None,
             settings: Settings::default(),
+            profiles: HashMap::new(),
             config_dir,
+            profile: default_profile(),
         };
+        // The phone belongs to whichever profile was asked for
+        CredentialOverrides { phone: overrides.phone, profile: overrides.profile, ..Default::default() }
+            .apply(&mut config);
 
         config.save()?;
         Ok(config)
@@ -140,15 +353,255 @@ impl Config {
         }
     }
 
+    /// Session file of the active profile
     pub fn session_path(&self) -> PathBuf {
-        self.config_dir.join("telegram_session.session")
+        match self.profiles.get(&self.profile).and_then(|p| p.session_file.as_ref()) {
+            Some(file) => self.config_dir.join(file),
+            None if self.profile == DEFAULT_PROFILE => self.config_dir.join("telegram_session.session"),
+            None => self.config_dir.join(format!("telegram_session_{}.session", self.profile)),
+        }
+    }
+
+    /// API id, API hash and phone number of the active profile
+    pub fn credentials(&self) -> (i32, String, Option<String>) {
+        match self.profiles.get(&self.profile) {
+            Some(profile) if self.profile != DEFAULT_PROFILE => (
+                profile.api_id.unwrap_or(self.api_id),
+                profile.api_hash.clone().unwrap_or_else(|| self.api_hash.clone()),
+                profile.phone_number.clone(),
+            ),
+            _ => (self.api_id, self.api_hash.clone(), self.phone_number.clone()),
+        }
+    }
+
+    /// "default" followed by the configured profiles, sorted
+    pub fn profile_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self
+            .profiles
+            .keys()
+            .filter(|name| name.as_str() != DEFAULT_PROFILE)
+            .cloned()
+            .collect();
+        names.sort();
+        names.insert(0, default_profile());
+        names
+    }
+
+    /// Make `name` the active profile; it must be "default" or configured
+    pub fn select_profile(&mut self, name: &str) -> Result<()> {
+        if name != DEFAULT_PROFILE && !self.profiles.contains_key(name) {
+            anyhow::bail!("Unknown profile \"{}\" (available: {})", name, self.profile_names().join(", "));
+        }
+        self.profile = name.to_string();
+        Ok(())
+    }
+
+    /// State file `telegram_<name>.json`; profiles other than "default" get their
+    /// own copy, `telegram_<name>_<profile>.json`, next to it
+    fn state_path(&self, name: &str) -> PathBuf {
+        if self.profile == DEFAULT_PROFILE {
+            self.config_dir.join(format!("telegram_{}.json", name))
+        } else {
+            self.config_dir.join(format!("telegram_{}_{}.json", name, self.profile))
+        }
     }
 
     pub fn layout_path(&self) -> PathBuf {
-        self.config_dir.join("telegram_layout.json")
+        self.state_path("layout")
     }
 
     pub fn aliases_path(&self) -> PathBuf {
-        self.config_dir.join("telegram_aliases.json")
+        self.state_path("aliases")
+    }
+
+    pub fn chat_aliases_path(&self) -> PathBuf {
+        self.state_path("chat_aliases")
+    }
+
+    pub fn drafts_path(&self) -> PathBuf {
+        self.state_path("drafts")
+    }
+
+    pub fn scroll_positions_path(&self) -> PathBuf {
+        self.state_path("scroll")
+    }
+
+    pub fn pinned_chats_path(&self) -> PathBuf {
+        self.state_path("pinned")
+    }
+
+    pub fn muted_chats_path(&self) -> PathBuf {
+        self.state_path("muted")
+    }
+
+    pub fn input_history_path(&self) -> PathBuf {
+        self.state_path("history")
+    }
+
+    pub fn offline_queue_path(&self) -> PathBuf {
+        self.state_path("offline_queue")
+    }
+}
+
+/// Ask on stdin until `validate` accepts the answer, printing why it didn't
+pub fn prompt_until_valid<T>(prompt: &str, validate: impl Fn(&str) -> Result<T>) -> Result<T> {
+    use std::io::{self, Write};
+    loop {
+        print!("{}", prompt);
+        io::stdout().flush()?;
+        let mut input = String::new();
+        if io::stdin().read_line(&mut input)? == 0 {
+            anyhow::bail!("Input closed during setup");
+        }
+        match validate(input.trim()) {
+            Ok(value) => return Ok(value),
+            Err(e) => println!("{}", e),
+        }
+    }
+}
+
+pub fn validate_api_id(input: &str) -> Result<i32> {
+    match input.parse::<i32>() {
+        Ok(id) if id > 0 => Ok(id),
+        _ => anyhow::bail!("API ID must be a positive number (see https://my.telegram.org)"),
+    }
+}
+
+pub fn validate_api_hash(input: &str) -> Result<String> {
+    if input.len() == 32 && input.chars().all(|c| c.is_ascii_hexdigit()) {
+        Ok(input.to_lowercase())
+    } else {
+        anyhow::bail!("API hash must be 32 hexadecimal characters")
+    }
+}
+
+/// Accept "+" followed by 7-15 digits; spaces, dashes and parentheses are ignored
+pub fn validate_phone(input: &str) -> Result<String> {
+    let phone: String = input
+        .chars()
+        .filter(|c| !matches!(c, ' ' | '-' | '(' | ')'))
+        .collect();
+    let digits = phone.strip_prefix('+').unwrap_or("");
+    if (7..=15).contains(&digits.len()) && digits.chars().all(|c| c.is_ascii_digit()) {
+        Ok(phone)
+    } else {
+        anyhow::bail!("Phone number must be in international format, e.g. +15551234567")
+    }
+}
+
+/// Profile names end up in session file names: letters, digits, '-' and '_' only
+pub fn validate_profile_name(input: &str) -> Result<String> {
+    if !input.is_empty() && input.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+        Ok(input.to_string())
+    } else {
+        anyhow::bail!("Profile name may only contain letters, digits, '-' and '_'")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_api_id() {
+        assert_eq!(validate_api_id("123456").unwrap(), 123456);
+        assert!(validate_api_id("12a456").is_err());
+        assert!(validate_api_id("0").is_err());
+        assert!(validate_api_id("99999999999").is_err());
+    }
+
+    #[test]
+    fn test_validate_api_hash() {
+        let hash = "0123456789ABCDEF0123456789abcdef";
+        assert_eq!(validate_api_hash(hash).unwrap(), hash.to_lowercase());
+        assert!(validate_api_hash("0123456789abcdef").is_err());
+        assert!(validate_api_hash("0123456789abcdef0123456789abcdeg").is_err());
+    }
+
+    #[test]
+    fn test_credential_overrides() {
+        let args: Vec<String> = ["--api-id", "123456", "--api-hash=0123456789abcdef0123456789ABCDEF"]
+            .iter()
+            .map(|a| a.to_string())
+            .collect();
+        let env = |var: &str| match var {
+            "TELEGRAM_API_ID" => Some("999".to_string()),
+            "TELEGRAM_PHONE" => Some("+1 555 123 4567".to_string()),
+            _ => None,
+        };
+        // Flags win over the environment
+        let overrides = CredentialOverrides::from_sources(&args, env).unwrap();
+        assert_eq!(overrides.api_id, Some(123456));
+        assert_eq!(overrides.api_hash.as_deref(), Some("0123456789abcdef0123456789abcdef"));
+        assert_eq!(overrides.phone.as_deref(), Some("+15551234567"));
+
+        assert_eq!(CredentialOverrides::from_sources(&[], |_| None).unwrap(), CredentialOverrides::default());
+        assert!(CredentialOverrides::from_sources(&["--api-id".to_string()], |_| None).is_err());
+        assert!(CredentialOverrides::from_sources(&[], |_| Some("nope".to_string())).is_err());
+    }
+
+    #[test]
+    fn test_profiles() {
+        let mut config = Config {
+            api_id: 1,
+            api_hash: "a".repeat(32),
+            phone_number: Some("+15551234567".to_string()),
+            settings: Settings::default(),
+            profiles: HashMap::new(),
+            config_dir: PathBuf::from("/cfg"),
+            profile: default_profile(),
+        };
+        assert_eq!(config.session_path(), PathBuf::from("/cfg/telegram_session.session"));
+        assert!(config.select_profile("work").is_err());
+
+        // A new --profile is added; API credentials fall back, the phone does not
+        let overrides = CredentialOverrides::from_sources(&["--profile=work".to_string()], |_| None).unwrap();
+        assert!(overrides.apply(&mut config));
+        assert_eq!(config.profile, "work");
+        assert_eq!(config.session_path(), PathBuf::from("/cfg/telegram_session_work.session"));
+        assert_eq!(config.credentials(), (1, "a".repeat(32), None));
+        assert!(!overrides.apply(&mut config));
+
+        let phone = CredentialOverrides { phone: Some("+46701234567".to_string()), ..Default::default() };
+        assert!(phone.apply(&mut config));
+        assert_eq!(config.phone_number.as_deref(), Some("+15551234567"));
+        assert_eq!(config.credentials().2.as_deref(), Some("+46701234567"));
+
+        config.profiles.get_mut("work").unwrap().session_file = Some("work.session".to_string());
+        assert_eq!(config.session_path(), PathBuf::from("/cfg/work.session"));
+        assert_eq!(config.profile_names(), vec!["default", "work"]);
+        config.select_profile("default").unwrap();
+        assert_eq!(config.credentials().2.as_deref(), Some("+15551234567"));
+
+        assert!(CredentialOverrides::from_sources(&["--profile=../x".to_string()], |_| None).is_err());
+    }
+
+    #[test]
+    fn test_state_paths_per_profile() {
+        let mut config = Config {
+            api_id: 1,
+            api_hash: "a".repeat(32),
+            phone_number: None,
+            settings: Settings::default(),
+            profiles: HashMap::from([("work".to_string(), Profile::default())]),
+            config_dir: PathBuf::from("/cfg"),
+            profile: default_profile(),
+        };
+        assert_eq!(config.layout_path(), PathBuf::from("/cfg/telegram_layout.json"));
+        assert_eq!(config.muted_chats_path(), PathBuf::from("/cfg/telegram_muted.json"));
+
+        config.select_profile("work").unwrap();
+        assert_eq!(config.layout_path(), PathBuf::from("/cfg/telegram_layout_work.json"));
+        assert_eq!(config.drafts_path(), PathBuf::from("/cfg/telegram_drafts_work.json"));
+        assert_eq!(config.offline_queue_path(), PathBuf::from("/cfg/telegram_offline_queue_work.json"));
+    }
+
+    #[test]
+    fn test_validate_phone() {
+        assert_eq!(validate_phone("+1 (555) 123-4567").unwrap(), "+15551234567");
+        assert_eq!(validate_phone("+46701234567").unwrap(), "+46701234567");
+        assert!(validate_phone("15551234567").is_err());
+        assert!(validate_phone("+1555").is_err());
+        assert!(validate_phone("+1555abc4567").is_err());
     }
 }
@@ -1,9 +1,39 @@
-use chrono::{DateTime, Local, TimeZone};
+use chrono::{DateTime, Datelike, Local, NaiveDate, TimeZone};
 use regex::Regex;
-use std::collections::HashMap;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
 
 use crate::widgets::MessageData;
 
+/// How outgoing messages are told apart from incoming ones (besides color)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MessageAlignment {
+    #[default]
+    Color,  // Color only
+    Prefix, // "→ " for outgoing, "← " for incoming
+    Align,  // Outgoing messages right-aligned
+}
+
+impl MessageAlignment {
+    pub fn from_setting(value: &str) -> Self {
+        match value {
+            "prefix" => MessageAlignment::Prefix,
+            "align" => MessageAlignment::Align,
+            _ => MessageAlignment::Color,
+        }
+    }
+
+    pub fn as_setting(&self) -> &'static str {
+        match self {
+            MessageAlignment::Color => "color",
+            MessageAlignment::Prefix => "prefix",
+            MessageAlignment::Align => "align",
+        }
+    }
+}
+
 /// Extract YouTube video ID from a URL
 #[cfg(test)]
 fn extract_youtube_id(url: &str) -> Option<String> {
@@ -34,8 +64,12 @@ pub fn format_reactions(reactions: &HashMap<String, u32>) -> String {
         return String::new();
     }
 
+    // HashMap order changes between calls; sort by count descending, then emoji, so it doesn't jitter
+    let mut sorted: Vec<(&String, &u32)> = reactions.iter().collect();
+    sorted.sort_by(|a, b| b.1.cmp(a.1).then_with(|| a.0.cmp(b.0)));
+
     let mut parts: Vec<String> = Vec::new();
-    for (emoji, count) in reactions.iter() {
+    for (emoji, count) in sorted {
         if *count > 1 {
             parts.push(format!("{}x{}", count, emoji));
         } else {
@@ -46,8 +80,21 @@ pub fn format_reactions(reactions: &HashMap<String, u32>) -> String {
     parts.join(" ")
 }
 
-/// Get media label for different types - matching Python's colored output
+/// Get media label for different types - matching Python's colored output.
+/// `title` is the link title for youtube/spotify, or size/duration details for
+/// files ("📷 Photo 1280×720", "📄 report.pdf (2.3 MB)"); files without details
+/// get the plain type label
 pub fn get_media_label(media_type: &str, title: Option<&str>) -> String {
+    if let Some(details) = title.filter(|t| !t.is_empty()) {
+        match media_type {
+            "photo" => return format!("📷 Photo {}", details),
+            "video" => return format!("🎬 Video {}", details),
+            "audio" => return format!("🎵 Audio {}", details),
+            "voice" => return format!("🎤 Voice {}", details),
+            "document" => return format!("📄 {}", details),
+            _ => {}
+        }
+    }
     match media_type {
         "youtube" => {
             if let Some(t) = title {
@@ -80,6 +127,105 @@ pub fn get_media_label(media_type: &str, title: Option<&str>) -> String {
     }
 }
 
+/// Human-readable byte count: "512 B", "14.2 KB", "2.3 MB"
+pub fn format_file_size(bytes: i64) -> String {
+    const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", size, UNITS[unit])
+}
+
+/// Audio/video length as "0:42", "3:05" or "1:02:03"
+pub fn format_duration(secs: i64) -> String {
+    let secs = secs.max(0);
+    let (hours, minutes, seconds) = (secs / 3600, secs / 60 % 60, secs % 60);
+    if hours > 0 {
+        format!("{}:{:02}:{:02}", hours, minutes, seconds)
+    } else {
+        format!("{}:{:02}", minutes, seconds)
+    }
+}
+
+/// Case-insensitive matcher for any word of a search query, for highlighting results
+pub fn search_regex(query: &str) -> Option<Regex> {
+    let words: Vec<String> = query.split_whitespace().map(regex::escape).collect();
+    if words.is_empty() {
+        return None;
+    }
+    Regex::new(&format!("(?i){}", words.join("|"))).ok()
+}
+
+/// Find `re` in the text of a line made of differently styled `pieces` and cut
+/// each piece where a match starts or ends, flagging the parts inside a match.
+/// Matching runs over the joined text, so a match may straddle pieces (e.g. a
+/// bold word and the plain text after it).
+pub fn split_matches(pieces: &[&str], re: &Regex) -> Vec<Vec<(String, bool)>> {
+    let text: String = pieces.concat();
+    let matches: Vec<(usize, usize)> = re
+        .find_iter(&text)
+        .filter(|m| !m.is_empty())
+        .map(|m| (m.start(), m.end()))
+        .collect();
+
+    let mut offset = 0;
+    pieces
+        .iter()
+        .map(|piece| {
+            let (start, end) = (offset, offset + piece.len());
+            offset = end;
+            // Cut points inside this piece, then the parts between them
+            let mut cuts = vec![start, end];
+            for &(m_start, m_end) in &matches {
+                cuts.extend([m_start, m_end].into_iter().filter(|&c| c > start && c < end));
+            }
+            cuts.sort_unstable();
+            cuts.dedup();
+            cuts.windows(2)
+                .map(|w| {
+                    let hit = matches.iter().any(|&(m_start, m_end)| w[0] >= m_start && w[1] <= m_end);
+                    (text[w[0]..w[1]].to_string(), hit)
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Flag the parts of styled `pieces` that `re` matches, keeping their other styles
+pub fn highlight_matches(pieces: Vec<(String, TextStyle)>, re: &Regex) -> Vec<(String, TextStyle)> {
+    let texts: Vec<&str> = pieces.iter().map(|(text, _)| text.as_str()).collect();
+    let split = split_matches(&texts, re);
+    pieces
+        .iter()
+        .zip(split)
+        .flat_map(|((_, style), parts)| {
+            parts.into_iter().map(move |(text, hit)| (text, TextStyle { matched: hit, ..*style }))
+        })
+        .collect()
+}
+
+/// http(s) links in text, in order, without trailing sentence punctuation
+pub fn extract_urls(text: &str) -> Vec<String> {
+    let url_regex = Regex::new(r"https?://[^\s]+").unwrap();
+    url_regex
+        .find_iter(text)
+        .map(|m| {
+            let mut url = m.as_str().trim_end_matches(['.', ',', ';', ':', '!', '?', '\'', '"']);
+            // "(see https://x.y)" - drop the closing paren unless the URL opened one
+            if url.ends_with(')') && !url.contains('(') {
+                url = &url[..url.len() - 1];
+            }
+            url.to_string()
+        })
+        .collect()
+}
+
 /// Shorten long URLs in text by truncating
 pub fn shorten_urls(text: &str, max_len: usize) -> String {
     let url_regex = Regex::new(r"https?://[^\s]+").unwrap();
@@ -101,6 +247,9 @@ pub fn shorten_urls(text: &str, max_len: usize) -> String {
     result
 }
 
+/// Columns of the original message quoted above a reply
+const REPLY_PREVIEW_WIDTH: usize = 50;
+
 /// Strip emojis from text (if emoji display is disabled)
 pub fn strip_emojis(text: &str) -> String {
     let emoji_regex = Regex::new(
@@ -109,6 +258,330 @@ pub fn strip_emojis(text: &str) -> String {
     emoji_regex.replace_all(text, "").to_string()
 }
 
+/// First line of a replied-to message, cut short for the "↳ Sender: text" quote
+fn reply_preview(text: &str, show_emojis: bool) -> String {
+    let text = if show_emojis { text.to_string() } else { strip_emojis(text) };
+    truncate_to_width(text.lines().next().unwrap_or(""), REPLY_PREVIEW_WIDTH)
+}
+
+/// Word-wrap text into lines of at most `max_width` terminal columns. Words wider
+/// than the width are chunked into full-width pieces on char boundaries.
+pub fn wrap_plain_text(text: &str, max_width: usize) -> Vec<String> {
+    if max_width == 0 || text.width() <= max_width {
+        return vec![text.to_string()];
+    }
+
+    let mut lines = Vec::new();
+    let mut current_line = String::new();
+    let mut current_len = 0;
+
+    for word in text.split_whitespace() {
+        let word_len = word.width();
+        let needed = if current_len == 0 { word_len } else { current_len + 1 + word_len };
+        if needed <= max_width {
+            if current_len > 0 {
+                current_line.push(' ');
+            }
+            current_line.push_str(word);
+            current_len = needed;
+            continue;
+        }
+
+        if current_len > 0 {
+            lines.push(std::mem::take(&mut current_line));
+        }
+
+        if word_len <= max_width {
+            current_line.push_str(word);
+            current_len = word_len;
+            continue;
+        }
+
+        // Oversized word: emit full-width chunks, keep the remainder as the current line
+        let mut chunks = split_at_width(word, max_width);
+        current_line = chunks.pop().unwrap_or_default();
+        current_len = current_line.width();
+        lines.extend(chunks);
+    }
+    if current_len > 0 {
+        lines.push(current_line);
+    }
+    lines
+}
+
+/// Cut a word into pieces of at most `max_width` columns. A wide char that would
+/// straddle the edge starts the next piece instead of overflowing.
+fn split_at_width(word: &str, max_width: usize) -> Vec<String> {
+    let mut chunks = Vec::new();
+    let mut chunk = String::new();
+    let mut chunk_width = 0;
+    for c in word.chars() {
+        let w = c.width().unwrap_or(0);
+        if chunk_width + w > max_width && !chunk.is_empty() {
+            chunks.push(std::mem::take(&mut chunk));
+            chunk_width = 0;
+        }
+        chunk.push(c);
+        chunk_width += w;
+    }
+    if !chunk.is_empty() {
+        chunks.push(chunk);
+    }
+    chunks
+}
+
+/// Rows the input box needs for `buf` at `width` columns, counting the block
+/// cursor drawn after the text
+pub fn input_line_count(buf: &str, width: usize) -> u16 {
+    if width == 0 {
+        return 1;
+    }
+    let rows = |columns: usize| columns.div_ceil(width).max(1);
+    let mut lines = 0;
+    let mut logical = buf.split('\n').peekable();
+    while let Some(line) = logical.next() {
+        let cursor = if logical.peek().is_none() { 1 } else { 0 };
+        lines += rows(line.width() + cursor);
+    }
+    lines.max(1) as u16
+}
+
+/// Inline styles of a piece of message text
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TextStyle {
+    pub bold: bool,
+    pub italic: bool,
+    pub code: bool,
+    pub matched: bool, // Part of a search match
+}
+
+/// Split `**bold**`, `*italic*` and `` `code` `` out of message text, markers removed.
+/// Bold and italic nest; code is literal. Markers that aren't closed, or that don't
+/// hug their content ("2 * 3 * 4"), stay as they are.
+pub fn parse_markdown(text: &str) -> Vec<(String, TextStyle)> {
+    let mut segments = Vec::new();
+    parse_markdown_into(text, TextStyle::default(), &mut segments);
+    segments
+}
+
+fn parse_markdown_into(text: &str, style: TextStyle, out: &mut Vec<(String, TextStyle)>) {
+    let mut literal_start = 0;
+    let mut i = 0;
+    while i < text.len() {
+        let rest = &text[i..];
+        // (marker length, end of the content within `rest`, style of the content)
+        let span = if let Some(code) = rest.strip_prefix('`') {
+            code.find('`')
+                .filter(|&len| len > 0)
+                .map(|len| (1, 1 + len, TextStyle { code: true, ..style }))
+        } else if rest.starts_with("**") {
+            closing_bold(rest).map(|end| (2, end, TextStyle { bold: true, ..style }))
+        } else if rest.starts_with('*') {
+            closing_italic(rest).map(|end| (1, end, TextStyle { italic: true, ..style }))
+        } else {
+            None
+        };
+
+        match span {
+            Some((marker, end, inner_style)) => {
+                push_markdown_segment(out, &text[literal_start..i], style);
+                let inner = &rest[marker..end];
+                if inner_style.code {
+                    push_markdown_segment(out, inner, inner_style);
+                } else {
+                    parse_markdown_into(inner, inner_style, out);
+                }
+                i += end + marker;
+                literal_start = i;
+            }
+            None => i += rest.chars().next().map_or(1, char::len_utf8),
+        }
+    }
+    push_markdown_segment(out, &text[literal_start..], style);
+}
+
+/// Content must start and end with a visible character
+fn hugs_markers(content: &str) -> bool {
+    !content.is_empty()
+        && !content.starts_with(char::is_whitespace)
+        && !content.ends_with(char::is_whitespace)
+}
+
+/// End of the content of a `**` span starting `rest`. In "***" the last two stars
+/// close it, so "**bold *italic***" keeps the italic's closing star.
+fn closing_bold(rest: &str) -> Option<usize> {
+    let mut end = 2 + rest[2..].find("**")?;
+    if rest[end..].starts_with("***") {
+        end += 1;
+    }
+    hugs_markers(&rest[2..end]).then_some(end)
+}
+
+/// End of the content of a `*` span starting `rest`, stepping over `**` pairs
+fn closing_italic(rest: &str) -> Option<usize> {
+    let bytes = rest.as_bytes();
+    let mut j = 1;
+    while j < bytes.len() {
+        if bytes[j] == b'*' {
+            if bytes.get(j + 1) == Some(&b'*') {
+                j += 2;
+                continue;
+            }
+            return hugs_markers(&rest[1..j]).then_some(j);
+        }
+        j += 1;
+    }
+    None
+}
+
+fn push_markdown_segment(out: &mut Vec<(String, TextStyle)>, text: &str, style: TextStyle) {
+    if text.is_empty() {
+        return;
+    }
+    match out.last_mut() {
+        Some((last, last_style)) if *last_style == style => last.push_str(text),
+        _ => out.push((text.to_string(), style)),
+    }
+}
+
+/// Style the characters of one wrapped line of parsed message text. `styled` holds
+/// every character of the message with its style and `pos` the next one to match,
+/// carried from line to line; wrapping only adds or drops whitespace.
+pub fn restyle_line(
+    line: &str,
+    styled: &[(char, TextStyle)],
+    pos: &mut usize,
+) -> Vec<(String, TextStyle)> {
+    let mut out = Vec::new();
+    // Leading whitespace is indentation from wrapping; spaces carry no visible style anyway
+    let body = line.trim_start();
+    push_markdown_segment(&mut out, &line[..line.len() - body.len()], TextStyle::default());
+    for c in body.chars() {
+        let skipped = styled[(*pos).min(styled.len())..]
+            .iter()
+            .position(|&(sc, _)| sc == c || !sc.is_whitespace())
+            .filter(|&skip| styled[*pos + skip].0 == c);
+        let style = match skipped {
+            Some(skip) => {
+                *pos += skip + 1;
+                styled[*pos - 1].1
+            }
+            // A space the wrap added, or text that isn't part of the message
+            None => TextStyle::default(),
+        };
+        let mut buf = [0; 4];
+        push_markdown_segment(&mut out, c.encode_utf8(&mut buf), style);
+    }
+    out
+}
+
+/// One screen line of message text, in differently styled pieces
+pub type StyledLine = Vec<(String, TextStyle)>;
+
+/// Wrap styled text to `width` columns after a header `header_width` columns wide,
+/// the way `wrap_plain_text` wraps plain text. Lines after the first start with
+/// `continuation` (e.g. spaces lining them up under the first). Always returns at
+/// least one line, so the header has something to go with.
+pub fn wrap_styled(pieces: &[(String, TextStyle)], header_width: usize, width: usize, continuation: &str) -> Vec<StyledLine> {
+    let styled: Vec<(char, TextStyle)> = pieces
+        .iter()
+        .flat_map(|(text, style)| text.chars().map(move |c| (c, *style)))
+        .collect();
+    let text: String = styled.iter().map(|(c, _)| c).collect();
+    let wrapped = if width == 0 {
+        vec![text]
+    } else if header_width >= width {
+        // No room after the header: it gets a line of its own
+        std::iter::once(String::new()).chain(wrap_plain_text(&text, width)).collect()
+    } else {
+        wrap_plain_text(&text, width - header_width)
+    };
+
+    let mut pos = 0;
+    let mut lines: Vec<StyledLine> = wrapped
+        .iter()
+        .enumerate()
+        .map(|(idx, line)| {
+            let mut styled_line = restyle_line(line, &styled, &mut pos);
+            if idx > 0 && !continuation.is_empty() {
+                styled_line.insert(0, (continuation.to_string(), TextStyle::default()));
+            }
+            styled_line
+        })
+        .collect();
+    if lines.is_empty() {
+        lines.push(Vec::new());
+    }
+    lines
+}
+
+/// Whether a word-bound segment is part of a word (not spaces or punctuation)
+fn is_word(segment: &str) -> bool {
+    segment.chars().any(char::is_alphanumeric)
+}
+
+/// Byte offset of the start of the word before `cursor` (Ctrl+Left, Alt+Backspace).
+/// Spaces and punctuation between the cursor and that word are skipped.
+pub fn prev_word_boundary(text: &str, cursor: usize) -> usize {
+    text[..cursor]
+        .split_word_bound_indices()
+        .rev()
+        .find(|(_, segment)| is_word(segment))
+        .map_or(0, |(idx, _)| idx)
+}
+
+/// Byte offset of the end of the word after `cursor` (Ctrl+Right)
+pub fn next_word_boundary(text: &str, cursor: usize) -> usize {
+    text[cursor..]
+        .split_word_bound_indices()
+        .find(|(_, segment)| is_word(segment))
+        .map_or(text.len(), |(idx, segment)| cursor + idx + segment.len())
+}
+
+/// Keep the first `max_lines` lines of wrapped text, pointing at /expand for the rest
+pub fn collapse_text(wrapped: &str, max_lines: usize, msg_num: usize) -> String {
+    let total = wrapped.lines().count();
+    if total <= max_lines {
+        return wrapped.to_string();
+    }
+    let kept: Vec<&str> = wrapped.lines().take(max_lines).collect();
+    format!(
+        "{} … ({} more lines, /expand {} to show)",
+        kept.join("\n"),
+        total - max_lines,
+        msg_num
+    )
+}
+
+/// Left bar marking lines inside a blockquote entity
+pub const QUOTE_PREFIX: &str = "▎ ";
+
+/// Prefix every line touched by a blockquote range with `QUOTE_PREFIX`.
+/// Ranges are (offset, length) in UTF-16 code units, as Telegram sends them.
+pub fn mark_blockquotes(text: &str, ranges: &[(usize, usize)]) -> String {
+    if ranges.is_empty() {
+        return text.to_string();
+    }
+
+    let mut result: Vec<String> = Vec::new();
+    let mut utf16_pos = 0;
+    for line in text.split('\n') {
+        let line_start = utf16_pos;
+        let line_end = line_start + line.encode_utf16().count();
+        utf16_pos = line_end + 1; // '\n'
+        let quoted = !line.is_empty()
+            && ranges
+                .iter()
+                .any(|&(offset, length)| offset < line_end && line_start < offset + length);
+        if quoted {
+            result.push(format!("{}{}", QUOTE_PREFIX, line));
+        } else {
+            result.push(line.to_string());
+        }
+    }
+    result.join("\n")
+}
+
 /// Wrap text to fit within a given width, with indent for continuation lines
 pub fn wrap_text(text: &str, indent: usize, width: usize) -> String {
     if width <= indent {
@@ -129,9 +602,8 @@ pub fn wrap_text(text: &str, indent: usize, width: usize) -> String {
         let first_line_of_para = i == 0;
 
         for word in &words {
-            // Handle very long words - use char_count instead of byte len
-            let word_char_count = word.chars().count();
-            if word_char_count > content_width {
+            // Handle very long words - measure display width, not bytes
+            if word.width() > content_width {
                 if !current_line.is_empty() {
                     if first_line_of_para && result_lines.is_empty() {
                         result_lines.push(current_line.clone());
@@ -141,23 +613,12 @@ pub fn wrap_text(text: &str, indent: usize, width: usize) -> String {
                     current_line.clear();
                 }
                 // Split word by character boundaries
-                let char_indices: Vec<(usize, char)> = word.char_indices().collect();
-                let mut char_pos = 0;
-                while char_pos < char_indices.len() {
-                    let chunk_end = (char_pos + content_width).min(char_indices.len());
-                    let byte_start = char_indices[char_pos].0;
-                    let byte_end = if chunk_end < char_indices.len() {
-                        char_indices[chunk_end].0
-                    } else {
-                        word.len()
-                    };
-                    let chunk = &word[byte_start..byte_end];
+                for chunk in split_at_width(word, content_width) {
                     if first_line_of_para && result_lines.is_empty() {
-                        result_lines.push(chunk.to_string());
+                        result_lines.push(chunk);
                     } else {
                         result_lines.push(format!("{}{}", pad, chunk));
                     }
-                    char_pos = chunk_end;
                 }
                 continue;
             }
@@ -168,7 +629,7 @@ pub fn wrap_text(text: &str, indent: usize, width: usize) -> String {
                 format!("{} {}", current_line, word)
             };
 
-            if test_line.chars().count() <= content_width {
+            if test_line.width() <= content_width {
                 current_line = test_line;
             } else {
                 if !current_line.is_empty() {
@@ -209,21 +670,138 @@ pub fn format_timestamp(timestamp: i64) -> String {
     }
 }
 
-/// Format all messages for a pane display - matching Python's _format_messages
+/// Line separating the messages read before the chat was opened from the unread ones
+pub const UNREAD_DIVIDER: &str = "──── unread messages ────";
+
+/// In grouped mode, messages from the same sender within this many seconds of the
+/// previous one are shown without repeating the sender
+pub const GROUP_WINDOW_SECS: i64 = 5 * 60;
+
+/// Centered "───── Monday, March 4 ─────" line marking the start of a new day.
+/// The year is only shown for dates outside the current year.
+pub fn format_day_separator(date: NaiveDate, width: usize) -> String {
+    let label = if date.year() == Local::now().year() {
+        date.format("%A, %B %-d").to_string()
+    } else {
+        date.format("%A, %B %-d, %Y").to_string()
+    };
+    let dashes = (width.saturating_sub(label.chars().count() + 2) / 2).clamp(3, 20);
+    let rule = "─".repeat(dashes);
+    format!("{} {} {}", rule, label, rule)
+}
+
+/// Cut text to at most `max_width` columns, marking the cut with "…"
+pub fn truncate_to_width(text: &str, max_width: usize) -> String {
+    if text.width() <= max_width {
+        return text.to_string();
+    }
+    if max_width == 0 {
+        return String::new();
+    }
+    let mut truncated = String::new();
+    let mut used = 0;
+    for c in text.chars() {
+        let w = c.width().unwrap_or(0);
+        if used + w > max_width - 1 {
+            break;
+        }
+        truncated.push(c);
+        used += w;
+    }
+    truncated.push('…');
+    truncated
+}
+
+/// Text of a display line that belongs to a message, styled and wrapped to the width
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Body {
+    pub header: String,         // What precedes it on the first line ("#3 Alice: ", "  ▎ ")
+    pub lines: Vec<StyledLine>, // The first continues the header; the rest carry their own indent
+}
+
+/// Display lines for a pane's messages, plus where each message begins
+#[derive(Clone, Default)]
+pub struct FormattedMessages {
+    pub lines: Vec<String>,
+    /// The message text of each line in `lines`, ready to draw; None for lines
+    /// that aren't message text (separators, reply quotes, blank lines)
+    pub bodies: Vec<Option<Body>>,
+    /// Index into `lines` of each message's first line (by position in `msg_data`);
+    /// None for messages that aren't shown, e.g. filtered out
+    pub message_starts: Vec<Option<usize>>,
+    /// Index into `lines` of the unread divider, if one is shown
+    pub unread_divider: Option<usize>,
+}
+
+/// Display settings for `format_messages_for_display`
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FormatOptions<'a> {
+    pub width: usize,
+    pub compact_mode: bool,
+    pub show_emojis: bool,
+    pub show_reactions: bool,
+    pub show_timestamps: bool,
+    pub show_line_numbers: bool,
+    pub filter_type: Option<&'a str>,
+    pub filter_value: Option<&'a str>,
+    pub first_unread: Option<i32>, // Unread divider goes above this message
+    pub alignment: MessageAlignment,
+    pub collapse_lines: usize,     // Long messages are cut to this many lines (0 = off)
+    pub log_view: bool,
+    pub grouped: bool,
+    pub render_markdown: bool, // Style **bold**, *italic* and `code` in message text
+    pub search_term: Option<&'a str>, // Matches are marked in message text
+}
+
+/// Format all messages for a pane display - matching Python's _format_messages.
+/// `aliases` renames senders; messages in `expanded` are never collapsed.
 pub fn format_messages_for_display(
     msg_data: &[MessageData],
-    width: usize,
-    compact_mode: bool,
-    show_emojis: bool,
-    show_reactions: bool,
-    show_timestamps: bool,
-    show_line_numbers: bool,
-    filter_type: Option<&str>,
-    filter_value: Option<&str>,
-    unread_count: u32,
     aliases: &HashMap<i64, String>,
-) -> Vec<String> {
+    expanded: &HashSet<i32>,
+    options: &FormatOptions,
+) -> FormattedMessages {
+    let FormatOptions {
+        width,
+        compact_mode,
+        show_emojis,
+        show_reactions,
+        show_timestamps,
+        show_line_numbers,
+        filter_type,
+        filter_value,
+        first_unread,
+        alignment,
+        collapse_lines,
+        log_view,
+        grouped,
+        render_markdown,
+        search_term,
+    } = *options;
     let mut lines: Vec<String> = Vec::new();
+    // Filled up to each line that gets a body; the rest are padded with None at the end
+    let mut bodies: Vec<Option<Body>> = Vec::new();
+    let mut message_starts = vec![None; msg_data.len()];
+
+    let search = search_term.and_then(search_regex);
+    let highlight = |pieces: Vec<(String, TextStyle)>| match &search {
+        Some(re) => highlight_matches(pieces, re),
+        None => pieces,
+    };
+
+    // Style and wrap the text that follows `header` on a message line
+    let message_body = |header: String, text: &str, right_align: bool| -> Body {
+        let pieces = highlight(if render_markdown {
+            parse_markdown(text)
+        } else {
+            vec![(text.to_string(), TextStyle::default())]
+        });
+        // Continuation indent makes no sense against the right edge
+        let continuation = if right_align { String::new() } else { " ".repeat(header.width()) };
+        let lines = wrap_styled(&pieces, header.width(), width, &continuation);
+        let header = if lines == [Vec::new()] { header.trim_end().to_string() } else { header };
+        Body { header, lines }
+    };
 
     // Show filter indicator if active
     if let Some(ft) = filter_type {
@@ -232,30 +810,69 @@ pub fn format_messages_for_display(
         lines.push(String::new());
     }
 
-    let unread_marker_idx = if unread_count > 0 {
-        msg_data.len().saturating_sub(unread_count as usize)
-    } else {
-        usize::MAX
-    };
+    let mut unread_divider = None;
+
+    let mut last_day: Option<NaiveDate> = None;
+    // (sender_id, is_outgoing, timestamp) of the last message shown, for grouping
+    let mut last_shown: Option<(i64, bool, i64)> = None;
 
     for (idx, data) in msg_data.iter().enumerate() {
-        // Show unread marker
-        if idx == unread_marker_idx && unread_count > 0 {
-            let marker = "-".repeat(width / 2);
-            lines.push(format!("{} {} unread {}", marker, unread_count, marker));
+        // Unread divider above the oldest message that was unread when the chat was opened
+        if first_unread == Some(data.msg_id) {
+            unread_divider = Some(lines.len());
+            lines.push(UNREAD_DIVIDER.to_string());
+            last_shown = None;
         }
 
-        let media_label = if let Some(ref media_type) = data.media_type {
-            get_media_label(media_type, None)
-        } else {
-            data.media_label.as_deref().unwrap_or("").to_string()
+        let media_label = data
+            .media_type
+            .as_deref()
+            .map(|media_type| get_media_label(media_type, data.media_label.as_deref()))
+            .unwrap_or_default();
+        // Quoted lines can't go through the word wrap without losing their structure,
+        // so everything from the first quote on is emitted as separate lines below
+        let marked = mark_blockquotes(&data.text, &data.quote_ranges);
+        let (mut text, trailing_lines) = match marked.find(QUOTE_PREFIX) {
+            Some(pos) if !data.quote_ranges.is_empty() && (pos == 0 || marked[..pos].ends_with('\n')) => (
+                marked[..pos].trim_end().to_string(),
+                marked[pos..].lines().collect::<Vec<_>>(),
+            ),
+            _ => (marked.clone(), Vec::new()),
         };
-        let mut text = data.text.clone();
 
-        if text.is_empty() && media_label.is_empty() {
+        if text.is_empty() && media_label.is_empty() && trailing_lines.is_empty() {
             continue;
         }
 
+        // Date separator whenever the day changes (including before the first message);
+        // like the times, hidden when timestamps are off
+        let day = Local
+            .timestamp_opt(data.timestamp, 0)
+            .single()
+            .map(|dt| dt.date_naive())
+            .filter(|day| show_timestamps && last_day != Some(*day));
+        if let Some(day) = day {
+            lines.push(format_day_separator(day, width));
+            last_day = Some(day);
+            last_shown = None;
+        }
+
+        // Grouped mode: a follow-up from the same sender drops the header and the
+        // blank line above it. Replies keep theirs so the quote stays attached.
+        let continues_group = grouped
+            && !log_view
+            && data.reply_to_msg_id.is_none()
+            && last_shown.is_some_and(|(sender_id, is_outgoing, timestamp)| {
+                sender_id == data.sender_id
+                    && is_outgoing == data.is_outgoing
+                    && data.timestamp - timestamp <= GROUP_WINDOW_SECS
+            });
+        last_shown = Some((data.sender_id, data.is_outgoing, data.timestamp));
+        if continues_group && !compact_mode && lines.last().is_some_and(|l| l.is_empty()) {
+            lines.pop();
+        }
+        message_starts[idx] = Some(lines.len());
+
         // Resolve sender name (use alias if available)
         let sender_name = aliases
             .get(&data.sender_id)
@@ -265,13 +882,47 @@ pub fn format_messages_for_display(
         let timestamp = format_timestamp(data.timestamp);
         let num_str = format!("#{}", idx + 1);
 
+        // Log view: exactly one "HH:MM name: text" line per message, cut to the width
+        if log_view {
+            let mut parts: Vec<String> = Vec::new();
+            if show_line_numbers {
+                parts.push(num_str);
+            }
+            parts.push(timestamp);
+            if alignment == MessageAlignment::Prefix {
+                parts.push(if data.is_outgoing { "→" } else { "←" }.to_string());
+            }
+            let mut flat = data.text.split_whitespace().collect::<Vec<_>>().join(" ");
+            flat = shorten_urls(&flat, 60);
+            if !show_emojis {
+                flat = strip_emojis(&flat);
+            }
+            if !media_label.is_empty() {
+                flat = format!("{} {}", media_label, flat).trim_end().to_string();
+            }
+            let used = parts.iter().map(|p| p.width() + 1).sum::<usize>()
+                + sender_name.width()
+                + 2;
+            let flat = truncate_to_width(&flat, width.saturating_sub(used));
+            let direction = if data.is_outgoing { "OUT" } else { "IN" };
+            let prefix = format!("{} ", parts.join(" "));
+            let right_align = data.is_outgoing && alignment == MessageAlignment::Align;
+            bodies.resize(lines.len(), None);
+            bodies.push(Some(message_body(format!("{}{}: ", prefix, sender_name), &flat, right_align)));
+            lines.push(format!("{}[{}]:{}:{}:{}", prefix, direction, data.sender_id, sender_name, flat));
+            continue;
+        }
+
         // Calculate prefix length for wrapping
-        let mut prefix_len = sender_name.len() + 2; // "name: "
+        let mut prefix_len = sender_name.width() + 2; // "name: "
         if show_line_numbers {
-            prefix_len += num_str.len() + 1; // "#N "
+            prefix_len += num_str.width() + 1; // "#N "
+        }
+        if alignment == MessageAlignment::Prefix {
+            prefix_len += 2; // "→ "
         }
         if show_timestamps {
-            prefix_len += timestamp.len() + 1; // "HH:MM "
+            prefix_len += timestamp.width() + 1; // "HH:MM "
         }
 
         // Process text
@@ -280,65 +931,43 @@ pub fn format_messages_for_display(
             if !show_emojis {
                 text = strip_emojis(&text);
             }
-            let wrapped = wrap_text(&text, prefix_len, width);
+            // The body is wrapped to the width later; wrapping here only counts the
+            // lines of a long message so it can be cut short
+            if collapse_lines > 0 && !expanded.contains(&data.msg_id) {
+                let wrapped = wrap_text(&text, prefix_len, width);
+                let collapsed = collapse_text(&wrapped, collapse_lines, idx + 1);
+                if collapsed != wrapped {
+                    text = collapsed;
+                }
+            }
             if !media_label.is_empty() {
-                text = format!("{} {}", media_label, wrapped);
-            } else {
-                text = wrapped;
+                text = format!("{} {}", media_label, text);
             }
         } else {
             text = media_label.to_string();
         }
 
-        // Handle reply info - show what message this is replying to
-        // Look up the actual message being replied to in msg_data
+        // Handle reply info - quote the message this is replying to
         if let Some(reply_to_id) = data.reply_to_msg_id {
-            // Try to find the message being replied to in our loaded messages
+            // Prefer the loaded message (it follows aliases and edits), then the resolved preview
             if let Some(original_msg) = msg_data.iter().find(|m| m.msg_id == reply_to_id) {
                 let reply_sender = aliases
                     .get(&original_msg.sender_id)
                     .cloned()
                     .unwrap_or_else(|| original_msg.sender_name.clone());
-                
-                let mut rt = original_msg.text.clone();
-                if !show_emojis {
-                    rt = strip_emojis(&rt);
-                }
-                // Get first line only and truncate if needed
-                let first_line = rt.lines().next().unwrap_or(&rt);
-                let display_text = if first_line.chars().count() > 50 {
-                    let truncate_at = first_line.char_indices().nth(50).map(|(i, _)| i).unwrap_or(first_line.len());
-                    format!("{}...", &first_line[..truncate_at])
-                } else {
-                    first_line.to_string()
-                };
                 // Add marker if replying to my own message
                 let reply_marker = if original_msg.is_outgoing {
                     "[REPLY_TO_ME] "
                 } else {
                     ""
                 };
-                lines.push(format!("{}  ↳ Reply to {}: {}", reply_marker, reply_sender, display_text));
+                let preview = reply_preview(&original_msg.text, show_emojis);
+                lines.push(format!("{}  ↳ {}: {}", reply_marker, reply_sender, preview));
+            } else if let (Some(reply_sender), Some(reply_text)) = (&data.reply_sender, &data.reply_text) {
+                lines.push(format!("  ↳ {}: {}", reply_sender, reply_preview(reply_text, show_emojis)));
             } else {
-                // Message not in our loaded history - show minimal info
-                // If we have cached reply info from Telegram, use it
-                if let (Some(reply_sender), Some(reply_text)) = (&data.reply_sender, &data.reply_text) {
-                    let mut rt = reply_text.clone();
-                    if !show_emojis {
-                        rt = strip_emojis(&rt);
-                    }
-                    let first_line = rt.lines().next().unwrap_or(&rt);
-                    let display_text = if first_line.chars().count() > 50 {
-                        let truncate_at = first_line.char_indices().nth(50).map(|(i, _)| i).unwrap_or(first_line.len());
-                        format!("{}...", &first_line[..truncate_at])
-                    } else {
-                        first_line.to_string()
-                    };
-                    lines.push(format!("  ↳ Reply to {}: {}", reply_sender, display_text));
-                } else {
-                    // No info available, just show message ID
-                    lines.push(format!("  ↳ Reply to message #{}", reply_to_id));
-                }
+                // Not resolved (yet), just show the message ID
+                lines.push(format!("  ↳ Reply to message #{}", reply_to_id));
             }
         }
 
@@ -353,32 +982,69 @@ pub fn format_messages_for_display(
         // Build message line
         let mut parts: Vec<String> = Vec::new();
 
-        if show_line_numbers {
+        if continues_group {
+            // Only the #N survives, padded so the text lines up under the previous message;
+            // the empty sender name tells the renderer there is no header to color
+            let number = if show_line_numbers { num_str.as_str() } else { "" };
+            parts.push(format!("{}{}", number, " ".repeat(prefix_len.saturating_sub(number.width()))));
+        }
+
+        if show_line_numbers && !continues_group {
             parts.push(num_str);
         }
-        if show_timestamps {
+        if show_timestamps && !continues_group {
             parts.push(timestamp);
         }
 
+        // Direction prefix so outgoing/incoming doesn't rely on color alone
+        if alignment == MessageAlignment::Prefix && !continues_group {
+            parts.push(if data.is_outgoing { "→" } else { "←" }.to_string());
+        }
+
         // Reply arrow if this was a reply
         if data.reply_to_msg_id.is_some() {
             parts.push("^".to_string());
         }
 
-        // Add sender name and message
-        // We use internal markers that will be parsed in app.rs for coloring
-        // Format: [OUT|IN]:sender_id:sender_name:message
-        let formatted_msg = if data.is_outgoing {
-            format!("[OUT]:{}:{}:{}", data.sender_id, sender_name, text)
+        // The padding already ends where the text starts
+        let prefix = if continues_group || parts.is_empty() {
+            parts.concat()
         } else {
-            format!("[IN]:{}:{}:{}", data.sender_id, sender_name, text)
+            format!("{} ", parts.join(" "))
         };
-        parts.push(formatted_msg);
 
-        let mut msg_line = parts.join(" ");
-        msg_line.push_str(&reactions_suffix);
+        text.push_str(&reactions_suffix);
+        if data.send_failed {
+            text.push_str(" ✗ not sent");
+        }
+
+        // Add sender name and message
+        // We use internal markers that will be parsed in app.rs for coloring
+        // Format: [OUT|IN]:sender_id:sender_name:message
+        let shown_name = if continues_group { "" } else { sender_name.as_str() };
+        let direction = if data.is_outgoing { "OUT" } else { "IN" };
+        let header = if shown_name.is_empty() { prefix.clone() } else { format!("{}{}: ", prefix, shown_name) };
+        let right_align = data.is_outgoing && alignment == MessageAlignment::Align;
+        bodies.resize(lines.len(), None);
+        bodies.push(Some(message_body(header, &text, right_align)));
+        lines.push(format!("{}[{}]:{}:{}:{}", prefix, direction, data.sender_id, shown_name, text));
 
-        lines.push(msg_line);
+        for line in trailing_lines {
+            if line.is_empty() {
+                continue;
+            }
+            let line = if show_emojis { line.to_string() } else { strip_emojis(line) };
+            // Quoted lines keep their bar on every wrapped line
+            let (header, quoted) = match line.strip_prefix(QUOTE_PREFIX) {
+                Some(quoted) => (format!("  {}", QUOTE_PREFIX), quoted),
+                None => ("  ".to_string(), line.as_str()),
+            };
+            let pieces = highlight(vec![(quoted.to_string(), TextStyle::default())]);
+            let body_lines = wrap_styled(&pieces, header.width(), width, &header);
+            bodies.resize(lines.len(), None);
+            lines.push(format!("{}{}", header, quoted));
+            bodies.push(Some(Body { header, lines: body_lines }));
+        }
 
         // Blank line between messages in non-compact mode
         if !compact_mode {
@@ -386,7 +1052,150 @@ pub fn format_messages_for_display(
         }
     }
 
-    lines
+    bodies.resize(lines.len(), None);
+    FormattedMessages {
+        lines,
+        bodies,
+        message_starts,
+        unread_divider,
+    }
+}
+
+/// File formats for /export
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Txt,  // "[time] Sender: text" lines
+    Json, // A list of `ExportRecord`s
+    Md,   // Markdown, each message in a quote block
+}
+
+impl ExportFormat {
+    pub fn from_arg(arg: &str) -> Option<Self> {
+        match arg.to_lowercase().as_str() {
+            "txt" | "text" => Some(ExportFormat::Txt),
+            "json" => Some(ExportFormat::Json),
+            "md" | "markdown" => Some(ExportFormat::Md),
+            _ => None,
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Txt => "txt",
+            ExportFormat::Json => "json",
+            ExportFormat::Md => "md",
+        }
+    }
+}
+
+/// One message as written by /export
+#[derive(Debug, Serialize)]
+struct ExportRecord<'a> {
+    id: i32,
+    time: String, // Local "YYYY-MM-DD HH:MM"
+    sender: &'a str,
+    text: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    media: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reply: Option<ExportReply<'a>>,
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    reactions: &'a HashMap<String, u32>,
+}
+
+#[derive(Debug, Serialize)]
+struct ExportReply<'a> {
+    id: i32,
+    sender: Option<&'a str>,
+    text: Option<&'a str>,
+}
+
+impl<'a> ExportRecord<'a> {
+    fn new(data: &'a MessageData) -> Self {
+        Self {
+            id: data.msg_id,
+            time: Local
+                .timestamp_opt(data.timestamp, 0)
+                .single()
+                .map(|dt| dt.format("%Y-%m-%d %H:%M").to_string())
+                .unwrap_or_default(),
+            sender: &data.sender_name,
+            text: &data.text,
+            media: data
+                .media_type
+                .as_ref()
+                .map(|media_type| get_media_label(media_type, data.media_label.as_deref())),
+            reply: data.reply_to_msg_id.map(|id| ExportReply {
+                id,
+                sender: data.reply_sender.as_deref(),
+                text: data.reply_text.as_deref(),
+            }),
+            reactions: &data.reactions,
+        }
+    }
+
+    /// Media label followed by the text
+    fn body(&self) -> String {
+        match &self.media {
+            Some(label) if self.text.is_empty() => label.clone(),
+            Some(label) => format!("{} {}", label, self.text),
+            None => self.text.to_string(),
+        }
+    }
+
+    fn reply_line(&self) -> Option<String> {
+        self.reply.as_ref().map(|reply| match (reply.sender, reply.text) {
+            (Some(sender), Some(text)) => format!("reply to #{} {}: {}", reply.id, sender, reply_preview(text, true)),
+            _ => format!("reply to #{}", reply.id),
+        })
+    }
+
+    fn to_txt(&self) -> String {
+        // Continuation lines are indented so each message still starts with "["
+        let mut out = format!("[{}] {}: {}\n", self.time, self.sender, self.body().replace('\n', "\n    "));
+        if let Some(reply) = self.reply_line() {
+            out.push_str(&format!("    ({})\n", reply));
+        }
+        let reactions = format_reactions(self.reactions);
+        if !reactions.is_empty() {
+            out.push_str(&format!("    reactions: {}\n", reactions));
+        }
+        out
+    }
+
+    fn to_md(&self) -> String {
+        let mut out = format!("**{}** · {}\n", self.sender, self.time);
+        if let Some(reply) = self.reply_line() {
+            out.push_str(&format!("> *{}*\n>\n", reply));
+        }
+        for line in self.body().lines() {
+            if line.is_empty() {
+                out.push_str(">\n");
+            } else {
+                out.push_str(&format!("> {}\n", line));
+            }
+        }
+        let reactions = format_reactions(self.reactions);
+        if !reactions.is_empty() {
+            out.push_str(&format!(">\n> {}\n", reactions));
+        }
+        out.push('\n');
+        out
+    }
+}
+
+/// Render messages (oldest first) for /export. Local echoes that never reached
+/// Telegram are left out.
+pub fn export_messages(msg_data: &[MessageData], format: ExportFormat) -> anyhow::Result<String> {
+    let records = msg_data
+        .iter()
+        .filter(|m| !m.pending && !m.send_failed)
+        .map(ExportRecord::new);
+    Ok(match format {
+        ExportFormat::Txt => records.map(|record| record.to_txt()).collect(),
+        ExportFormat::Json => serde_json::to_string_pretty(&records.collect::<Vec<_>>())?,
+        ExportFormat::Md => records.map(|record| record.to_md()).collect(),
+    })
 }
 
 #[cfg(test)]
@@ -417,6 +1226,25 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_media_label_details() {
+        assert_eq!(get_media_label("photo", Some("1280×720")), "📷 Photo 1280×720");
+        assert_eq!(get_media_label("document", Some("report.pdf (2.3 MB)")), "📄 report.pdf (2.3 MB)");
+        assert_eq!(get_media_label("photo", None), "[IMG]");
+        assert_eq!(get_media_label("document", Some("")), "[FILE]");
+        assert_eq!(get_media_label("sticker", Some("sticker.webp")), "[STICKER]");
+        assert_eq!(get_media_label("youtube", Some("Song")), "[YouTube: Song]");
+        assert_eq!(get_media_label("voice", None), "[VOICE]");
+
+        assert_eq!(format_file_size(512), "512 B");
+        assert_eq!(format_file_size(2_411_725), "2.3 MB");
+        assert_eq!(format_file_size(3 * 1024 * 1024 * 1024), "3.0 GB");
+
+        assert_eq!(format_duration(42), "0:42");
+        assert_eq!(format_duration(185), "3:05");
+        assert_eq!(format_duration(3723), "1:02:03");
+    }
+
     #[test]
     fn test_format_reactions() {
         let mut reactions = HashMap::new();
@@ -426,6 +1254,13 @@ mod tests {
         let result = format_reactions(&reactions);
         assert!(result.contains("5x👍") || result.contains("👍"));
         assert!(result.contains("❤️"));
+
+        // Stable order: count descending, ties broken by emoji
+        reactions.insert("🔥".to_string(), 5);
+        reactions.insert("😂".to_string(), 1);
+        let result = format_reactions(&reactions);
+        assert_eq!(result, "5x👍 5x🔥 ❤️ 😂");
+        assert_eq!(format_reactions(&reactions), result);
     }
 
     #[test]
@@ -442,6 +1277,320 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_wrap_plain_text_long_word() {
+        let word = "a".repeat(5000);
+        let lines = wrap_plain_text(&word, 80);
+        assert_eq!(lines.len(), 63); // 62 full lines + 40 chars
+        assert!(lines[..62].iter().all(|l| l.chars().count() == 80));
+        assert_eq!(lines[62].chars().count(), 40);
+
+        let lines = wrap_plain_text(&format!("hi {} there", "é".repeat(25)), 10);
+        assert_eq!(lines, vec!["hi", "éééééééééé", "éééééééééé", "ééééé", "there"]);
+    }
+
+    #[test]
+    fn test_collapse_text() {
+        let text = (1..=10).map(|i| format!("line {}", i)).collect::<Vec<_>>().join("\n");
+        assert_eq!(collapse_text(&text, 10, 1), text);
+        let collapsed = collapse_text(&text, 3, 7);
+        assert_eq!(collapsed, "line 1\nline 2\nline 3 … (7 more lines, /expand 7 to show)");
+    }
+
+    #[test]
+    fn test_blockquotes() {
+        // Quote on the middle line; "é" is one UTF-16 unit, "👋" is two
+        let text = "Said:\nhé 👋 there\nagreed";
+        let marked = mark_blockquotes(text, &[(6, 10)]);
+        assert_eq!(marked, "Said:\n▎ hé 👋 there\nagreed");
+        assert_eq!(mark_blockquotes(text, &[]), text);
+
+        let data = vec![MessageData {
+            quote_ranges: vec![(6, 10)],
+            ..MessageData::test(1, 1, "Alice", text)
+        }];
+        let lines: Vec<String> = format_messages_for_display(
+            &data,
+            &HashMap::new(),
+            &HashSet::new(),
+            &FormatOptions {
+                width: 80,
+                compact_mode: true,
+                show_emojis: true,
+                show_reactions: true,
+                ..Default::default()
+            },
+        )
+        .lines
+        .into_iter()
+        .filter(|l| !l.starts_with('─'))
+        .collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].ends_with(":Alice:Said:"));
+        assert_eq!(lines[1], "  ▎ hé 👋 there");
+        assert_eq!(lines[2], "  agreed");
+    }
+
+    #[test]
+    fn test_day_separators() {
+        let msg = |msg_id, timestamp| MessageData {
+            timestamp,
+            ..MessageData::test(msg_id, 1, "Alice", "hi")
+        };
+        let day1 = Local.with_ymd_and_hms(2024, 3, 4, 10, 0, 0).unwrap().timestamp();
+        let day2 = Local.with_ymd_and_hms(2024, 3, 5, 9, 0, 0).unwrap().timestamp();
+        let data = vec![msg(1, day1), msg(2, day1 + 60), msg(3, day2)];
+        let lines = format_messages_for_display(
+            &data,
+            &HashMap::new(),
+            &HashSet::new(),
+            &FormatOptions {
+                width: 60,
+                show_emojis: true,
+                show_reactions: true,
+                show_timestamps: true,
+                ..Default::default()
+            },
+        ).lines;
+        let separators: Vec<&String> = lines.iter().filter(|l| l.starts_with('─')).collect();
+        assert_eq!(separators.len(), 2);
+        assert!(separators[0].contains(" Monday, March 4, 2024 "));
+        assert!(separators[1].contains(" Tuesday, March 5, 2024 "));
+
+        // The first message after midnight opens a new day
+        let late = Local.with_ymd_and_hms(2024, 3, 4, 23, 59, 0).unwrap().timestamp();
+        let lines = format_messages_for_display(
+            &[msg(1, late), msg(2, late + 120)],
+            &HashMap::new(),
+            &HashSet::new(),
+            &FormatOptions {
+                width: 60,
+                show_emojis: true,
+                show_reactions: true,
+                show_timestamps: true,
+                ..Default::default()
+            },
+        ).lines;
+        let separators: Vec<&String> = lines.iter().filter(|l| l.starts_with('─')).collect();
+        assert_eq!(separators.len(), 2);
+        assert!(separators[1].contains("Tuesday, March 5"));
+
+        // No separators without timestamps
+        let lines = format_messages_for_display(
+            &data,
+            &HashMap::new(),
+            &HashSet::new(),
+            &FormatOptions {
+                width: 60,
+                show_emojis: true,
+                show_reactions: true,
+                ..Default::default()
+            },
+        ).lines;
+        assert!(!lines.iter().any(|l| l.starts_with('─')));
+
+        // Each message starts after its day separator; the blank lines between messages don't count
+        let formatted = format_messages_for_display(
+            &data,
+            &HashMap::new(),
+            &HashSet::new(),
+            &FormatOptions {
+                width: 60,
+                show_emojis: true,
+                show_reactions: true,
+                show_timestamps: true,
+                ..Default::default()
+            },
+        );
+        assert_eq!(formatted.message_starts, vec![Some(1), Some(3), Some(6)]);
+        assert!(formatted.lines[6].ends_with(":Alice:hi"));
+    }
+
+    #[test]
+    fn test_export_messages() {
+        let timestamp = Local.with_ymd_and_hms(2024, 3, 4, 10, 5, 0).unwrap().timestamp();
+        let msg = |msg_id, text: &str| MessageData {
+            timestamp,
+            ..MessageData::test(msg_id, 1, "Alice", text)
+        };
+        let mut reply = msg(2, "sure\n\nsee you");
+        reply.reply_to_msg_id = Some(1);
+        reply.reply_sender = Some("Bob".to_string());
+        reply.reply_text = Some("lunch?".to_string());
+        reply.reactions.insert("👍".to_string(), 2);
+        let mut unsent = msg(-1, "never sent");
+        unsent.pending = true;
+        let data = vec![msg(1, "hi"), reply, unsent];
+
+        let txt = export_messages(&data, ExportFormat::Txt).unwrap();
+        assert_eq!(
+            txt,
+            "[2024-03-04 10:05] Alice: hi\n\
+             [2024-03-04 10:05] Alice: sure\n    \n    see you\n\
+             \x20   (reply to #1 Bob: lunch?)\n\
+             \x20   reactions: 2x👍\n"
+        );
+
+        let md = export_messages(&data, ExportFormat::Md).unwrap();
+        assert!(md.contains("**Alice** · 2024-03-04 10:05\n> *reply to #1 Bob: lunch?*\n>\n> sure\n>\n> see you\n>\n> 2x👍\n"));
+
+        let json: serde_json::Value =
+            serde_json::from_str(&export_messages(&data, ExportFormat::Json).unwrap()).unwrap();
+        assert_eq!(json.as_array().unwrap().len(), 2);
+        assert_eq!(json[1]["id"], 2);
+        assert_eq!(json[1]["time"], "2024-03-04 10:05");
+        assert_eq!(json[1]["reply"]["id"], 1);
+        assert_eq!(json[1]["reply"]["sender"], "Bob");
+        assert!(json[0].get("reply").is_none());
+        assert_eq!(json[1]["reactions"]["👍"], 2);
+    }
+
+    #[test]
+    fn test_unread_divider_follows_message() {
+        let msg = |msg_id| MessageData {
+            timestamp: 1_700_000_000,
+            ..MessageData::test(msg_id, 1, "Alice", &format!("message {}", msg_id))
+        };
+        // Messages arriving after the chat was opened don't move the divider
+        let data = vec![msg(1), msg(2), msg(3), msg(4)];
+        let formatted = format_messages_for_display(
+            &data,
+            &HashMap::new(),
+            &HashSet::new(),
+            &FormatOptions {
+                width: 60,
+                compact_mode: true,
+                show_emojis: true,
+                show_reactions: true,
+                first_unread: Some(2),
+                ..Default::default()
+            },
+        );
+        let divider = formatted.unread_divider.unwrap();
+        assert_eq!(formatted.lines[divider], UNREAD_DIVIDER);
+        assert_eq!(formatted.message_starts[1], Some(divider + 1));
+        assert!(formatted.lines[divider - 1].ends_with("message 1"));
+
+        let formatted = format_messages_for_display(
+            &data,
+            &HashMap::new(),
+            &HashSet::new(),
+            &FormatOptions {
+                width: 60,
+                compact_mode: true,
+                show_emojis: true,
+                show_reactions: true,
+                ..Default::default()
+            },
+        );
+        assert_eq!(formatted.unread_divider, None);
+    }
+
+    #[test]
+    fn test_message_alignment_prefix() {
+        let msg = |msg_id, is_outgoing| MessageData {
+            is_outgoing,
+            ..MessageData::test(msg_id, msg_id as i64, "Alice", "hi")
+        };
+        let data = vec![msg(1, true), msg(2, false)];
+        let message_lines = |alignment| -> Vec<String> {
+            format_messages_for_display(
+                &data,
+                &HashMap::new(),
+                &HashSet::new(),
+                &FormatOptions {
+                    width: 80,
+                    compact_mode: true,
+                    show_emojis: true,
+                    show_reactions: true,
+                    alignment,
+                    ..Default::default()
+                },
+            )
+            .lines
+            .into_iter()
+            .filter(|l| !l.starts_with('─'))
+            .collect()
+        };
+        let lines = message_lines(MessageAlignment::Prefix);
+        assert!(lines[0].starts_with("→ [OUT]:"));
+        assert!(lines[1].starts_with("← [IN]:"));
+
+        let lines = message_lines(MessageAlignment::Color);
+        assert!(lines[0].starts_with("[OUT]:"));
+        assert_eq!(MessageAlignment::from_setting("align"), MessageAlignment::Align);
+        assert_eq!(MessageAlignment::from_setting("bogus"), MessageAlignment::Color);
+    }
+
+    #[test]
+    fn test_grouped_messages() {
+        let now = Local::now().timestamp();
+        let msg = |msg_id: i32, sender_id: i64, offset: i64, reply: Option<i32>| MessageData {
+            timestamp: now + offset,
+            reply_to_msg_id: reply,
+            ..MessageData::test(
+                msg_id,
+                sender_id,
+                if sender_id == 1 { "Alice" } else { "Bob" },
+                &format!("text {}", msg_id),
+            )
+        };
+        let data = vec![
+            msg(1, 1, -3600, None),
+            msg(2, 1, -3590, None),
+            msg(3, 1, -3580, Some(1)),
+            msg(4, 1, -3570, None),
+            msg(5, 1, 0, None),
+            msg(6, 2, 10, None),
+        ];
+        let formatted = format_messages_for_display(
+            &data,
+            &HashMap::new(),
+            &HashSet::new(),
+            &FormatOptions {
+                width: 80,
+                show_emojis: true,
+                show_reactions: true,
+                show_line_numbers: true,
+                grouped: true,
+                ..Default::default()
+            },
+        );
+        let line = |idx: usize| formatted.lines[formatted.message_starts[idx].unwrap()].as_str();
+        assert!(line(0).starts_with("#1 [IN]:1:Alice:"));
+        // Number kept, header replaced by padding as wide as "#2 Alice: "
+        assert_eq!(line(1), "#2        [IN]:1::text 2");
+        // Follow-ups sit right under the previous message, without a blank line
+        assert_eq!(formatted.message_starts[1], Some(formatted.message_starts[0].unwrap() + 1));
+        // A reply keeps its header; the next message joins its group
+        assert!(line(2).starts_with("  ↳ Alice: text 1"));
+        let reply_start = formatted.message_starts[2].unwrap();
+        assert!(formatted.lines[reply_start + 1].contains("[IN]:1:Alice:"));
+        assert_eq!(line(3), "#4        [IN]:1::text 4");
+        // Too long after the previous one, or another sender: a new group starts
+        assert!(line(4).contains("[IN]:1:Alice:"));
+        assert!(line(5).contains("[IN]:2:Bob:"));
+
+        // The padding matches the header's display width, not its byte length
+        let mut wide = vec![msg(1, 1, 0, None), msg(2, 1, 10, None)];
+        for m in &mut wide {
+            m.sender_name = "李明".to_string();
+        }
+        let formatted = format_messages_for_display(
+            &wide,
+            &HashMap::new(),
+            &HashSet::new(),
+            &FormatOptions {
+                width: 80,
+                show_line_numbers: true,
+                grouped: true,
+                ..Default::default()
+            },
+        );
+        // "#2 李明: " is nine columns wide
+        assert_eq!(formatted.lines[formatted.message_starts[1].unwrap()], "#2       [IN]:1::text 2");
+    }
+
     #[test]
     fn test_strip_emojis() {
         let text = "Hello 👋 World 🌍";
@@ -451,4 +1600,306 @@ mod tests {
         assert!(result.contains("Hello"));
         assert!(result.contains("World"));
     }
+
+    #[test]
+    fn test_log_view_one_line_per_message() {
+        let data = vec![MessageData {
+            timestamp: Local::now().timestamp(),
+            reply_to_msg_id: Some(99),
+            reply_sender: Some("Bob".to_string()),
+            reply_text: Some("earlier".to_string()),
+            ..MessageData::test(1, 7, "Alice", "first line\nsecond line that is long enough to need cutting")
+        }];
+        let lines: Vec<String> = format_messages_for_display(
+            &data,
+            &HashMap::new(),
+            &HashSet::new(),
+            &FormatOptions {
+                width: 40,
+                show_emojis: true,
+                show_reactions: true,
+                show_timestamps: true,
+                log_view: true,
+                ..Default::default()
+            },
+        )
+        .lines
+        .into_iter()
+        .filter(|l| !l.starts_with('─'))
+        .collect();
+
+        assert_eq!(lines.len(), 1);
+        let (prefix, text) = lines[0].split_once(":Alice:").unwrap();
+        assert!(prefix.ends_with("[IN]:7"));
+        assert!(text.starts_with("first line second line"));
+        assert!(text.ends_with('…'));
+        // "HH:MM " + "Alice: " + text fills the width exactly
+        assert_eq!(6 + 7 + text.chars().count(), 40);
+    }
+
+    #[test]
+    fn test_reply_quote_uses_resolved_preview() {
+        let reply = MessageData {
+            timestamp: Local::now().timestamp(),
+            reply_to_msg_id: Some(2),
+            reply_sender: Some("Alice".to_string()),
+            reply_text: Some("lunch tomorrow?\nsecond line".to_string()),
+            ..MessageData::test(5, 7, "Bob", "sure")
+        };
+        let lines = format_messages_for_display(
+            &[reply],
+            &HashMap::new(),
+            &HashSet::new(),
+            &FormatOptions {
+                width: 80,
+                show_emojis: true,
+                show_reactions: true,
+                show_timestamps: true,
+                ..Default::default()
+            },
+        ).lines;
+        assert!(lines.iter().any(|l| l == "  ↳ Alice: lunch tomorrow?"));
+    }
+
+    #[test]
+    fn test_truncate_to_width() {
+        assert_eq!(truncate_to_width("short", 10), "short");
+        assert_eq!(truncate_to_width("héllo world", 5), "héll…");
+        assert_eq!(truncate_to_width("abc", 0), "");
+    }
+
+    #[test]
+    fn test_wrap_by_display_width() {
+        // Each CJK char is two columns wide: 12 columns in total
+        let lines = wrap_plain_text("日本語テスト", 5);
+        assert_eq!(lines, vec!["日本", "語テ", "スト"]);
+        assert!(lines.iter().all(|l| l.width() <= 5));
+
+        // "café 🎉" is 7 columns (é is one, the emoji two)
+        assert_eq!(wrap_plain_text("café 🎉", 7), vec!["café 🎉"]);
+        assert_eq!(wrap_plain_text("café 🎉", 6), vec!["café", "🎉"]);
+
+        let wrapped = wrap_text("日本語テスト café 🎉", 2, 8);
+        assert!(wrapped.lines().all(|l| l.width() <= 8), "{:?}", wrapped);
+    }
+
+    #[test]
+    fn test_truncate_wide_chars() {
+        assert_eq!(truncate_to_width("日本語テスト", 6), "日本…");
+        assert_eq!(truncate_to_width("café 🎉 party", 7), "café …");
+    }
+
+    #[test]
+    fn test_input_line_count() {
+        // 12 columns + cursor at 10 columns per row
+        assert_eq!(input_line_count("日本語テスト", 10), 2);
+        // 7 columns + cursor fits in 8, not in 7
+        assert_eq!(input_line_count("café 🎉", 8), 1);
+        assert_eq!(input_line_count("café 🎉", 7), 2);
+        assert_eq!(input_line_count("café\n🎉", 10), 2);
+        assert_eq!(input_line_count("", 10), 1);
+    }
+
+    fn md(bold: bool, italic: bool, code: bool) -> TextStyle {
+        TextStyle { bold, italic, code, matched: false }
+    }
+
+    #[test]
+    fn test_parse_markdown() {
+        let plain = md(false, false, false);
+        assert_eq!(
+            parse_markdown("a **b** *c* `d`"),
+            vec![
+                ("a ".to_string(), plain),
+                ("b".to_string(), md(true, false, false)),
+                (" ".to_string(), plain),
+                ("c".to_string(), md(false, true, false)),
+                (" ".to_string(), plain),
+                ("d".to_string(), md(false, false, true)),
+            ]
+        );
+        // Nesting both ways, and code keeps its stars
+        assert_eq!(
+            parse_markdown("**bold *both***"),
+            vec![("bold ".to_string(), md(true, false, false)), ("both".to_string(), md(true, true, false))]
+        );
+        assert_eq!(
+            parse_markdown("*it **both** it*"),
+            vec![
+                ("it ".to_string(), md(false, true, false)),
+                ("both".to_string(), md(true, true, false)),
+                (" it".to_string(), md(false, true, false)),
+            ]
+        );
+        assert_eq!(parse_markdown("`a*b*c`"), vec![("a*b*c".to_string(), md(false, false, true))]);
+    }
+
+    #[test]
+    fn test_parse_markdown_unclosed_markers_stay_literal() {
+        let plain = md(false, false, false);
+        for text in ["**bold", "*italic", "`code", "2 * 3 * 4", "** spaced **", "****", "``"] {
+            assert_eq!(parse_markdown(text), vec![(text.to_string(), plain)], "{}", text);
+        }
+        // An unclosed marker doesn't stop later spans
+        assert_eq!(
+            parse_markdown("* not, *yes*"),
+            vec![("* not, ".to_string(), plain), ("yes".to_string(), md(false, true, false))]
+        );
+    }
+
+    #[test]
+    fn test_restyle_wrapped_lines() {
+        let styled: Vec<(char, TextStyle)> = parse_markdown("one **two three** four")
+            .iter()
+            .flat_map(|(text, style)| text.chars().map(move |c| (c, *style)))
+            .collect();
+        let bold = md(true, false, false);
+        let mut pos = 0;
+        // The wrap dropped the space after "two" and indented the next line
+        assert_eq!(
+            restyle_line("one two", &styled, &mut pos),
+            vec![("one ".to_string(), TextStyle::default()), ("two".to_string(), bold)]
+        );
+        assert_eq!(
+            restyle_line("  three four", &styled, &mut pos),
+            vec![
+                ("  ".to_string(), TextStyle::default()),
+                ("three".to_string(), bold),
+                (" four".to_string(), TextStyle::default()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_message_bodies_styled_and_wrapped() {
+        let data = vec![MessageData::test(1, 1, "Alice", "**hi** there friend")];
+        let format = |render_markdown| {
+            format_messages_for_display(
+                &data,
+                &HashMap::new(),
+                &HashSet::new(),
+                &FormatOptions {
+                    width: 20,
+                    compact_mode: true,
+                    show_emojis: true,
+                    render_markdown,
+                    ..Default::default()
+                },
+            )
+        };
+        let plain = |text: &str| (text.to_string(), TextStyle::default());
+
+        let formatted = format(true);
+        let start = formatted.message_starts[0].unwrap();
+        assert_eq!(formatted.bodies.len(), formatted.lines.len());
+        // Markers are gone before wrapping; the next line lines up under the text
+        assert_eq!(
+            formatted.bodies[start],
+            Some(Body {
+                header: "Alice: ".to_string(),
+                lines: vec![
+                    vec![("hi".to_string(), md(true, false, false)), plain(" there")],
+                    vec![plain("       "), plain("friend")],
+                ],
+            })
+        );
+
+        let formatted = format(false);
+        let body = formatted.bodies[formatted.message_starts[0].unwrap()].as_ref().unwrap();
+        assert_eq!(body.lines[0], vec![plain("**hi** there")]);
+        assert_eq!(formatted.bodies.iter().filter(|b| b.is_some()).count(), 1);
+    }
+
+    #[test]
+    fn test_search_highlights_message_text_only() {
+        let data = vec![MessageData::test(1, 1, "Alice", "abcdefghijklmnopqrstuvwxyz")];
+        let body = |search_term| {
+            let formatted = format_messages_for_display(
+                &data,
+                &HashMap::new(),
+                &HashSet::new(),
+                &FormatOptions {
+                    width: 17,
+                    compact_mode: true,
+                    show_line_numbers: true,
+                    search_term: Some(search_term),
+                    ..Default::default()
+                },
+            );
+            formatted.bodies[formatted.message_starts[0].unwrap()].clone().unwrap()
+        };
+        let piece = |text: &str, matched| (text.to_string(), TextStyle { matched, ..Default::default() });
+
+        // "#1 Alice: " leaves seven columns; the match keeps its highlight across the wrap
+        let hit = body("GHIJ");
+        assert_eq!(hit.header, "#1 Alice: ");
+        assert_eq!(hit.lines[0], vec![piece("abcdef", false), piece("g", true)]);
+        assert_eq!(hit.lines[1], vec![piece("          ", false), piece("hij", true), piece("klmn", false)]);
+
+        // The number and sender name aren't searched
+        assert!(body("alice 1").lines.iter().flatten().all(|(_, style)| !style.matched));
+    }
+
+    #[test]
+    fn test_word_boundaries() {
+        let text = "hello, world!  foo";
+        // Punctuation and runs of spaces are skipped over
+        assert_eq!(prev_word_boundary(text, text.len()), 15);
+        assert_eq!(prev_word_boundary(text, 15), 7);
+        assert_eq!(prev_word_boundary(text, 13), 7);
+        assert_eq!(prev_word_boundary(text, 7), 0);
+        assert_eq!(prev_word_boundary(text, 3), 0);
+        assert_eq!(prev_word_boundary(text, 0), 0);
+        assert_eq!(next_word_boundary(text, 0), 5);
+        assert_eq!(next_word_boundary(text, 2), 5);
+        assert_eq!(next_word_boundary(text, 5), 12);
+        assert_eq!(next_word_boundary(text, 12), text.len());
+        assert_eq!(next_word_boundary(text, text.len()), text.len());
+        assert_eq!(next_word_boundary("end...", 3), 6);
+
+        // Apostrophes stay inside words; offsets are bytes in multi-byte text
+        assert_eq!(next_word_boundary("don't stop", 0), 5);
+        assert_eq!(prev_word_boundary("привет мир", "привет мир".len()), "привет ".len());
+    }
+
+    #[test]
+    fn test_split_matches() {
+        let piece = |text: &str, hit: bool| (text.to_string(), hit);
+        // Several matches in one line, any case
+        let re = search_regex("lunch  PIZZA").unwrap();
+        assert_eq!(
+            split_matches(&["Pizza for Lunch?"], &re),
+            vec![vec![piece("Pizza", true), piece(" for ", false), piece("Lunch", true), piece("?", false)]]
+        );
+        assert_eq!(split_matches(&["nothing here"], &re), vec![vec![piece("nothing here", false)]]);
+
+        // A match straddling differently styled pieces is marked in each of them
+        let re = search_regex("bob:").unwrap();
+        assert_eq!(
+            split_matches(&["#3 ", "Bob", ": hi **there**"], &re),
+            vec![
+                vec![piece("#3 ", false)],
+                vec![piece("Bob", true)],
+                vec![piece(":", true), piece(" hi **there**", false)],
+            ]
+        );
+
+        // Regex syntax in the query is taken literally
+        let re = search_regex("a.b").unwrap();
+        assert_eq!(split_matches(&["axb a.b"], &re), vec![vec![piece("axb ", false), piece("a.b", true)]]);
+        assert!(search_regex("  ").is_none());
+    }
+
+    #[test]
+    fn test_extract_urls() {
+        assert_eq!(
+            extract_urls("see https://a.example/x, and (http://b.example/y)."),
+            vec!["https://a.example/x", "http://b.example/y"]
+        );
+        assert_eq!(
+            extract_urls("https://en.wikipedia.org/wiki/Rust_(programming_language)"),
+            vec!["https://en.wikipedia.org/wiki/Rust_(programming_language)"]
+        );
+        assert!(extract_urls("no links here, ftp://nope").is_empty());
+    }
 }
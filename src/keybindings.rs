@@ -0,0 +1,292 @@
+use anyhow::{bail, Result};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::collections::HashMap;
+
+/// Commands reachable through a remappable shortcut
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Quit,
+    RefreshChats,
+    SplitVertical,
+    SplitHorizontal,
+    ToggleSplitDirection,
+    GrowPane,
+    ShrinkPane,
+    SwapPanes,
+    ClosePane,
+    ToggleChatList,
+    ClearPane,
+    ToggleReactions,
+    ToggleNotifications,
+    ToggleCompact,
+    ToggleGrouping,
+    ToggleEmojis,
+    ToggleLineNumbers,
+    ToggleTimestamps,
+    ToggleUserColors,
+    ToggleBorders,
+    ToggleFocusMode,
+    ReactionPicker,
+    JumpToUnread,
+    FocusPrevPane,
+    FocusNextPane,
+    FocusLeft,
+    FocusDown,
+    FocusUp,
+    FocusRight,
+    ScrollLineUp,
+    ScrollLineDown,
+    ScrollToTop,
+    ScrollToBottom,
+    NextMatch,
+    PrevMatch,
+    WordLeft,
+    WordRight,
+}
+
+/// Action name used in the config file and its default key
+const ACTIONS: &[(Action, &str, &str)] = &[
+    (Action::Quit, "quit", "ctrl+q"),
+    (Action::RefreshChats, "refresh_chats", "ctrl+r"),
+    (Action::SplitVertical, "split_vertical", "ctrl+v"),
+    (Action::SplitHorizontal, "split_horizontal", "ctrl+b"),
+    (Action::ToggleSplitDirection, "toggle_split_direction", "ctrl+k"),
+    (Action::GrowPane, "grow_pane", "alt+>"),
+    (Action::ShrinkPane, "shrink_pane", "alt+<"),
+    (Action::SwapPanes, "swap_panes", "alt+s"),
+    (Action::ClosePane, "close_pane", "ctrl+w"),
+    (Action::ToggleChatList, "toggle_chat_list", "ctrl+s"),
+    (Action::ClearPane, "clear_pane", "ctrl+l"),
+    (Action::ToggleReactions, "toggle_reactions", "ctrl+e"),
+    (Action::ToggleNotifications, "toggle_notifications", "ctrl+n"),
+    (Action::ToggleCompact, "toggle_compact", "ctrl+d"),
+    (Action::ToggleGrouping, "toggle_grouping", "ctrl+p"),
+    (Action::ToggleEmojis, "toggle_emojis", "ctrl+o"),
+    (Action::ToggleLineNumbers, "toggle_line_numbers", "ctrl+g"),
+    (Action::ToggleTimestamps, "toggle_timestamps", "ctrl+t"),
+    (Action::ToggleUserColors, "toggle_user_colors", "ctrl+u"),
+    (Action::ToggleBorders, "toggle_borders", "ctrl+y"),
+    (Action::ToggleFocusMode, "toggle_focus_mode", "ctrl+f"),
+    (Action::ReactionPicker, "reaction_picker", "alt+r"),
+    (Action::JumpToUnread, "jump_to_unread", "alt+u"),
+    (Action::FocusPrevPane, "focus_prev_pane", "alt+left"),
+    (Action::FocusNextPane, "focus_next_pane", "alt+right"),
+    (Action::FocusLeft, "focus_left", "alt+h"),
+    (Action::FocusDown, "focus_down", "alt+j"),
+    (Action::FocusUp, "focus_up", "alt+k"),
+    (Action::FocusRight, "focus_right", "alt+l"),
+    (Action::ScrollLineUp, "scroll_line_up", "ctrl+up"),
+    (Action::ScrollLineDown, "scroll_line_down", "ctrl+down"),
+    (Action::ScrollToTop, "scroll_to_top", "ctrl+home"),
+    (Action::ScrollToBottom, "scroll_to_bottom", "ctrl+end"),
+    (Action::NextMatch, "next_match", "alt+n"),
+    (Action::PrevMatch, "prev_match", "alt+p"),
+    (Action::WordLeft, "word_left", "ctrl+left"),
+    (Action::WordRight, "word_right", "ctrl+right"),
+];
+
+/// Parse a key spec like "ctrl+q", "alt+left" or "f5". Letters are case-insensitive;
+/// plain characters need ctrl or alt so they don't swallow typing, and can't take
+/// shift (terminals report it as part of the character, not as a modifier).
+pub fn parse_key_spec(spec: &str) -> Result<(KeyCode, KeyModifiers)> {
+    let spec = spec.trim().to_lowercase();
+    let mut parts: Vec<&str> = spec.split('+').map(str::trim).collect();
+    // "ctrl++" binds the plus key
+    if spec.ends_with("++") {
+        parts.pop();
+        parts.pop();
+        parts.push("+");
+    }
+    let Some(key) = parts.pop().filter(|k| !k.is_empty()) else {
+        bail!("missing key in \"{}\"", spec);
+    };
+
+    let mut modifiers = KeyModifiers::NONE;
+    for part in parts {
+        modifiers |= match part {
+            "ctrl" | "control" => KeyModifiers::CONTROL,
+            "alt" | "meta" => KeyModifiers::ALT,
+            "shift" => KeyModifiers::SHIFT,
+            _ => bail!("unknown modifier \"{}\"", part),
+        };
+    }
+
+    let code = match key {
+        "up" => KeyCode::Up,
+        "down" => KeyCode::Down,
+        "left" => KeyCode::Left,
+        "right" => KeyCode::Right,
+        "home" => KeyCode::Home,
+        "end" => KeyCode::End,
+        "pageup" => KeyCode::PageUp,
+        "pagedown" => KeyCode::PageDown,
+        "delete" | "del" => KeyCode::Delete,
+        "insert" => KeyCode::Insert,
+        _ if key.len() > 1 && key.starts_with('f') => match key[1..].parse::<u8>() {
+            Ok(n) if (1..=12).contains(&n) => KeyCode::F(n),
+            _ => bail!("unknown key \"{}\"", key),
+        },
+        _ => {
+            let mut chars = key.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => KeyCode::Char(c),
+                _ => bail!("unknown key \"{}\"", key),
+            }
+        }
+    };
+
+    if matches!(code, KeyCode::Char(_))
+        && !modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT)
+    {
+        bail!("\"{}\" needs ctrl or alt, it would block typing", spec);
+    }
+    if matches!(code, KeyCode::Char(_)) && modifiers.contains(KeyModifiers::SHIFT) {
+        bail!("\"{}\": shift can't be combined with a character key", spec);
+    }
+    Ok((code, modifiers))
+}
+
+/// Which action each shortcut triggers: the defaults from `ACTIONS`, with the
+/// `keybindings` setting applied on top
+#[derive(Debug, Clone)]
+pub struct Keybindings {
+    bindings: Vec<(KeyCode, KeyModifiers, Action)>,
+}
+
+impl Keybindings {
+    /// Build the lookup from the `keybindings` setting (action name -> key spec,
+    /// "none" unbinds). Returns a warning for every entry that couldn't be used;
+    /// actions with a bad entry keep their default key.
+    pub fn from_settings(overrides: &HashMap<String, String>) -> (Self, Vec<String>) {
+        let mut warnings = Vec::new();
+        let mut names: Vec<&String> = overrides.keys().collect();
+        names.sort();
+        for name in names {
+            if !ACTIONS.iter().any(|(_, n, _)| n == name) {
+                warnings.push(format!("unknown keybinding action \"{}\"", name));
+            }
+        }
+
+        let mut bindings: Vec<(KeyCode, KeyModifiers, Action)> = Vec::new();
+        for &(action, name, default) in ACTIONS {
+            let spec = match overrides.get(name) {
+                Some(spec) if spec.trim().eq_ignore_ascii_case("none") => continue,
+                Some(spec) => match parse_key_spec(spec) {
+                    Ok(key) => key,
+                    Err(e) => {
+                        warnings.push(format!("keybinding {}: {}", name, e));
+                        parse_key_spec(default).expect("default keybindings parse")
+                    }
+                },
+                None => parse_key_spec(default).expect("default keybindings parse"),
+            };
+            if let Some((_, _, other)) = bindings.iter().find(|(c, m, _)| (*c, *m) == spec) {
+                let other = ACTIONS.iter().find(|(a, _, _)| a == other).map_or("", |(_, n, _)| n);
+                warnings.push(format!("keybinding {}: key already used by {}, left unbound", name, other));
+                continue;
+            }
+            bindings.push((spec.0, spec.1, action));
+        }
+
+        (Self { bindings }, warnings)
+    }
+
+    /// The action bound to a key press, if any
+    pub fn action_for(&self, key: &KeyEvent) -> Option<Action> {
+        let mut modifiers =
+            key.modifiers & (KeyModifiers::CONTROL | KeyModifiers::ALT | KeyModifiers::SHIFT);
+        let code = match key.code {
+            // Shift is already part of the character
+            KeyCode::Char(c) => {
+                modifiers.remove(KeyModifiers::SHIFT);
+                KeyCode::Char(c.to_ascii_lowercase())
+            }
+            code => code,
+        };
+        self.bindings
+            .iter()
+            .find(|(c, m, _)| *c == code && *m == modifiers)
+            .map(|(_, _, action)| *action)
+    }
+}
+
+impl Default for Keybindings {
+    fn default() -> Self {
+        Self::from_settings(&HashMap::new()).0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn press(code: KeyCode, modifiers: KeyModifiers) -> KeyEvent {
+        KeyEvent::new(code, modifiers)
+    }
+
+    #[test]
+    fn test_parse_key_spec() {
+        assert_eq!(
+            parse_key_spec("Ctrl+Q").unwrap(),
+            (KeyCode::Char('q'), KeyModifiers::CONTROL)
+        );
+        assert_eq!(
+            parse_key_spec("ctrl+alt+left").unwrap(),
+            (KeyCode::Left, KeyModifiers::CONTROL | KeyModifiers::ALT)
+        );
+        assert_eq!(parse_key_spec("f5").unwrap(), (KeyCode::F(5), KeyModifiers::NONE));
+        assert!(parse_key_spec("q").is_err());
+        assert!(parse_key_spec("hyper+q").is_err());
+        assert!(parse_key_spec("ctrl+").is_err());
+        assert!(parse_key_spec("f13").is_err());
+        // Shift with a letter would never match a key press
+        assert!(parse_key_spec("ctrl+shift+x").is_err());
+        assert_eq!(
+            parse_key_spec("ctrl+shift+left").unwrap(),
+            (KeyCode::Left, KeyModifiers::CONTROL | KeyModifiers::SHIFT)
+        );
+    }
+
+    #[test]
+    fn test_defaults_and_overrides() {
+        let defaults = Keybindings::default();
+        let ctrl_w = press(KeyCode::Char('w'), KeyModifiers::CONTROL);
+        assert_eq!(defaults.action_for(&ctrl_w), Some(Action::ClosePane));
+        // Extra modifiers make it a different key
+        let ctrl_alt_w = press(KeyCode::Char('w'), KeyModifiers::CONTROL | KeyModifiers::ALT);
+        assert_eq!(defaults.action_for(&ctrl_alt_w), None);
+        let ctrl_end = press(KeyCode::End, KeyModifiers::CONTROL);
+        assert_eq!(defaults.action_for(&ctrl_end), Some(Action::ScrollToBottom));
+        let ctrl_left = press(KeyCode::Left, KeyModifiers::CONTROL);
+        assert_eq!(defaults.action_for(&ctrl_left), Some(Action::WordLeft));
+
+        let overrides = HashMap::from([
+            ("close_pane".to_string(), "ctrl+alt+w".to_string()),
+            ("quit".to_string(), "none".to_string()),
+        ]);
+        let (bindings, warnings) = Keybindings::from_settings(&overrides);
+        assert!(warnings.is_empty());
+        assert_eq!(bindings.action_for(&ctrl_w), None);
+        assert_eq!(bindings.action_for(&ctrl_alt_w), Some(Action::ClosePane));
+        let ctrl_q = press(KeyCode::Char('q'), KeyModifiers::CONTROL);
+        assert_eq!(bindings.action_for(&ctrl_q), None);
+    }
+
+    #[test]
+    fn test_invalid_settings_warn() {
+        let overrides = HashMap::from([
+            ("close_tab".to_string(), "ctrl+x".to_string()),
+            ("clear_pane".to_string(), "x".to_string()),
+            ("toggle_borders".to_string(), "ctrl+e".to_string()),
+        ]);
+        let (bindings, warnings) = Keybindings::from_settings(&overrides);
+        assert_eq!(warnings.len(), 3);
+        assert!(warnings[0].contains("close_tab"));
+        // A bad spec keeps the default key
+        let ctrl_l = press(KeyCode::Char('l'), KeyModifiers::CONTROL);
+        assert_eq!(bindings.action_for(&ctrl_l), Some(Action::ClearPane));
+        // The first action keeps a key claimed twice
+        let ctrl_e = press(KeyCode::Char('e'), KeyModifiers::CONTROL);
+        assert_eq!(bindings.action_for(&ctrl_e), Some(Action::ToggleReactions));
+    }
+}
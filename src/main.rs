@@ -1,6 +1,9 @@
 use anyhow::Result;
 use crossterm::{
-    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyModifiers},
+    event::{
+        self, DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture,
+        Event, KeyCode, KeyModifiers,
+    },
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
@@ -11,13 +14,17 @@ mod app;
 mod commands;
 mod config;
 mod formatting;
+mod keybindings;
 mod persistence;
 mod split_view;
 mod telegram;
+mod theme;
 mod utils;
 mod widgets;
 
 use app::App;
+use keybindings::Action;
+use split_view::FocusDirection;
 
 #[tokio::main]
 async fn main() -> Result<()> {
@@ -27,7 +34,8 @@ async fn main() -> Result<()> {
     // Setup terminal
     enable_raw_mode()?;
     let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
+    // Bracketed paste delivers a paste as one event, so its newlines don't send the message
+    execute!(stdout, EnterAlternateScreen, EnableMouseCapture, EnableBracketedPaste)?;
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
@@ -42,7 +50,8 @@ async fn main() -> Result<()> {
     execute!(
         terminal.backend_mut(),
         LeaveAlternateScreen,
-        DisableMouseCapture
+        DisableMouseCapture,
+        DisableBracketedPaste
     )?;
     terminal.show_cursor()?;
 
@@ -53,212 +62,276 @@ async fn run_app<B: ratatui::backend::Backend>(
     terminal: &mut Terminal<B>,
     app: &mut App,
 ) -> Result<()> {
-    let mut last_telegram_check = std::time::Instant::now();
+    let mut input_events = spawn_input_reader();
+
+    // Housekeeping tick: expiring status lines, send progress, and a fallback poll for updates
+    let mut tick = tokio::time::interval(std::time::Duration::from_millis(500));
+    tick.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
 
     loop {
+        // Re-fetched each pass: /profile replaces the client and with it the notifier
+        let telegram_updates = app.telegram.update_notifier();
+
         // Only redraw when something changed
         if app.needs_redraw {
             terminal.draw(|f| app.draw(f))?;
             app.needs_redraw = false;
+            app.clamp_scroll_after_resize();
+            app.needs_redraw = app.apply_pending_jumps();
         }
 
-        // Process Telegram events every 500ms
-        if last_telegram_check.elapsed() >= std::time::Duration::from_millis(500) {
-            let had_updates = app.process_telegram_events().await?;
-            last_telegram_check = std::time::Instant::now();
-            if had_updates {
-                app.needs_redraw = true;
+        tokio::select! {
+            event = input_events.recv() => {
+                let Some(event) = event else { break };
+                if handle_event(app, event).await? {
+                    break;
+                }
+            }
+            _ = telegram_updates.notified() => {
+                if app.process_telegram_events().await? || app.connection_changed() {
+                    app.needs_redraw = true;
+                }
+            }
+            _ = tick.tick() => {
+                let had_updates = app.process_telegram_events().await?;
+                if had_updates
+                    || app.has_pending_sends()
+                    || app.expire_status_lines()
+                    || app.connection_changed()
+                {
+                    app.needs_redraw = true;
+                }
             }
         }
+    }
 
-        // Sleep until next telegram check (or cap at 500ms)
-        let poll_timeout = std::time::Duration::from_millis(500)
-            .saturating_sub(last_telegram_check.elapsed())
-            .max(std::time::Duration::from_millis(16));
+    Ok(())
+}
 
-        if event::poll(poll_timeout)? {
-            let event = event::read()?;
-            match event {
-                Event::Key(key) => {
-                    app.needs_redraw = true;
-                    match key.code {
-                    // Ctrl+Q: Quit
-                    KeyCode::Char('q') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                        app.save_state()?;
-                        break;
-                    }
-                    // Ctrl+R: Refresh chats
-                    KeyCode::Char('r') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                        app.refresh_chats().await?;
-                    }
-                    // Ctrl+V: Split vertical
-                    KeyCode::Char('v') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                        app.split_vertical();
-                    }
-                    // Ctrl+B: Split horizontal
-                    KeyCode::Char('b') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                        app.split_horizontal();
-                    }
-                    // Ctrl+K: Toggle split direction
-                    KeyCode::Char('k') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                        app.toggle_split_direction();
-                    }
-                    // Ctrl+W: Close pane
-                    KeyCode::Char('w') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                        app.close_pane();
-                    }                    // Ctrl+S: Toggle chat list (Sidebar)
-                    KeyCode::Char('s') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                        app.toggle_chat_list();
-                    }                    // Ctrl+L: Clear pane
-                    KeyCode::Char('l') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                        app.clear_pane();
-                    }
-                    // Ctrl+E: Toggle reactions
-                    KeyCode::Char('e') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                        app.toggle_reactions();
-                    }
-                    // Ctrl+N: Toggle notifications
-                    KeyCode::Char('n') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                        app.toggle_notifications();
-                    }
-                    // Ctrl+D: Toggle compact mode
-                    KeyCode::Char('d') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                        app.toggle_compact();
-                    }
-                    // Ctrl+O: Toggle emojis
-                    KeyCode::Char('o') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                        app.toggle_emojis();
-                    }
-                    // Ctrl+G: Toggle line numbers
-                    KeyCode::Char('g') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                        app.toggle_line_numbers();
-                    }
-                    // Ctrl+T: Toggle timestamps
-                    KeyCode::Char('t') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                        app.toggle_timestamps();
-                    }
-                    // Ctrl+U: Toggle user colors
-                    KeyCode::Char('u') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                        app.toggle_user_colors();
-                    }
-                    // Ctrl+Y: Toggle borders
-                    KeyCode::Char('y') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                        app.toggle_borders();
-                    }
-                    // Esc: Cancel reply mode
-                    KeyCode::Esc => {
-                        if let Some(pane) = app.panes.get_mut(app.focused_pane_idx) {
-                            if pane.reply_to_message.is_some() {
-                                pane.reply_to_message = None;
-                                pane.hide_reply_preview();
-                            }
-                        }
-                    }
-                    // Shift+Tab: Cycle focus backwards (only if input empty)
-                    KeyCode::BackTab => {
-                        let input_empty = app
-                            .panes
-                            .get(app.focused_pane_idx)
-                            .map_or(true, |p| p.input_buffer.is_empty());
-                        if app.focus_on_chat_list || input_empty {
-                            app.cycle_focus_reverse();
-                        }
-                    }
-                    // Tab: Autocomplete or cycle focus
-                    KeyCode::Tab => {
-                        app.handle_tab();
-                    }
-                    // Alt+Left/Right: Focus previous/next pane
-                    KeyCode::Left if key.modifiers.contains(KeyModifiers::ALT) => {
-                        app.focus_prev_pane();
-                    }
-                    KeyCode::Right if key.modifiers.contains(KeyModifiers::ALT) => {
-                        app.focus_next_pane();
-                    }
-                    // Arrow keys
-                    KeyCode::Up => {
-                        app.handle_up();
-                    }
-                    KeyCode::Down => {
-                        app.handle_down();
-                    }
-                    KeyCode::Left => {
-                        if !app.focus_on_chat_list {
-                            app.handle_input_left();
-                        }
-                    }
-                    KeyCode::Right => {
-                        if !app.focus_on_chat_list {
-                            app.handle_input_right();
-                        }
-                    }
-                    // Home/End: Move cursor to start/end
-                    KeyCode::Home => {
-                        if !app.focus_on_chat_list {
-                            app.handle_home();
-                        }
-                    }
-                    KeyCode::End => {
-                        if !app.focus_on_chat_list {
-                            app.handle_end();
-                        }
-                    }
-                    // PageUp/PageDown: Scroll messages
-                    KeyCode::PageUp => {
-                        app.handle_page_up();
-                    }
-                    KeyCode::PageDown => {
-                        app.handle_page_down();
-                    }
-                    // Enter: Submit
-                    KeyCode::Enter => {
-                        app.handle_enter().await?;
-                    }
-                    // Character input (only when not on chat list)
-                    KeyCode::Char(c) => {
-                        if !app.focus_on_chat_list {
-                            app.handle_char(c);
-                        }
-                    }
-                    // Backspace
-                    KeyCode::Backspace => {
-                        if !app.focus_on_chat_list {
-                            app.handle_backspace();
-                        }
-                    }
-                    // Delete
-                    KeyCode::Delete => {
-                        if !app.focus_on_chat_list {
-                            app.handle_delete();
+/// Read terminal events on a dedicated thread so input is never stalled by (or
+/// stalls) Telegram calls; events queue up in the channel until the loop gets to them.
+fn spawn_input_reader() -> tokio::sync::mpsc::UnboundedReceiver<Event> {
+    let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+    std::thread::spawn(move || {
+        while let Ok(event) = event::read() {
+            if tx.send(event).is_err() {
+                break;
+            }
+        }
+    });
+    rx
+}
+
+/// Handle one terminal event. Returns true when the app should quit.
+async fn handle_event(app: &mut App, event: Event) -> Result<bool> {
+    match event {
+        Event::Key(key) => {
+            app.needs_redraw = true;
+            // The reaction picker takes all keys while it is open
+            if let Some(picker) = app.reaction_picker.as_mut() {
+                match key.code {
+                    KeyCode::Left => picker.move_by(-1, 0),
+                    KeyCode::Right => picker.move_by(1, 0),
+                    KeyCode::Up => picker.move_by(0, -1),
+                    KeyCode::Down => picker.move_by(0, 1),
+                    KeyCode::Enter => app.apply_reaction_picker().await,
+                    KeyCode::Esc => app.reaction_picker = None,
+                    _ => {}
+                }
+                return Ok(false);
+            }
+            if let Some(action) = app.keybindings.action_for(&key) {
+                return run_action(app, action).await;
+            }
+            match key.code {
+                // Esc on the chat list: drop its filter
+                KeyCode::Esc if app.focus_on_chat_list && app.clear_chat_list_filter() => {}
+                // Esc: Cancel reply or edit mode, otherwise leave search results
+                KeyCode::Esc => {
+                    let mut cancelled = false;
+                    if let Some(pane) = app.panes.get_mut(app.focused_pane_idx) {
+                        if pane.reply_to_message.is_some() {
+                            pane.reply_to_message = None;
+                            pane.hide_reply_preview();
+                            cancelled = true;
                         }
+                        cancelled |= pane.editing_message.is_some();
+                        pane.cancel_edit();
+                    }
+                    // Leaving the results list, or dropping the highlights left by /next and /prev
+                    if !cancelled
+                        && (app.clear_search(app.focused_pane_idx).await
+                            || app
+                                .panes
+                                .get_mut(app.focused_pane_idx)
+                                .is_some_and(|p| p.clear_search_matches()))
+                    {
+                        app.notify("Search cleared");
                     }
-                    _ => {}
+                }
+                // Shift+Tab: Cycle focus backwards
+                KeyCode::BackTab => {
+                    app.handle_backtab();
+                }
+                // Tab: Autocomplete or cycle focus
+                KeyCode::Tab => {
+                    app.handle_tab();
+                }
+                // Arrow keys
+                KeyCode::Up => {
+                    app.handle_up();
+                }
+                KeyCode::Down => {
+                    app.handle_down();
+                }
+                KeyCode::Left if !app.focus_on_chat_list => {
+                    app.handle_input_left();
+                }
+                KeyCode::Right if !app.focus_on_chat_list => {
+                    app.handle_input_right();
+                }
+                // Home/End: Move cursor to line start/end, first/last chat in the list,
+                // or with an empty input the oldest/newest message
+                KeyCode::Home => {
+                    app.handle_home();
+                }
+                KeyCode::End => {
+                    app.handle_end();
+                }
+                // PageUp/PageDown: Scroll messages
+                KeyCode::PageUp => {
+                    app.handle_page_up().await;
+                }
+                KeyCode::PageDown => {
+                    app.handle_page_down();
+                }
+                // Enter: Submit
+                KeyCode::Enter => {
+                    app.handle_enter().await?;
+                }
+                // Unbound Ctrl shortcut (e.g. remapped away): don't type the letter.
+                // Ctrl+Alt is AltGr on some keyboards, so that still types
+                KeyCode::Char(_)
+                    if key.modifiers.contains(KeyModifiers::CONTROL)
+                        && !key.modifiers.contains(KeyModifiers::ALT) => {}
+                // Character input (on the chat list it filters the chats)
+                KeyCode::Char(c) => {
+                    if app.focus_on_chat_list {
+                        app.handle_chat_list_char(c);
+                    } else {
+                        app.handle_char(c);
                     }
                 }
-                Event::Mouse(mouse) => {
-                    app.needs_redraw = true;
-                    if let event::MouseEventKind::Down(event::MouseButton::Left) = mouse.kind {
-                        // Check if clicking on chat list first
-                        if let Some(area) = app.chat_list_area {
-                            if mouse.column >= area.x && mouse.column < area.x + area.width 
-                                && mouse.row >= area.y && mouse.row < area.y + area.height {
-                                // Clicked on chat list
-                                app.handle_chat_list_click(mouse.row, area).await?;
-                            }
-                        }
-                        // Check if clicking on a pane
-                        app.handle_mouse_click(mouse.column, mouse.row);
-                        // Load messages for focused pane if needed
-                        app.load_pane_messages_if_needed(app.focused_pane_idx).await;
+                // Alt+Backspace: Delete the previous word (Ctrl+W closes the pane)
+                KeyCode::Backspace
+                    if key.modifiers.contains(KeyModifiers::ALT) && !app.focus_on_chat_list =>
+                {
+                    app.handle_delete_word();
+                }
+                // Backspace
+                KeyCode::Backspace => {
+                    if app.focus_on_chat_list {
+                        app.handle_chat_list_backspace();
+                    } else {
+                        app.handle_backspace();
                     }
                 }
-                Event::Resize(_, _) => {
-                    app.needs_redraw = true;
+                // Delete
+                KeyCode::Delete if !app.focus_on_chat_list => {
+                    app.handle_delete();
+                }
+                _ => {}
+            }
+        }
+        Event::Mouse(mouse) => {
+            app.needs_redraw = true;
+            // Wheel over a pane scrolls it; over the chat list it does nothing
+            match mouse.kind {
+                event::MouseEventKind::ScrollUp => {
+                    app.handle_mouse_scroll(mouse.column, mouse.row, true)
+                }
+                event::MouseEventKind::ScrollDown => {
+                    app.handle_mouse_scroll(mouse.column, mouse.row, false)
                 }
                 _ => {}
             }
+            if let event::MouseEventKind::Down(event::MouseButton::Left) = mouse.kind {
+                // Check if clicking on chat list first
+                if let Some(area) = app.chat_list_area
+                    && mouse.column >= area.x
+                    && mouse.column < area.x + area.width
+                    && mouse.row >= area.y
+                    && mouse.row < area.y + area.height
+                {
+                    // Clicked on chat list (Alt+click peeks without marking read)
+                    let peek = mouse.modifiers.contains(KeyModifiers::ALT);
+                    app.handle_chat_list_click(mouse.row, area, peek).await?;
+                }
+                // Check if clicking on a pane
+                app.handle_mouse_click(mouse.column, mouse.row);
+                // Load messages for focused pane if needed
+                app.load_pane_messages_if_needed(app.focused_pane_idx).await;
+            }
+        }
+        Event::Paste(text) => {
+            app.needs_redraw = true;
+            if app.reaction_picker.is_none() {
+                app.handle_paste(&text);
+            }
+        }
+        Event::Resize(_, _) => {
+            app.handle_resize();
         }
+        _ => {}
     }
 
-    Ok(())
+    Ok(false)
+}
+
+/// Run a shortcut from the keybindings. Returns true when the app should quit.
+async fn run_action(app: &mut App, action: Action) -> Result<bool> {
+    match action {
+        Action::Quit => {
+            app.save_state()?;
+            return Ok(true);
+        }
+        Action::RefreshChats => app.refresh_chats().await?,
+        Action::SplitVertical => app.split_vertical(),
+        Action::SplitHorizontal => app.split_horizontal(),
+        Action::ToggleSplitDirection => app.toggle_split_direction(),
+        Action::GrowPane => app.resize_focused_pane(true),
+        Action::ShrinkPane => app.resize_focused_pane(false),
+        Action::SwapPanes => app.swap_with_next_pane(),
+        Action::ClosePane => app.close_pane(),
+        Action::ToggleChatList => app.toggle_chat_list(),
+        Action::ClearPane => app.clear_pane(),
+        Action::ToggleReactions => app.toggle_reactions(),
+        Action::ToggleNotifications => app.toggle_notifications(),
+        Action::ToggleCompact => app.toggle_compact(),
+        Action::ToggleGrouping => app.toggle_grouping(),
+        Action::ToggleEmojis => app.toggle_emojis(),
+        Action::ToggleLineNumbers => app.toggle_line_numbers(),
+        Action::ToggleTimestamps => app.toggle_timestamps(),
+        Action::ToggleUserColors => app.toggle_user_colors(),
+        Action::ToggleBorders => app.toggle_borders(),
+        Action::ToggleFocusMode => app.toggle_focus_mode(),
+        Action::ReactionPicker => app.open_reaction_picker_for_focused().await,
+        Action::JumpToUnread => app.jump_to_unread_divider(),
+        Action::FocusPrevPane => app.focus_prev_pane(),
+        Action::FocusNextPane => app.focus_next_pane(),
+        Action::FocusLeft => app.focus_direction(FocusDirection::Left),
+        Action::FocusDown => app.focus_direction(FocusDirection::Down),
+        Action::FocusUp => app.focus_direction(FocusDirection::Up),
+        Action::FocusRight => app.focus_direction(FocusDirection::Right),
+        Action::ScrollLineUp => app.handle_scroll_line(true),
+        Action::ScrollLineDown => app.handle_scroll_line(false),
+        Action::ScrollToTop => app.scroll_to_edge(true),
+        Action::ScrollToBottom => app.scroll_to_edge(false),
+        Action::NextMatch => app.step_search_match(app.focused_pane_idx, true).await,
+        Action::PrevMatch => app.step_search_match(app.focused_pane_idx, false).await,
+        Action::WordLeft if !app.focus_on_chat_list => app.handle_input_word_left(),
+        Action::WordRight if !app.focus_on_chat_list => app.handle_input_word_right(),
+        Action::WordLeft | Action::WordRight => {}
+    }
+    Ok(false)
 }
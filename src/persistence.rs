@@ -1,13 +1,22 @@
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fs;
+use std::path::Path;
 
 use crate::config::Config;
 use crate::split_view::PaneNode;
+use crate::widgets::{ChatPane, FilterType};
+
+/// Schema version written to telegram_layout.json. Files without one are
+/// version 0; every field added since has a serde default, so older files load
+/// (see `LayoutData::from_json`). 2 added per-pane view state, 3 split weights.
+pub const LAYOUT_VERSION: u32 = 3;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LayoutData {
+    #[serde(default)]
+    pub version: u32,
     pub panes: Vec<PaneState>,
     pub focused_pane: usize,
     #[serde(default)]
@@ -23,18 +32,50 @@ pub struct PaneState {
     pub filter_type: Option<String>,
     #[serde(default)]
     pub filter_value: Option<String>,
+    #[serde(default)]
+    pub log_view: bool,
+    #[serde(default)]
+    pub search_query: Option<String>, // Active /search, re-run on startup
+    #[serde(default)]
+    pub expanded_messages: Vec<i32>, // Message ids shown in full (/expand)
+}
+
+impl PaneState {
+    pub fn from_pane(pane: &ChatPane) -> Self {
+        let mut expanded_messages: Vec<i32> = pane.expanded_messages.iter().copied().collect();
+        expanded_messages.sort_unstable();
+        Self {
+            chat_id: pane.chat_id,
+            chat_name: pane.base_chat_name().to_string(),
+            scroll_offset: pane.scroll_offset,
+            filter_type: pane.filter_type.as_ref().map(|ft| ft.as_setting().to_string()),
+            filter_value: pane.filter_value.clone(),
+            log_view: pane.log_view,
+            search_query: pane.search_query.clone(),
+            expanded_messages,
+        }
+    }
+
+    /// Rebuild a pane's view state; messages (and search results) are fetched later
+    pub fn to_pane(&self) -> ChatPane {
+        let mut pane = ChatPane::new();
+        pane.chat_id = self.chat_id;
+        pane.chat_name = self.chat_name.clone();
+        pane.scroll_offset = self.scroll_offset;
+        pane.filter_type = self.filter_type.as_deref().and_then(FilterType::from_setting);
+        pane.filter_value = pane.filter_type.as_ref().and(self.filter_value.clone());
+        pane.log_view = self.log_view;
+        pane.search_query = self.search_query.clone();
+        pane.expanded_messages = self.expanded_messages.iter().copied().collect();
+        pane
+    }
 }
 
 impl LayoutData {
     pub fn new() -> Self {
         Self {
-            panes: vec![PaneState {
-                chat_id: None,
-                chat_name: "No chat selected".to_string(),
-                scroll_offset: 0,
-                filter_type: None,
-                filter_value: None,
-            }],
+            version: LAYOUT_VERSION,
+            panes: vec![PaneState::from_pane(&ChatPane::new())],
             focused_pane: 0,
             pane_tree: None,
         }
@@ -44,13 +85,32 @@ impl LayoutData {
         let path = config.layout_path();
         if path.exists() {
             let content = fs::read_to_string(path)?;
-            let layout: LayoutData = serde_json::from_str(&content)?;
-            Ok(layout)
+            Self::from_json(&content)
         } else {
             Ok(Self::new())
         }
     }
 
+    /// Parse a saved layout, bringing older schema versions up to date. A layout
+    /// from a newer version may mean something else entirely, so it is replaced
+    /// by the default one.
+    pub fn from_json(content: &str) -> Result<Self> {
+        let mut layout: LayoutData = serde_json::from_str(content)?;
+        if layout.version > LAYOUT_VERSION {
+            return Ok(Self::new());
+        }
+        if layout.version == 0 {
+            // Unversioned files count scroll offsets from the top of the history,
+            // not up from the newest message; start those panes at the bottom
+            for pane in &mut layout.panes {
+                pane.scroll_offset = 0;
+            }
+        }
+        // Before 3 splits had no weights; the empty default renders them equal
+        layout.version = LAYOUT_VERSION;
+        Ok(layout)
+    }
+
     pub fn save(&self, config: &Config) -> Result<()> {
         let path = config.layout_path();
         let content = serde_json::to_string_pretty(self)?;
@@ -115,10 +175,310 @@ impl Default for Aliases {
     }
 }
 
+/// Local display names for chats, set with /rename
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChatAliases {
+    #[serde(flatten)]
+    pub map: HashMap<i64, String>, // chat_id -> display name
+}
+
+impl ChatAliases {
+    pub fn new() -> Self {
+        Self {
+            map: HashMap::new(),
+        }
+    }
+
+    pub fn load(config: &Config) -> Result<Self> {
+        let path = config.chat_aliases_path();
+        if path.exists() {
+            let content = fs::read_to_string(path)?;
+            let chat_aliases: ChatAliases = serde_json::from_str(&content)?;
+            Ok(chat_aliases)
+        } else {
+            Ok(Self::new())
+        }
+    }
+
+    pub fn save(&self, config: &Config) -> Result<()> {
+        let path = config.chat_aliases_path();
+        let content = serde_json::to_string_pretty(self)?;
+        fs::write(path, content)?;
+        Ok(())
+    }
+
+    pub fn get(&self, chat_id: i64) -> Option<&String> {
+        self.map.get(&chat_id)
+    }
+
+    pub fn insert(&mut self, chat_id: i64, name: String) {
+        self.map.insert(chat_id, name);
+    }
+
+    pub fn remove(&mut self, chat_id: &i64) -> Option<String> {
+        self.map.remove(chat_id)
+    }
+}
+
+impl Default for ChatAliases {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Unsent input per chat, kept while the chat isn't open in a pane
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(transparent)] // flatten can't read integer map keys back
+pub struct Drafts {
+    pub map: HashMap<i64, String>, // chat_id -> draft text
+}
+
+impl Drafts {
+    pub fn new() -> Self {
+        Self {
+            map: HashMap::new(),
+        }
+    }
+
+    pub fn load(config: &Config) -> Result<Self> {
+        let path = config.drafts_path();
+        if path.exists() {
+            let content = fs::read_to_string(path)?;
+            let drafts: Drafts = serde_json::from_str(&content)?;
+            Ok(drafts)
+        } else {
+            Ok(Self::new())
+        }
+    }
+
+    pub fn save(&self, config: &Config) -> Result<()> {
+        let path = config.drafts_path();
+        let content = serde_json::to_string_pretty(self)?;
+        fs::write(path, content)?;
+        Ok(())
+    }
+
+    /// Remember `text` for a chat; blank text drops the draft
+    pub fn stash(&mut self, chat_id: i64, text: String) {
+        if text.trim().is_empty() {
+            self.map.remove(&chat_id);
+        } else {
+            self.map.insert(chat_id, text);
+        }
+    }
+
+    /// Remove and return a chat's draft
+    pub fn take(&mut self, chat_id: i64) -> Option<String> {
+        self.map.remove(&chat_id)
+    }
+}
+
+impl Default for Drafts {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Last scroll offset per chat, restored when a pane reopens the chat
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct ScrollPositions {
+    pub map: HashMap<i64, usize>, // chat_id -> lines scrolled up from the newest message
+}
+
+impl ScrollPositions {
+    pub fn new() -> Self {
+        Self {
+            map: HashMap::new(),
+        }
+    }
+
+    pub fn load(config: &Config) -> Result<Self> {
+        let path = config.scroll_positions_path();
+        if path.exists() {
+            let content = fs::read_to_string(path)?;
+            let positions: ScrollPositions = serde_json::from_str(&content)?;
+            Ok(positions)
+        } else {
+            Ok(Self::new())
+        }
+    }
+
+    pub fn save(&self, config: &Config) -> Result<()> {
+        let path = config.scroll_positions_path();
+        let content = serde_json::to_string_pretty(self)?;
+        fs::write(path, content)?;
+        Ok(())
+    }
+
+    /// Remember a chat's offset; 0 (following new messages) is the default and isn't stored
+    pub fn remember(&mut self, chat_id: i64, offset: usize) {
+        if offset == 0 {
+            self.map.remove(&chat_id);
+        } else {
+            self.map.insert(chat_id, offset);
+        }
+    }
+
+    /// Remove and return a chat's offset (0 if none was saved)
+    pub fn take(&mut self, chat_id: i64) -> usize {
+        self.map.remove(&chat_id).unwrap_or(0)
+    }
+}
+
+impl Default for ScrollPositions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Entries kept in the input history
+pub const MAX_INPUT_HISTORY: usize = 100;
+
+/// Sent messages and commands, oldest first, browsed with Up/Down
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct InputHistory {
+    pub entries: Vec<String>,
+}
+
+impl InputHistory {
+    pub fn load(config: &Config) -> Result<Self> {
+        let path = config.input_history_path();
+        if path.exists() {
+            let content = fs::read_to_string(path)?;
+            let history: InputHistory = serde_json::from_str(&content)?;
+            Ok(history)
+        } else {
+            Ok(Self::default())
+        }
+    }
+
+    pub fn save(&self, config: &Config) -> Result<()> {
+        let path = config.input_history_path();
+        let content = serde_json::to_string_pretty(self)?;
+        fs::write(path, content)?;
+        Ok(())
+    }
+
+    /// Add an entry unless it repeats the previous one, dropping the oldest past the cap
+    pub fn push(&mut self, text: String) {
+        if self.entries.last() == Some(&text) {
+            return;
+        }
+        self.entries.push(text);
+        if self.entries.len() > MAX_INPUT_HISTORY {
+            self.entries.remove(0);
+        }
+    }
+
+    pub fn get(&self, idx: usize) -> Option<&str> {
+        self.entries.get(idx).map(String::as_str)
+    }
+
+    /// The closest older entry than `from` (None: start at the newest). With
+    /// `commands_only`, entries that aren't /commands are skipped.
+    pub fn older(&self, from: Option<usize>, commands_only: bool) -> Option<usize> {
+        let end = from.unwrap_or(self.entries.len()).min(self.entries.len());
+        (0..end)
+            .rev()
+            .find(|&idx| !commands_only || self.entries[idx].starts_with('/'))
+    }
+
+    /// The closest newer entry than `from`; None once past the newest
+    pub fn newer(&self, from: usize, commands_only: bool) -> Option<usize> {
+        (from + 1..self.entries.len())
+            .find(|&idx| !commands_only || self.entries[idx].starts_with('/'))
+    }
+}
+
+/// A set of chat ids kept in its own file: the chats pinned with /pinchat
+/// (listed first; local only, unlike Telegram's own pins) or muted with /mute
+/// (kept locally so notifications stay off even if Telegram's setting is lost)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct ChatSet {
+    pub ids: HashSet<i64>,
+}
+
+impl ChatSet {
+    pub fn load(path: &Path) -> Result<Self> {
+        if path.exists() {
+            let content = fs::read_to_string(path)?;
+            let set: ChatSet = serde_json::from_str(&content)?;
+            Ok(set)
+        } else {
+            Ok(Self::default())
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let content = serde_json::to_string_pretty(self)?;
+        fs::write(path, content)?;
+        Ok(())
+    }
+
+    pub fn contains(&self, chat_id: i64) -> bool {
+        self.ids.contains(&chat_id)
+    }
+
+    /// Add or remove a chat; false if it already was in that state
+    pub fn set(&mut self, chat_id: i64, present: bool) -> bool {
+        if present {
+            self.ids.insert(chat_id)
+        } else {
+            self.ids.remove(&chat_id)
+        }
+    }
+}
+
+/// A message written while offline, waiting for the connection to come back
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QueuedSend {
+    pub chat_id: i64,
+    pub text: String,
+    #[serde(default)]
+    pub reply_to: Option<i32>,
+}
+
+/// Messages still waiting to be sent when the client quit, sent after the next start
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct OfflineQueue {
+    pub sends: Vec<QueuedSend>,
+}
+
+impl OfflineQueue {
+    pub fn load(config: &Config) -> Result<Self> {
+        let path = config.offline_queue_path();
+        if path.exists() {
+            let content = fs::read_to_string(path)?;
+            let queue: OfflineQueue = serde_json::from_str(&content)?;
+            Ok(queue)
+        } else {
+            Ok(Self::default())
+        }
+    }
+
+    pub fn save(&self, config: &Config) -> Result<()> {
+        let path = config.offline_queue_path();
+        let content = serde_json::to_string_pretty(self)?;
+        fs::write(path, content)?;
+        Ok(())
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AppState {
     pub settings: AppSettings,
     pub aliases: Aliases,
+    pub chat_aliases: ChatAliases,
+    pub drafts: Drafts,
+    pub scroll_positions: ScrollPositions,
+    pub pinned_chats: ChatSet,
+    pub muted_chats: ChatSet,
+    pub input_history: InputHistory,
+    pub offline_queue: OfflineQueue,
     pub layout: LayoutData,
 }
 
@@ -132,6 +492,9 @@ pub struct AppSettings {
     
     #[serde(default)]
     pub compact_mode: bool,
+
+    #[serde(default)]
+    pub group_messages: bool, // Consecutive messages from one sender share a header
     
     #[serde(default = "default_true")]
     pub show_emojis: bool,
@@ -150,6 +513,42 @@ pub struct AppSettings {
 
     #[serde(default = "default_true")]
     pub show_chat_list: bool,
+
+    #[serde(default)]
+    pub focus_mode: bool,
+
+    #[serde(default = "default_message_alignment")]
+    pub message_alignment: String, // "color", "prefix" or "align"
+
+    #[serde(default = "default_notification_sound")]
+    pub notification_sound: String, // "off", "system" or a path to a sound file
+
+    #[serde(default = "default_collapse_lines")]
+    pub collapse_lines: usize, // Collapse messages longer than this many lines (0 = never)
+
+    #[serde(default = "default_max_panes")]
+    pub max_panes: usize, // Splits beyond this many panes are refused
+
+    #[serde(default = "default_message_fetch_limit")]
+    pub message_fetch_limit: usize, // Messages loaded when a chat is opened or refreshed (/limit)
+
+    #[serde(default)]
+    pub away_message: Option<String>, // Auto-reply to DMs while set (/away)
+
+    #[serde(default = "default_theme")]
+    pub theme: String, // Built-in color theme, see theme.rs
+
+    #[serde(default = "default_true")]
+    pub render_markdown: bool, // Style **bold**, *italic* and `code` in messages
+
+    #[serde(default)]
+    pub dnd: bool, // Quiet hours: no desktop notifications between dnd_start and dnd_end (/dnd)
+
+    #[serde(default = "default_dnd_start")]
+    pub dnd_start: u8, // Local hour the quiet window starts
+
+    #[serde(default = "default_dnd_end")]
+    pub dnd_end: u8, // Local hour it ends; may be earlier than dnd_start to cross midnight
 }
 
 impl Default for AppSettings {
@@ -158,12 +557,25 @@ impl Default for AppSettings {
             show_reactions: true,
             show_notifications: true,
             compact_mode: false,
+            group_messages: false,
             show_emojis: true,
             show_line_numbers: false,
             show_timestamps: true,
             show_user_colors: true,
             show_borders: true,
             show_chat_list: true,
+            focus_mode: false,
+            message_alignment: default_message_alignment(),
+            notification_sound: default_notification_sound(),
+            collapse_lines: default_collapse_lines(),
+            max_panes: default_max_panes(),
+            message_fetch_limit: default_message_fetch_limit(),
+            away_message: None,
+            theme: default_theme(),
+            render_markdown: true,
+            dnd: false,
+            dnd_start: default_dnd_start(),
+            dnd_end: default_dnd_end(),
         }
     }
 }
@@ -172,6 +584,38 @@ fn default_true() -> bool {
     true
 }
 
+fn default_message_alignment() -> String {
+    "color".to_string()
+}
+
+fn default_notification_sound() -> String {
+    "off".to_string()
+}
+
+fn default_collapse_lines() -> usize {
+    15
+}
+
+fn default_max_panes() -> usize {
+    8
+}
+
+fn default_message_fetch_limit() -> usize {
+    50
+}
+
+fn default_theme() -> String {
+    "default".to_string()
+}
+
+fn default_dnd_start() -> u8 {
+    22
+}
+
+fn default_dnd_end() -> u8 {
+    7
+}
+
 impl AppState {
     pub fn load(config: &Config) -> Result<Self> {
         Ok(Self {
@@ -179,22 +623,214 @@ impl AppState {
                 show_reactions: config.settings.show_reactions,
                 show_notifications: config.settings.show_notifications,
                 compact_mode: config.settings.compact_mode,
+                group_messages: config.settings.group_messages,
                 show_emojis: config.settings.show_emojis,
                 show_line_numbers: config.settings.show_line_numbers,
                 show_timestamps: config.settings.show_timestamps,
                 show_user_colors: config.settings.show_user_colors,
                 show_borders: config.settings.show_borders,
                 show_chat_list: config.settings.show_chat_list,
+                focus_mode: config.settings.focus_mode,
+                message_alignment: config.settings.message_alignment.clone(),
+                notification_sound: config.settings.notification_sound.clone(),
+                collapse_lines: config.settings.collapse_lines,
+                max_panes: config.settings.max_panes,
+                message_fetch_limit: config.settings.message_fetch_limit,
+                away_message: config.settings.away_message.clone(),
+                theme: config.settings.theme.clone(),
+                render_markdown: config.settings.render_markdown,
+                dnd: config.settings.dnd,
+                dnd_start: config.settings.dnd_start,
+                dnd_end: config.settings.dnd_end,
             },
             aliases: Aliases::load(config)?,
+            chat_aliases: ChatAliases::load(config)?,
+            drafts: Drafts::load(config)?,
+            scroll_positions: ScrollPositions::load(config)?,
+            pinned_chats: ChatSet::load(&config.pinned_chats_path())?,
+            muted_chats: ChatSet::load(&config.muted_chats_path())?,
+            input_history: InputHistory::load(config)?,
+            offline_queue: OfflineQueue::load(config)?,
             layout: LayoutData::load(config)?,
         })
     }
 
     pub fn _save(&self, config: &Config) -> Result<()> {
         self.aliases.save(config)?;
+        self.chat_aliases.save(config)?;
         self.layout.save(config)?;
         // Settings are saved as part of config
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(pane: &ChatPane) -> ChatPane {
+        let json = serde_json::to_string(&PaneState::from_pane(pane)).unwrap();
+        serde_json::from_str::<PaneState>(&json).unwrap().to_pane()
+    }
+
+    fn pane_in_chat() -> ChatPane {
+        let mut pane = ChatPane::new();
+        pane.chat_id = Some(42);
+        pane.chat_name = "Team".to_string();
+        pane.scroll_offset = 3;
+        pane
+    }
+
+    #[test]
+    fn test_pane_state_round_trips_each_view_setting() {
+        let mut filtered = pane_in_chat();
+        filtered.filter_type = Some(FilterType::Media);
+        filtered.filter_value = Some("photo".to_string());
+        let restored = round_trip(&filtered);
+        assert_eq!(restored.filter_type, Some(FilterType::Media));
+        assert_eq!(restored.filter_value.as_deref(), Some("photo"));
+        assert_eq!(restored.search_query, None);
+
+        let mut searching = pane_in_chat();
+        searching.search_query = Some("deploy".to_string());
+        searching.chat_name = "Team | Search: 'deploy' (4 results)".to_string();
+        let restored = round_trip(&searching);
+        assert_eq!(restored.search_query.as_deref(), Some("deploy"));
+        assert_eq!(restored.chat_name, "Team");
+        assert_eq!(restored.filter_type, None);
+
+        let mut log = pane_in_chat();
+        log.log_view = true;
+        log.expanded_messages.extend([7, 3]);
+        let restored = round_trip(&log);
+        assert!(restored.log_view);
+        assert_eq!(restored.expanded_messages, log.expanded_messages);
+    }
+
+    #[test]
+    fn test_pane_state_round_trips_combined_view() {
+        let mut pane = pane_in_chat();
+        pane.filter_type = Some(FilterType::Sender);
+        pane.filter_value = Some("alice".to_string());
+        pane.search_query = Some("lunch".to_string());
+        pane.log_view = true;
+        pane.expanded_messages.insert(12);
+
+        let restored = round_trip(&pane);
+        assert_eq!(restored.chat_id, Some(42));
+        assert_eq!(restored.scroll_offset, 3);
+        assert_eq!(restored.filter_type, Some(FilterType::Sender));
+        assert_eq!(restored.filter_value.as_deref(), Some("alice"));
+        assert_eq!(restored.search_query.as_deref(), Some("lunch"));
+        assert!(restored.log_view);
+        assert!(restored.expanded_messages.contains(&12));
+    }
+
+    #[test]
+    fn test_layout_loads_older_files() {
+        // Version 0 layout: no version, no view fields, and an unknown filter type
+        let json = r#"{
+            "panes": [{"chat_id": 1, "chat_name": "Old", "scroll_offset": 40,
+                       "filter_type": "bogus", "filter_value": "x"}],
+            "focused_pane": 0
+        }"#;
+        let layout = LayoutData::from_json(json).unwrap();
+        assert_eq!(layout.version, LAYOUT_VERSION);
+
+        let pane = layout.panes[0].to_pane();
+        assert_eq!(pane.chat_id, Some(1));
+        assert_eq!(pane.scroll_offset, 0); // Counted from the top back then
+        assert_eq!(pane.filter_type, None);
+        assert_eq!(pane.filter_value, None);
+        assert!(!pane.log_view);
+        assert!(pane.search_query.is_none());
+
+        // A layout from a newer version is dropped rather than misread
+        let json = r#"{"version": 99, "panes": [{"chat_id": 1, "chat_name": "New", "scroll_offset": 5}],
+                       "focused_pane": 0}"#;
+        let layout = LayoutData::from_json(json).unwrap();
+        assert_eq!(layout.panes[0].chat_id, None);
+    }
+
+    #[test]
+    fn test_drafts_stash_and_take() {
+        let mut drafts = Drafts::new();
+        drafts.stash(7, "half a thought".to_string());
+        drafts.stash(8, "   ".to_string());
+        assert!(!drafts.map.contains_key(&8));
+
+        let json = serde_json::to_string(&drafts).unwrap();
+        let mut loaded: Drafts = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded.take(7).as_deref(), Some("half a thought"));
+        assert_eq!(loaded.take(7), None);
+
+        // Stashing an empty buffer (e.g. after sending) drops the old draft
+        drafts.stash(7, String::new());
+        assert!(drafts.map.is_empty());
+    }
+
+    #[test]
+    fn test_scroll_positions_remember_and_restore() {
+        let mut positions = ScrollPositions::new();
+        positions.remember(7, 120);
+        positions.remember(8, 0);
+        assert!(!positions.map.contains_key(&8));
+
+        let json = serde_json::to_string(&positions).unwrap();
+        let mut loaded: ScrollPositions = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded.take(7), 120);
+        assert_eq!(loaded.take(7), 0);
+        assert_eq!(loaded.take(9), 0);
+
+        // Scrolled back to the bottom: nothing worth keeping
+        positions.remember(7, 0);
+        assert!(positions.map.is_empty());
+    }
+
+    #[test]
+    fn test_input_history_dedupe_and_cap() {
+        let mut history = InputHistory::default();
+        history.push("hi".to_string());
+        history.push("hi".to_string());
+        assert_eq!(history.entries.len(), 1);
+        for i in 0..MAX_INPUT_HISTORY + 5 {
+            history.push(format!("msg {}", i));
+        }
+        assert_eq!(history.entries.len(), MAX_INPUT_HISTORY);
+        assert_eq!(history.get(0), Some("msg 5"));
+
+        let json = serde_json::to_string(&history).unwrap();
+        let loaded: InputHistory = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded.entries, history.entries);
+    }
+
+    #[test]
+    fn test_input_history_commands_only() {
+        let mut history = InputHistory::default();
+        for text in ["/search foo", "hello", "/theme mono", "bye"] {
+            history.push(text.to_string());
+        }
+        assert_eq!(history.older(None, false), Some(3));
+        assert_eq!(history.older(None, true), Some(2));
+        assert_eq!(history.older(Some(2), true), Some(0));
+        assert_eq!(history.older(Some(0), true), None);
+        assert_eq!(history.newer(0, true), Some(2));
+        assert_eq!(history.newer(2, true), None);
+        assert_eq!(history.newer(2, false), Some(3));
+    }
+
+    #[test]
+    fn test_chat_set_toggle_and_round_trip() {
+        let mut set = ChatSet::default();
+        assert!(set.set(7, true));
+        assert!(!set.set(7, true));
+
+        let json = serde_json::to_string(&set).unwrap();
+        assert_eq!(json, "[7]");
+        let mut loaded: ChatSet = serde_json::from_str(&json).unwrap();
+        assert!(loaded.contains(7));
+        assert!(loaded.set(7, false));
+        assert!(!loaded.set(7, false));
+        assert!(!loaded.contains(7));
+    }
+}
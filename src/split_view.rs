@@ -3,6 +3,7 @@ use ratatui::{
     Frame,
 };
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
 use crate::widgets::ChatPane;
 
@@ -12,12 +13,65 @@ pub enum SplitDirection {
     Vertical,
 }
 
+/// Where to move focus from the focused pane (Alt+h/j/k/l)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FocusDirection {
+    Left,
+    Down,
+    Up,
+    Right,
+}
+
+/// The pane next to `from` in `dir`, going by the rectangles of the last draw.
+/// Only panes whose center lies in that direction count; panes sharing rows (or
+/// columns) with `from` win over diagonal ones, then the nearest center.
+pub fn neighbor_in_direction(
+    areas: &HashMap<usize, Rect>,
+    from: usize,
+    dir: FocusDirection,
+) -> Option<usize> {
+    let origin = areas.get(&from)?;
+    // Doubled centers stay integral
+    let center = |r: &Rect| (2 * r.x as i32 + r.width as i32, 2 * r.y as i32 + r.height as i32);
+    let (ox, oy) = center(origin);
+    let overlaps = |a_start: u16, a_len: u16, b_start: u16, b_len: u16| {
+        a_start < b_start + b_len && b_start < a_start + a_len
+    };
+
+    areas
+        .iter()
+        .filter(|&(&idx, _)| idx != from)
+        .filter_map(|(&idx, area)| {
+            let (cx, cy) = center(area);
+            let (ahead, aligned) = match dir {
+                FocusDirection::Left => (cx < ox, overlaps(area.y, area.height, origin.y, origin.height)),
+                FocusDirection::Right => (cx > ox, overlaps(area.y, area.height, origin.y, origin.height)),
+                FocusDirection::Up => (cy < oy, overlaps(area.x, area.width, origin.x, origin.width)),
+                FocusDirection::Down => (cy > oy, overlaps(area.x, area.width, origin.x, origin.width)),
+            };
+            let distance = (cx - ox).pow(2) + (cy - oy).pow(2);
+            ahead.then_some(((!aligned, distance, idx), idx))
+        })
+        .min_by_key(|(key, _)| *key)
+        .map(|(_, idx)| idx)
+}
+
+/// Size weight of each side of a new split
+const DEFAULT_WEIGHT: u16 = 50;
+/// Weight moved between siblings per grow/shrink step
+const RESIZE_STEP: u16 = 5;
+/// No pane shrinks below this weight
+const MIN_WEIGHT: u16 = 10;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum PaneNode {
     Single(usize),  // Index into App.panes
     Split {
         direction: SplitDirection,
         children: Vec<Box<PaneNode>>,
+        /// Relative size of each child; empty (older layouts) means equal sizes
+        #[serde(default)]
+        weights: Vec<u16>,
     },
 }
 
@@ -31,6 +85,7 @@ impl PaneNode {
         *self = PaneNode::Split {
             direction,
             children: vec![Box::new(old_node), Box::new(PaneNode::Single(new_pane_idx))],
+            weights: vec![DEFAULT_WEIGHT; 2],
         };
     }
 
@@ -55,13 +110,16 @@ impl PaneNode {
     pub fn find_and_remove_pane(&mut self, pane_idx: usize) -> bool {
         match self {
             PaneNode::Single(idx) => *idx == pane_idx,
-            PaneNode::Split { children, .. } => {
+            PaneNode::Split { children, weights, .. } => {
                 // Check if any child IS the pane we want to remove
                 if let Some(pos) = children.iter().position(|child| {
                     matches!(**child, PaneNode::Single(idx) if idx == pane_idx)
                 }) {
                     // Remove this direct child
                     children.remove(pos);
+                    if pos < weights.len() {
+                        weights.remove(pos);
+                    }
                     
                     // If only one child remains, collapse the split
                     if children.len() == 1 {
@@ -83,6 +141,59 @@ impl PaneNode {
         }
     }
 
+    /// Grow (or shrink) a pane against its neighbour in the innermost split that
+    /// holds it. None if the pane isn't in a split; Some(false) if it is already
+    /// at the size limit.
+    pub fn resize_pane(&mut self, pane_idx: usize, grow: bool) -> Option<bool> {
+        let PaneNode::Split { children, weights, .. } = self else {
+            return None;
+        };
+        let pos = children.iter().position(|child| child.get_pane_indices().contains(&pane_idx))?;
+        if let Some(resized) = children[pos].resize_pane(pane_idx, grow) {
+            return Some(resized);
+        }
+        if children.len() < 2 {
+            return None;
+        }
+        if weights.len() != children.len() {
+            *weights = vec![DEFAULT_WEIGHT; children.len()];
+        }
+        let sibling = if pos + 1 < children.len() { pos + 1 } else { pos - 1 };
+        let (from, to) = if grow { (sibling, pos) } else { (pos, sibling) };
+        if weights[from] < MIN_WEIGHT + RESIZE_STEP {
+            return Some(false);
+        }
+        weights[from] -= RESIZE_STEP;
+        weights[to] += RESIZE_STEP;
+        Some(true)
+    }
+
+    /// Renumber leaves to 0..n in tree order. Returns the old -> new index map.
+    pub fn compact_indices(&mut self) -> HashMap<usize, usize> {
+        let mut remap = HashMap::new();
+        for old in self.get_pane_indices() {
+            let next = remap.len();
+            remap.entry(old).or_insert(next);
+        }
+        self.remap_indices(&remap);
+        remap
+    }
+
+    fn remap_indices(&mut self, remap: &HashMap<usize, usize>) {
+        match self {
+            PaneNode::Single(idx) => {
+                if let Some(&new_idx) = remap.get(idx) {
+                    *idx = new_idx;
+                }
+            }
+            PaneNode::Split { children, .. } => {
+                for child in children.iter_mut() {
+                    child.remap_indices(remap);
+                }
+            }
+        }
+    }
+
     pub fn render(
         &self,
         f: &mut Frame,
@@ -100,13 +211,20 @@ impl PaneNode {
                     render_fn(f, area, pane, is_focused);
                 }
             }
-            PaneNode::Split { direction, children } => {
+            PaneNode::Split { direction, children, weights } => {
                 if children.is_empty() {
                     return;
                 }
 
-                let constraints: Vec<Constraint> = (0..children.len())
-                    .map(|_| Constraint::Percentage(100 / children.len() as u16))
+                let weights = if weights.len() == children.len() {
+                    weights.clone()
+                } else {
+                    vec![DEFAULT_WEIGHT; children.len()]
+                };
+                let total: u32 = weights.iter().map(|&w| w as u32).sum();
+                let constraints: Vec<Constraint> = weights
+                    .iter()
+                    .map(|&w| Constraint::Ratio(w as u32, total.max(1)))
                     .collect();
 
                 let layout_direction = match direction {
@@ -140,10 +258,145 @@ impl PaneNode {
     }
 }
 
+/// Compact `panes` to exactly the panes referenced by `tree`, renumbering the
+/// tree's leaves to match. Orphaned slots (closed panes, stale saved panes) are
+/// dropped; leaves pointing past the end of `panes` get a fresh default pane.
+/// Returns the old -> new index map so callers can fix up stored indices.
+pub fn compact_panes<T: Default>(tree: &mut PaneNode, panes: &mut Vec<T>) -> HashMap<usize, usize> {
+    let remap = tree.compact_indices();
+    let mut slots: Vec<Option<T>> = std::mem::take(panes).into_iter().map(Some).collect();
+    let mut order: Vec<(usize, usize)> = remap.iter().map(|(&old, &new)| (new, old)).collect();
+    order.sort_unstable();
+    *panes = order
+        .into_iter()
+        .map(|(_, old)| slots.get_mut(old).and_then(Option::take).unwrap_or_default())
+        .collect();
+    remap
+}
+
+/// Exchange the contents of two pane slots. The tree keeps pointing at the same
+/// slots, so each pane moves to the other's place on screen. False if either
+/// index is out of range.
+pub fn swap_panes<T>(panes: &mut [T], a: usize, b: usize) -> bool {
+    if a >= panes.len() || b >= panes.len() {
+        return false;
+    }
+    panes.swap(a, b);
+    true
+}
+
+/// Where a slot index points after `swap_panes(a, b)`: anything following a
+/// pane's contents (focus, undo entries) moves with it
+pub fn swapped_index(idx: usize, a: usize, b: usize) -> usize {
+    if idx == a {
+        b
+    } else if idx == b {
+        a
+    } else {
+        idx
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_swap_panes_moves_chats() {
+        let mut tree = PaneNode::new_single(0);
+        tree.split(SplitDirection::Vertical, 1);
+        let mut panes: Vec<ChatPane> = (0..2).map(|_| ChatPane::new()).collect();
+        panes[0].chat_id = Some(100);
+        panes[1].chat_id = Some(200);
+
+        assert!(swap_panes(&mut panes, 0, 1));
+        assert_eq!(panes[0].chat_id, Some(200));
+        assert_eq!(panes[1].chat_id, Some(100));
+        // The tree still references valid slots
+        assert!(tree.get_pane_indices().iter().all(|&idx| idx < panes.len()));
+
+        assert!(!swap_panes(&mut panes, 0, 5));
+        assert_eq!(panes[0].chat_id, Some(200));
+    }
+
+    #[test]
+    fn test_swapped_index_follows_contents() {
+        let mut panes = vec!["a", "b", "c"];
+        assert!(swap_panes(&mut panes, 0, 2));
+        // Focus on "a" (slot 0) follows it to slot 2, and back
+        assert_eq!(panes[swapped_index(0, 0, 2)], "a");
+        assert_eq!(panes[swapped_index(2, 0, 2)], "c");
+        // Untouched slots keep their index
+        assert_eq!(swapped_index(1, 0, 2), 1);
+        assert_eq!(panes[swapped_index(1, 0, 2)], "b");
+    }
+
+    #[test]
+    fn test_resize_pane() {
+        let mut node = PaneNode::new_single(0);
+        assert_eq!(node.resize_pane(0, true), None);
+
+        node.split(SplitDirection::Vertical, 1);
+        // Pane 1 sits in a nested split; growing it only touches that split
+        if let PaneNode::Split { children, .. } = &mut node {
+            children[1].split(SplitDirection::Horizontal, 2);
+        }
+        assert_eq!(node.resize_pane(1, true), Some(true));
+        let PaneNode::Split { children, weights, .. } = &node else { unreachable!() };
+        assert_eq!(weights, &vec![50, 50]);
+        let PaneNode::Split { weights: inner, .. } = children[1].as_ref() else { unreachable!() };
+        assert_eq!(inner, &vec![55, 45]);
+
+        // Shrinking stops at the minimum
+        for _ in 0..20 {
+            node.resize_pane(0, false);
+        }
+        assert_eq!(node.resize_pane(0, false), Some(false));
+        let PaneNode::Split { weights, .. } = &node else { unreachable!() };
+        assert_eq!(weights[0], 10);
+        assert_eq!(weights[0] + weights[1], 100);
+
+        // Closing a pane drops its weight along with it
+        node.find_and_remove_pane(2);
+        let PaneNode::Split { children, weights, .. } = &node else { unreachable!() };
+        assert_eq!(children.len(), weights.len());
+    }
+
+    #[test]
+    fn test_split_weights_default_for_old_layouts() {
+        let json = r#"{"Split":{"direction":"Vertical","children":[{"Single":0},{"Single":1}]}}"#;
+        let mut node: PaneNode = serde_json::from_str(json).unwrap();
+        assert_eq!(node.resize_pane(1, true), Some(true));
+        let PaneNode::Split { weights, .. } = &node else { unreachable!() };
+        assert_eq!(weights, &vec![45, 55]);
+    }
+
+    #[test]
+    fn test_neighbor_in_direction() {
+        // ┌───┬───┐
+        // │ 0 │ 1 │
+        // ├───┼───┤
+        // │ 2 │ 3 │
+        // └───┴───┘   plus 4, a full-width pane below
+        let areas = HashMap::from([
+            (0, Rect::new(0, 0, 40, 10)),
+            (1, Rect::new(40, 0, 40, 10)),
+            (2, Rect::new(0, 10, 40, 10)),
+            (3, Rect::new(40, 10, 40, 10)),
+            (4, Rect::new(0, 20, 80, 10)),
+        ]);
+        assert_eq!(neighbor_in_direction(&areas, 0, FocusDirection::Right), Some(1));
+        assert_eq!(neighbor_in_direction(&areas, 0, FocusDirection::Down), Some(2));
+        assert_eq!(neighbor_in_direction(&areas, 3, FocusDirection::Left), Some(2));
+        assert_eq!(neighbor_in_direction(&areas, 3, FocusDirection::Up), Some(1));
+        assert_eq!(neighbor_in_direction(&areas, 3, FocusDirection::Down), Some(4));
+        assert_eq!(neighbor_in_direction(&areas, 4, FocusDirection::Up), Some(2));
+        // Nothing further in that direction
+        assert_eq!(neighbor_in_direction(&areas, 0, FocusDirection::Left), None);
+        assert_eq!(neighbor_in_direction(&areas, 4, FocusDirection::Down), None);
+        assert_eq!(neighbor_in_direction(&areas, 9, FocusDirection::Down), None);
+    }
+
     #[test]
     fn test_split_single_pane() {
         let mut node = PaneNode::new_single(0);
@@ -181,4 +434,68 @@ mod tests {
         let next = node.get_next_pane_idx(2);
         assert_eq!(next, Some(0)); // Wraps around
     }
+
+    /// Indices in the tree are exactly 0..panes.len(), each used once
+    fn assert_contiguous(node: &PaneNode, panes: &[&str]) {
+        let mut indices = node.get_pane_indices();
+        indices.sort_unstable();
+        assert_eq!(indices, (0..panes.len()).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_compact_after_close() {
+        let mut node = PaneNode::new_single(0);
+        let mut panes = vec!["a", "b", "c"];
+        node.split(SplitDirection::Vertical, 1);
+        node.split(SplitDirection::Horizontal, 2);
+
+        assert!(node.find_and_remove_pane(1));
+        let remap = compact_panes(&mut node, &mut panes);
+
+        assert_eq!(panes, vec!["a", "c"]);
+        assert_eq!(remap.get(&2), Some(&1));
+        assert_eq!(remap.get(&1), None);
+        assert_contiguous(&node, &panes);
+    }
+
+    #[test]
+    fn test_split_close_split_has_no_orphans() {
+        let mut node = PaneNode::new_single(0);
+        let mut panes = vec!["p0".to_string()];
+
+        // Repeated split -> close -> split never grows past the live pane count
+        for (round, name) in (1..=10).enumerate() {
+            let new_idx = panes.len();
+            panes.push(format!("p{}", name));
+            node.split(SplitDirection::Vertical, new_idx);
+
+            let closing = if round % 2 == 0 { new_idx } else { 0 };
+            assert!(node.find_and_remove_pane(closing));
+            compact_panes(&mut node, &mut panes);
+
+            assert_eq!(panes.len(), node.count_panes());
+            let names: Vec<&str> = panes.iter().map(String::as_str).collect();
+            assert_contiguous(&node, &names);
+        }
+        assert_eq!(panes.len(), 1);
+
+        let new_idx = panes.len();
+        panes.push("last".to_string());
+        node.split(SplitDirection::Horizontal, new_idx);
+        assert_eq!(node.get_pane_indices(), vec![0, 1]);
+        assert_eq!(panes.len(), 2);
+    }
+
+    #[test]
+    fn test_compact_fills_and_drops_slots() {
+        // Saved layout whose tree references a missing slot and leaves a stale one
+        let mut node = PaneNode::new_single(3);
+        node.split(SplitDirection::Vertical, 5);
+        let mut panes = vec!["s0", "s1", "s2", "s3"];
+
+        compact_panes(&mut node, &mut panes);
+
+        assert_eq!(panes, vec!["s3", ""]);
+        assert_eq!(node.get_pane_indices(), vec![0, 1]);
+    }
 }
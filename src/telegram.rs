@@ -1,36 +1,575 @@
 use anyhow::Result;
-use grammers_client::{Client, Config as ClientConfig, InitParams, SignInError, Update};
+use grammers_client::{
+    Client, Config as ClientConfig, InitParams, InvocationError, SignInError, Update,
+};
 use grammers_session::Session;
+use std::path::Path;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU8, Ordering};
 use std::sync::Arc;
+use std::task::{Context, Poll};
+use tokio::io::{AsyncRead, ReadBuf};
 use tokio::sync::Mutex;
 
 use crate::app::ChatInfo;
 use crate::config::Config;
+use crate::widgets::ANONYMOUS_SENDER_ID;
+
+/// grammers-client version we build against (keep in sync with Cargo.toml), for /debug
+pub const GRAMMERS_VERSION: &str = "0.7";
+
+/// A message as fetched from history, before it becomes `MessageData` for a pane
+pub struct RawMessage {
+    pub id: i32,
+    pub sender_id: i64,
+    pub sender_name: String,
+    pub text: String,
+    pub quote_ranges: Vec<(usize, usize)>,
+    pub reply_to_id: Option<i32>,
+    pub media_type: Option<String>,
+    pub media_label: Option<String>,
+    pub reactions: std::collections::HashMap<String, u32>,
+    pub timestamp: i64,
+}
 
 /// Updates received from Telegram
 pub enum TelegramUpdate {
+    /// Carries everything needed to append the message without re-fetching history
     NewMessage {
         chat_id: i64,
-        _sender_name: String,
+        msg_id: i32,
+        sender_id: i64,
+        sender_name: String,
         text: String,
+        quote_ranges: Vec<(usize, usize)>,
         is_outgoing: bool,
+        reply_to_id: Option<i32>,
+        media_type: Option<String>,
+        media_label: Option<String>,
+        timestamp: i64,
     },
     #[allow(dead_code)]
     UserTyping {
         chat_id: i64,
         user_name: String,
     },
+    /// A background send failed (e.g. no post rights in a channel)
+    SendFailed {
+        chat_id: i64,
+        text: String,
+        error: String,
+    },
+    /// A send that failed because the connection is down; retried on reconnect
+    SendQueued {
+        chat_id: i64,
+        text: String,
+        reply_to: Option<i32>,
+    },
+    /// Last seen state of a 1-on-1 chat's peer, fetched in the background
+    UserStatus {
+        chat_id: i64,
+        status: Option<UserStatus>,
+    },
+}
+
+/// Whether a dialog's notifications are muted at `now` (unix seconds)
+fn is_muted(settings: &grammers_tl_types::enums::PeerNotifySettings, now: i64) -> bool {
+    let grammers_tl_types::enums::PeerNotifySettings::Settings(settings) = settings;
+    settings.mute_until.is_some_and(|until| i64::from(until) > now)
+}
+
+/// Order chats most recently active first, like the official clients. The sort
+/// is stable, so chats with the same date keep the order they came in.
+pub fn sort_by_activity(chats: &mut [ChatInfo]) {
+    chats.sort_by_key(|c| std::cmp::Reverse(c.last_message_date));
+}
+
+/// What sort of chat a dialog is. Group management needs different requests
+/// for basic groups (messages.*) and supergroups/channels (channels.*).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChatKind {
+    User,
+    BasicGroup,
+    Supergroup,
+    Channel,
+}
+
+impl ChatKind {
+    pub fn of(chat: &grammers_client::types::Chat) -> Self {
+        use grammers_client::types::Chat;
+        match chat {
+            Chat::User(_) => ChatKind::User,
+            Chat::Group(g) if g.is_megagroup() => ChatKind::Supergroup,
+            Chat::Group(_) => ChatKind::BasicGroup,
+            Chat::Channel(_) => ChatKind::Channel,
+        }
+    }
+
+    /// Multi-member chat where anyone may post (basic group or supergroup)
+    pub fn is_group(self) -> bool {
+        matches!(self, ChatKind::BasicGroup | ChatKind::Supergroup)
+    }
+}
+
+/// A user's last-seen state. Users who hide their last seen time only expose a
+/// rough bucket (recently, within a week/month, or long ago).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UserStatus {
+    Online,
+    Offline { was_online: i64 },
+    Recently,
+    LastWeek,
+    LastMonth,
+    LongAgo,
+}
+
+impl UserStatus {
+    pub fn from_raw(status: &grammers_tl_types::enums::UserStatus) -> Self {
+        use grammers_tl_types::enums::UserStatus as Raw;
+        match status {
+            Raw::Online(_) => UserStatus::Online,
+            Raw::Offline(s) => UserStatus::Offline { was_online: s.was_online as i64 },
+            Raw::Recently(_) => UserStatus::Recently,
+            Raw::LastWeek(_) => UserStatus::LastWeek,
+            Raw::LastMonth(_) => UserStatus::LastMonth,
+            Raw::Empty => UserStatus::LongAgo,
+        }
+    }
+
+    /// Human-readable text such as "online" or "last seen 5m ago"
+    pub fn describe(&self, now: i64) -> String {
+        match self {
+            UserStatus::Online => "online".to_string(),
+            UserStatus::Offline { was_online } => {
+                format!("last seen {}", crate::utils::format_relative_time(*was_online, now))
+            }
+            UserStatus::Recently => "last seen recently".to_string(),
+            UserStatus::LastWeek => "last seen within a week".to_string(),
+            UserStatus::LastMonth => "last seen within a month".to_string(),
+            UserStatus::LongAgo => "last seen a long time ago".to_string(),
+        }
+    }
+}
+
+/// Connection health as last observed by the update listener and sends
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConnectionState {
+    Connected,
+    Connecting,
+    Offline,
+}
+
+impl ConnectionState {
+    /// State implied by a request's outcome; `None` when the error says nothing
+    /// about the network (e.g. a chat that could not be found locally)
+    pub fn after<T>(result: &Result<T>) -> Option<Self> {
+        match result {
+            Ok(_) => Some(Self::Connected),
+            Err(e) => match e.downcast_ref::<InvocationError>() {
+                // The server answered, so the link itself is fine
+                Some(InvocationError::Rpc(_)) => Some(Self::Connected),
+                Some(_) => Some(Self::Offline),
+                None => None,
+            },
+        }
+    }
 }
 
+/// How long the listener waits before retrying after losing the connection
+const RECONNECT_DELAY: std::time::Duration = std::time::Duration::from_secs(5);
+
 #[derive(Clone)]
 pub struct TelegramClient {
     client: Arc<Mutex<Client>>,
+    connection: Arc<std::sync::Mutex<ConnectionState>>,
+    // Chats by id, filled by get_dialogs, resolve_username and lookups so that
+    // find_chat_inner rarely has to walk the whole dialog list
+    chat_cache: Arc<Mutex<std::collections::HashMap<i64, grammers_client::types::Chat>>>,
     update_handle: Arc<Mutex<Option<tokio::task::JoinHandle<()>>>>,
     pending_updates: Arc<Mutex<Vec<TelegramUpdate>>>,
+    update_notify: Arc<tokio::sync::Notify>, // Signalled whenever an update is queued
+    // Polls seen in fetched or received messages, for /vote
+    polls: Arc<Mutex<PollCache>>,
+}
+
+/// Polls kept for /vote; past this the oldest seen are dropped
+const MAX_CACHED_POLLS: usize = 500;
+
+/// Polls by (chat_id, msg_id), bounded to the most recently seen
+#[derive(Default)]
+struct PollCache {
+    polls: std::collections::HashMap<(i64, i32), PollInfo>,
+    order: std::collections::VecDeque<(i64, i32)>,
+}
+
+impl PollCache {
+    fn get(&self, key: &(i64, i32)) -> Option<&PollInfo> {
+        self.polls.get(key)
+    }
+
+    fn insert(&mut self, key: (i64, i32), poll: PollInfo) {
+        if self.polls.insert(key, poll).is_none() {
+            self.order.push_back(key);
+        }
+        if self.order.len() > MAX_CACHED_POLLS
+            && let Some(oldest) = self.order.pop_front()
+        {
+            self.polls.remove(&oldest);
+        }
+    }
+}
+
+/// A poll's question and options as last fetched from Telegram
+#[derive(Debug, Clone, PartialEq)]
+pub struct PollInfo {
+    pub question: String,
+    pub options: Vec<PollOption>,
+    pub total_voters: Option<i32>,
+    pub closed: bool,
+}
+
+/// A group or channel member as listed by /members
+#[derive(Debug, Clone, PartialEq)]
+pub struct Member {
+    pub user_id: i64,
+    pub name: String,
+    pub username: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PollOption {
+    pub text: String,
+    pub option: Vec<u8>,       // Opaque id sent back when voting
+    pub voters: Option<i32>,   // None while Telegram hides the results (e.g. until we vote)
+    pub chosen: bool,          // Our vote
+}
+
+impl PollInfo {
+    fn from_message(message: &grammers_client::types::Message) -> Option<Self> {
+        use grammers_client::types::Media;
+        use grammers_tl_types::enums::TextWithEntities;
+        let Some(Media::Poll(poll)) = message.media() else {
+            return None;
+        };
+        let TextWithEntities::Entities(question) = poll.question();
+        let results: Vec<_> = poll.iter_voters_summary().map(|r| r.collect()).unwrap_or_default();
+        let options = poll
+            .iter_answers()
+            .map(|answer| {
+                let TextWithEntities::Entities(text) = &answer.text;
+                let result = results.iter().find(|r| r.option == answer.option);
+                PollOption {
+                    text: text.text.clone(),
+                    option: answer.option.clone(),
+                    voters: result.map(|r| r.voters),
+                    chosen: result.is_some_and(|r| r.chosen),
+                }
+            })
+            .collect();
+        Some(Self {
+            question: question.text.clone(),
+            options,
+            total_voters: poll.total_voters(),
+            closed: poll.closed(),
+        })
+    }
+
+    /// The question, then one numbered line per option: "1) Option A (40%) ✓"
+    pub fn render(&self) -> String {
+        let mut lines = vec![if self.closed {
+            format!("{} (closed)", self.question)
+        } else {
+            self.question.clone()
+        }];
+        for (i, option) in self.options.iter().enumerate() {
+            let mut line = format!("{}) {}", i + 1, option.text);
+            if let (Some(voters), Some(total)) = (option.voters, self.total_voters) {
+                let percent = if total > 0 { (voters as f64 * 100.0 / total as f64).round() } else { 0.0 };
+                line.push_str(&format!(" ({}%)", percent));
+            }
+            if option.chosen {
+                line.push_str(" ✓");
+            }
+            lines.push(line);
+        }
+        lines.join("\n")
+    }
+
+    pub fn voted(&self) -> bool {
+        self.options.iter().any(|o| o.chosen)
+    }
+
+    /// Why voting for option `idx` (0-based) can't work, if it can't
+    pub fn vote_error(&self, idx: usize) -> Option<String> {
+        if self.closed {
+            Some("the poll is closed".to_string())
+        } else if idx >= self.options.len() {
+            Some(format!("the poll has {} options", self.options.len()))
+        } else if self.voted() {
+            Some("you already voted in this poll".to_string())
+        } else {
+            None
+        }
+    }
+
+    /// Count our vote for option `idx` locally, until the next fetch brings the real results
+    pub fn apply_vote(&mut self, idx: usize) -> bool {
+        if self.vote_error(idx).is_some() {
+            return false;
+        }
+        let option = &mut self.options[idx];
+        option.chosen = true;
+        if let Some(voters) = option.voters.as_mut() {
+            *voters += 1;
+        }
+        if let Some(total) = self.total_voters.as_mut() {
+            *total += 1;
+        }
+        true
+    }
+}
+
+/// Options a poll may have, per Telegram's limits
+const POLL_OPTIONS: std::ops::RangeInclusive<usize> = 2..=10;
+
+/// Check a new poll against Telegram's limits before sending it
+fn validate_poll(question: &str, options: &[String]) -> Result<()> {
+    if question.trim().is_empty() {
+        anyhow::bail!("the question is empty");
+    }
+    if question.chars().count() > 255 {
+        anyhow::bail!("the question is longer than 255 characters");
+    }
+    if !POLL_OPTIONS.contains(&options.len()) {
+        anyhow::bail!(
+            "a poll needs {} to {} options, got {}",
+            POLL_OPTIONS.start(),
+            POLL_OPTIONS.end(),
+            options.len()
+        );
+    }
+    if let Some(option) = options.iter().find(|o| o.trim().is_empty() || o.chars().count() > 100) {
+        anyhow::bail!("option \"{}\" must be 1 to 100 characters", option);
+    }
+    Ok(())
+}
+
+/// Text shown for a message: polls render as question and options
+fn message_text(message: &grammers_client::types::Message) -> String {
+    match PollInfo::from_message(message) {
+        Some(poll) => poll.render(),
+        None => message.text().to_string(),
+    }
+}
+
+/// Blockquote entities of a message as (offset, length) in UTF-16 code units
+fn quote_ranges(message: &grammers_client::types::Message) -> Vec<(usize, usize)> {
+    use grammers_tl_types::enums::MessageEntity;
+    message
+        .fmt_entities()
+        .map(|entities| {
+            entities
+                .iter()
+                .filter_map(|e| match e {
+                    MessageEntity::Blockquote(q) => Some((q.offset as usize, q.length as usize)),
+                    _ => None,
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// (sender_id, sender_name) for display. Messages without a sender and anonymous
+/// group admins (who post as the group itself) collapse into one neutral sender.
+fn message_sender(message: &grammers_client::types::Message) -> (i64, String) {
+    match message.sender() {
+        Some(sender)
+            if matches!(sender, grammers_client::types::Chat::User(_))
+                || message.raw.post
+                || sender.id() != message.chat().id() =>
+        {
+            (sender.id(), sender.name().to_string())
+        }
+        _ => (ANONYMOUS_SENDER_ID, "Anonymous".to_string()),
+    }
+}
+
+const PRIVACY_RESTRICTED: &str =
+    "their privacy settings don't allow adding them; send them an invite link instead";
+
+/// Human-readable reason for an RPC error from adding or removing a chat member
+fn explain_member_error(rpc_name: &str) -> Option<&'static str> {
+    let reason = match rpc_name {
+        "USER_PRIVACY_RESTRICTED" | "USER_NOT_MUTUAL_CONTACT" => PRIVACY_RESTRICTED,
+        "USER_ALREADY_PARTICIPANT" => "already a member",
+        "USER_KICKED" | "USER_BANNED_IN_CHANNEL" => "banned from this chat; unban them first",
+        "USER_CHANNELS_TOO_MUCH" => "they are in too many groups and channels",
+        "USERS_TOO_MUCH" => "the group is full",
+        "USER_NOT_PARTICIPANT" => "not a member",
+        "CHAT_ADMIN_REQUIRED" | "CHAT_WRITE_FORBIDDEN" | "RIGHT_FORBIDDEN" => {
+            "you need admin rights to manage members"
+        }
+        "USER_ADMIN_INVALID" => "admins can only be removed by the admin who promoted them",
+        "BOT_GROUPS_BLOCKED" => "this bot can't be added to groups",
+        _ => return None,
+    };
+    Some(reason)
+}
+
+/// Rights for a permanent ban: revoking view_messages removes the user from the chat
+fn banned_rights() -> grammers_tl_types::types::ChatBannedRights {
+    grammers_tl_types::types::ChatBannedRights {
+        view_messages: true,
+        send_messages: true,
+        send_media: true,
+        send_stickers: true,
+        send_gifs: true,
+        send_games: true,
+        send_inline: true,
+        embed_links: true,
+        send_polls: true,
+        change_info: true,
+        invite_users: true,
+        pin_messages: true,
+        manage_topics: true,
+        send_photos: true,
+        send_videos: true,
+        send_roundvideos: true,
+        send_audios: true,
+        send_voices: true,
+        send_docs: true,
+        send_plain: true,
+        until_date: 0, // 0 = forever
+    }
+}
+
+/// ID of the chat created by a CreateChat/CreateChannel call
+fn new_chat_id(updates: &grammers_tl_types::enums::Updates) -> Result<i64> {
+    use grammers_tl_types::enums::{Chat, Updates};
+    let chats = match updates {
+        Updates::Updates(u) => &u.chats,
+        Updates::Combined(u) => &u.chats,
+        _ => anyhow::bail!("Unexpected response when creating the group"),
+    };
+    chats
+        .iter()
+        .find_map(|chat| match chat {
+            Chat::Chat(c) => Some(c.id),
+            Chat::Forbidden(c) => Some(c.id),
+            Chat::Channel(c) => Some(c.id),
+            _ => None,
+        })
+        .ok_or_else(|| anyhow::anyhow!("Could not determine new group ID"))
+}
+
+/// Short media kind shown in place of text ("photo", "video", ...), if any
+fn media_type(message: &grammers_client::types::Message) -> Option<String> {
+    if let Some(media) = message.media() {
+        use grammers_client::types::Media;
+        Some(match media {
+            Media::Photo(_) => "photo".to_string(),
+            Media::Document(doc) => {
+                if let Some(mime) = doc.mime_type() {
+                    if mime.starts_with("video/") {
+                        "video".to_string()
+                    } else if mime.starts_with("audio/") {
+                        "audio".to_string()
+                    } else {
+                        "document".to_string()
+                    }
+                } else {
+                    "document".to_string()
+                }
+            }
+            Media::Contact(_) => "contact".to_string(),
+            Media::Dice(_) => "dice".to_string(),
+            Media::Poll(_) => "poll".to_string(),
+            Media::Venue(_) => "location".to_string(),
+            Media::Sticker(_) => "sticker".to_string(),
+            _ => "media".to_string(),
+        })
+    } else {
+        None
+    }
+}
+
+/// Size, duration or resolution details for the media label ("1280×720",
+/// "report.pdf (2.3 MB)"), when Telegram sent them
+fn media_label(message: &grammers_client::types::Message) -> Option<String> {
+    use grammers_client::types::Media;
+    use grammers_tl_types::enums::{Photo, PhotoSize};
+
+    // Stickers, GIFs and the like are labelled by kind alone
+    let kind = media_type(message)?;
+    if !matches!(kind.as_str(), "photo" | "video" | "audio" | "voice" | "document") {
+        return None;
+    }
+    let label = match message.media()? {
+        Media::Photo(photo) => {
+            let Some(Photo::Photo(raw)) = &photo.raw.photo else {
+                return None;
+            };
+            let (w, h) = raw
+                .sizes
+                .iter()
+                .filter_map(|size| match size {
+                    PhotoSize::Size(s) => Some((s.w, s.h)),
+                    PhotoSize::PhotoCachedSize(s) => Some((s.w, s.h)),
+                    PhotoSize::Progressive(s) => Some((s.w, s.h)),
+                    _ => None,
+                })
+                .max_by_key(|(w, h)| i64::from(*w) * i64::from(*h))?;
+            format!("{}×{}", w, h)
+        }
+        Media::Document(doc) if kind == "document" => match (doc.name(), doc.size()) {
+            ("", 0) => return None,
+            ("", size) => crate::formatting::format_file_size(size),
+            (name, 0) => name.to_string(),
+            (name, size) => format!("{} ({})", name, crate::formatting::format_file_size(size)),
+        },
+        Media::Document(doc) => crate::formatting::format_duration(doc.duration()? as i64),
+        _ => return None,
+    };
+    Some(label)
+}
+
+/// File reader for uploads that publishes how much has been read, as a percent
+struct ProgressReader {
+    inner: tokio::fs::File,
+    read: usize,
+    size: usize,
+    progress: Arc<AtomicU8>,
+}
+
+impl AsyncRead for ProgressReader {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        let before = buf.filled().len();
+        let result = Pin::new(&mut self.inner).poll_read(cx, buf);
+        if let Poll::Ready(Ok(())) = result {
+            self.read += buf.filled().len() - before;
+            let percent = (self.read * 100).checked_div(self.size).unwrap_or(100).min(100);
+            self.progress.store(percent as u8, Ordering::Relaxed);
+        }
+        result
+    }
 }
 
 impl TelegramClient {
     pub async fn new(config: &Config) -> Result<Self> {
+        Self::connect(config, true).await
+    }
+
+    /// Connect with the active profile's existing session, failing instead of
+    /// prompting for a login (used when switching profiles inside the TUI)
+    pub async fn resume(config: &Config) -> Result<Self> {
+        Self::connect(config, false).await
+    }
+
+    async fn connect(config: &Config, allow_login: bool) -> Result<Self> {
         // Ensure config directory exists before trying to load/save session
         std::fs::create_dir_all(&config.config_dir)?;
 
@@ -41,10 +580,11 @@ impl TelegramClient {
             Session::new()
         };
 
+        let (api_id, api_hash, phone) = config.credentials();
         let client_config = ClientConfig {
             session,
-            api_id: config.api_id,
-            api_hash: config.api_hash.clone(),
+            api_id,
+            api_hash,
             params: InitParams {
                 ..Default::default()
             },
@@ -54,7 +594,14 @@ impl TelegramClient {
 
         // Check if we're authorized
         if !client.is_authorized().await? {
-            Self::sign_in(&client).await?;
+            if !allow_login {
+                anyhow::bail!(
+                    "profile '{}' is not logged in; start with --profile {} to log in",
+                    config.profile,
+                    config.profile
+                );
+            }
+            Self::sign_in(&client, phone.as_deref()).await?;
         }
 
         // Always save session after connecting
@@ -62,22 +609,29 @@ impl TelegramClient {
         std::fs::write(&session_path, &session_data)?;
 
         Ok(Self {
+            connection: Arc::new(std::sync::Mutex::new(ConnectionState::Connecting)),
+            chat_cache: Arc::new(Mutex::new(std::collections::HashMap::new())),
             update_handle: Arc::new(Mutex::new(None)),
             pending_updates: Arc::new(Mutex::new(Vec::new())),
+            update_notify: Arc::new(tokio::sync::Notify::new()),
+            polls: Arc::new(Mutex::new(PollCache::default())),
             client: Arc::new(Mutex::new(client)),
         })
     }
 
-    async fn sign_in(client: &Client) -> Result<()> {
+    /// Log in with a code sent to `phone`, asking for the number if it isn't configured
+    async fn sign_in(client: &Client, phone: Option<&str>) -> Result<()> {
         use std::io::{self, Write};
 
-        print!("Enter your phone number (international format): ");
-        io::stdout().flush()?;
-        let mut phone = String::new();
-        io::stdin().read_line(&mut phone)?;
-        let phone = phone.trim();
+        let phone = match phone {
+            Some(phone) => phone.to_string(),
+            None => crate::config::prompt_until_valid(
+                "Enter your phone number (international format): ",
+                crate::config::validate_phone,
+            )?,
+        };
 
-        let token = client.request_login_code(phone).await?;
+        let token = client.request_login_code(&phone).await?;
 
         print!("Enter the code you received: ");
         io::stdout().flush()?;
@@ -104,25 +658,40 @@ impl TelegramClient {
         Ok(())
     }
 
-    pub async fn get_me(&self) -> Result<i64> {
+    /// Returns the logged-in user's (id, display name)
+    pub async fn get_me(&self) -> Result<(i64, String)> {
         let client = self.client.lock().await;
         let user = client.get_me().await?;
-        Ok(user.id())
+        Ok((user.id(), user.full_name()))
+    }
+
+    pub fn connection_state(&self) -> ConnectionState {
+        *self.connection.lock().unwrap()
+    }
+
+    fn set_connection_state(&self, state: ConnectionState) {
+        *self.connection.lock().unwrap() = state;
+    }
+
+    /// Convert a request result, updating the connection state from its outcome
+    fn track<T>(&self, result: std::result::Result<T, InvocationError>) -> Result<T> {
+        let result = result.map_err(anyhow::Error::from);
+        if let Some(state) = ConnectionState::after(&result) {
+            self.set_connection_state(state);
+        }
+        result
     }
 
     pub async fn get_dialogs(&self) -> Result<Vec<ChatInfo>> {
         let client = self.client.lock().await;
         let mut chats = Vec::new();
 
+        // Entries are overwritten rather than cleared so resolved chats without a dialog survive
+        let mut cache = self.chat_cache.lock().await;
         let mut dialogs = client.iter_dialogs();
-        while let Some(dialog) = dialogs.next().await? {
+        while let Some(dialog) = self.track(dialogs.next().await)? {
             let chat = dialog.chat();
-
-            let chat_type = match chat {
-                grammers_client::types::Chat::User(_) => (false, false),
-                grammers_client::types::Chat::Group(_) => (false, true),
-                grammers_client::types::Chat::Channel(_) => (true, false),
-            };
+            cache.insert(chat.id(), chat.clone());
 
             // Extract username
             let username = match chat {
@@ -135,6 +704,14 @@ impl TelegramClient {
                 _ => None,
             };
 
+            // Broadcast channels only accept posts from the creator or admins with post rights
+            let can_post = match chat {
+                grammers_client::types::Chat::Channel(c) => {
+                    c.admin_rights().is_some_and(|r| r.post_messages)
+                }
+                _ => true,
+            };
+
             // Get chat name with fallback for empty names
             let chat_name = chat.name().to_string();
             let display_name = if chat_name.trim().is_empty() {
@@ -153,11 +730,22 @@ impl TelegramClient {
                     grammers_client::grammers_tl_types::enums::Dialog::Dialog(d) => d.unread_count as u32,
                     _ => 0,
                 },
-                _is_channel: chat_type.0,
-                is_group: chat_type.1,
+                kind: ChatKind::of(chat),
+                can_post,
+                muted: match &dialog.raw {
+                    grammers_client::grammers_tl_types::enums::Dialog::Dialog(d) => {
+                        is_muted(&d.notify_settings, chrono::Utc::now().timestamp())
+                    }
+                    _ => false,
+                },
+                last_message_date: dialog
+                    .last_message
+                    .as_ref()
+                    .map_or(0, |m| m.date().timestamp()),
             });
         }
 
+        sort_by_activity(&mut chats);
         Ok(chats)
     }
 
@@ -165,7 +753,17 @@ impl TelegramClient {
         &self,
         chat_id: i64,
         limit: usize,
-    ) -> Result<Vec<(i32, i64, String, String, Option<i32>, Option<String>, std::collections::HashMap<String, u32>)>> {
+    ) -> Result<Vec<RawMessage>> {
+        self.get_messages_before(chat_id, 0, limit).await
+    }
+
+    /// Up to `limit` messages older than `before_id` (0 = newest), oldest first
+    pub async fn get_messages_before(
+        &self,
+        chat_id: i64,
+        before_id: i32,
+        limit: usize,
+    ) -> Result<Vec<RawMessage>> {
         let client = self.client.lock().await;
 
         let chat = match self.find_chat_inner(&client, chat_id).await? {
@@ -174,7 +772,7 @@ impl TelegramClient {
         };
 
         let mut messages = Vec::new();
-        let mut iter = client.iter_messages(&chat);
+        let mut iter = client.iter_messages(&chat).offset_id(before_id);
 
         let mut count = 0;
         while let Some(message) = iter.next().await? {
@@ -182,44 +780,17 @@ impl TelegramClient {
                 break;
             }
 
-            let text = message.text();
-            let (sender_id, sender_name) = if let Some(sender) = message.sender() {
-                (sender.id(), sender.name().to_string())
-            } else {
-                (0, "Unknown".to_string())
-            };
+            let text = message_text(&message);
+            let (sender_id, sender_name) = message_sender(&message);
+            if let Some(poll) = PollInfo::from_message(&message) {
+                self.polls.lock().await.insert((chat_id, message.id()), poll);
+            }
 
             // Check if this is a reply
             let reply_to_id = message.reply_to_message_id();
 
-            // Detect media type
-            let media_type = if let Some(media) = message.media() {
-                use grammers_client::types::Media;
-                Some(match media {
-                    Media::Photo(_) => "photo".to_string(),
-                    Media::Document(doc) => {
-                        if let Some(mime) = doc.mime_type() {
-                            if mime.starts_with("video/") {
-                                "video".to_string()
-                            } else if mime.starts_with("audio/") {
-                                "audio".to_string()
-                            } else {
-                                "document".to_string()
-                            }
-                        } else {
-                            "document".to_string()
-                        }
-                    }
-                    Media::Contact(_) => "contact".to_string(),
-                    Media::Dice(_) => "dice".to_string(),
-                    Media::Poll(_) => "poll".to_string(),
-                    Media::Venue(_) => "location".to_string(),
-                    Media::Sticker(_) => "sticker".to_string(),
-                    _ => "media".to_string(),
-                })
-            } else {
-                None
-            };
+            let media_type = media_type(&message);
+            let media_label = media_label(&message);
 
             // Get reactions from message
             let mut reactions = std::collections::HashMap::new();
@@ -244,15 +815,18 @@ impl TelegramClient {
 
             // Include messages with text or media
             if !text.is_empty() || media_type.is_some() {
-                messages.push((
-                    message.id(),
+                messages.push(RawMessage {
+                    id: message.id(),
                     sender_id,
                     sender_name,
-                    text.to_string(),
+                    text: text.to_string(),
+                    quote_ranges: quote_ranges(&message),
                     reply_to_id,
                     media_type,
+                    media_label,
                     reactions,
-                ));
+                    timestamp: message.date().timestamp(),
+                });
             }
 
             count += 1;
@@ -267,7 +841,26 @@ impl TelegramClient {
         let chat = self.find_chat_inner(&client, chat_id).await?;
 
         if let Some(chat) = chat {
-            client.send_message(&chat, text).await?;
+            self.track(client.send_message(&chat, text).await)?;
+        }
+
+        Ok(())
+    }
+
+    /// Send text with explicit formatting entities (offsets/lengths in UTF-16 units)
+    pub async fn send_formatted_message(
+        &self,
+        chat_id: i64,
+        text: &str,
+        entities: Vec<grammers_tl_types::enums::MessageEntity>,
+    ) -> Result<()> {
+        let client = self.client.lock().await;
+        let chat = self.find_chat_inner(&client, chat_id).await?;
+
+        if let Some(chat) = chat {
+            use grammers_client::InputMessage;
+            let input = InputMessage::text(text).fmt_entities(entities);
+            self.track(client.send_message(&chat, input).await)?;
         }
 
         Ok(())
@@ -285,12 +878,69 @@ impl TelegramClient {
         if let Some(chat) = chat {
             use grammers_client::InputMessage;
             let input = InputMessage::text(text).reply_to(Some(message_id));
-            client.send_message(&chat, input).await?;
+            self.track(client.send_message(&chat, input).await)?;
         }
 
         Ok(())
     }
 
+    /// Send a text message (optionally as a reply) in the background. Failures are
+    /// reported through the update queue: SendQueued when the network is down,
+    /// SendFailed for anything else.
+    pub async fn send_or_queue(&self, chat_id: i64, text: String, reply_to: Option<i32>) {
+        let result = match reply_to {
+            Some(msg_id) => self.reply_to_message(chat_id, msg_id, &text).await,
+            None => self.send_message(chat_id, &text).await,
+        };
+        let Err(e) = &result else { return };
+        let update = if ConnectionState::after(&result) == Some(ConnectionState::Offline) {
+            TelegramUpdate::SendQueued { chat_id, text, reply_to }
+        } else {
+            TelegramUpdate::SendFailed { chat_id, text, error: e.to_string() }
+        };
+        self.queue_update(update).await;
+    }
+
+    /// Upload a local file, as a photo for common image types and as a document
+    /// otherwise. The client lock is released before the upload so other requests
+    /// aren't stuck behind a large file. `progress` is kept at the percent read so far.
+    pub async fn upload_file(
+        &self,
+        chat_id: i64,
+        path: &Path,
+        caption: Option<&str>,
+        progress: Arc<AtomicU8>,
+    ) -> Result<()> {
+        let (client, chat) = {
+            let client = self.client.lock().await;
+            let chat = self.find_chat_inner(&client, chat_id).await?;
+            (client.clone(), chat)
+        };
+        let Some(chat) = chat else {
+            anyhow::bail!("Chat not found");
+        };
+
+        let file = tokio::fs::File::open(path).await?;
+        let size = file.metadata().await?.len() as usize;
+        let name = path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+        let mut reader = ProgressReader {
+            inner: file,
+            read: 0,
+            size,
+            progress,
+        };
+        let uploaded = client.upload_stream(&mut reader, size, name).await?;
+        use grammers_client::InputMessage;
+        let input = InputMessage::text(caption.unwrap_or(""));
+        let input = if crate::utils::is_image_path(path) {
+            input.photo(uploaded)
+        } else {
+            input.document(uploaded)
+        };
+        self.track(client.send_message(&chat, input).await)?;
+        Ok(())
+    }
+
     pub async fn edit_message(
         &self,
         chat_id: i64,
@@ -316,14 +966,35 @@ impl TelegramClient {
         Ok(())
     }
 
-    pub async fn delete_message(&self, chat_id: i64, message_id: i32) -> Result<()> {
+    /// Delete a message for everyone (`revoke`) or only from our own history.
+    /// Supergroups and channels have a single shared history, so there a delete
+    /// always applies to everyone and "only for me" is refused.
+    pub async fn delete_message(&self, chat_id: i64, message_id: i32, revoke: bool) -> Result<()> {
         let client = self.client.lock().await;
-        let chat = self.find_chat_inner(&client, chat_id).await?;
+        let chat = self.find_chat_inner(&client, chat_id).await?
+            .ok_or_else(|| anyhow::anyhow!("Chat not found"))?;
 
-        if let Some(chat) = chat {
-            client.delete_messages(&chat, &[message_id]).await?;
+        let result = match chat.pack().try_to_input_channel() {
+            Some(_) if !revoke => {
+                anyhow::bail!("Messages in supergroups and channels can only be deleted for everyone")
+            }
+            Some(channel) => client
+                .invoke(&grammers_tl_types::functions::channels::DeleteMessages {
+                    channel,
+                    id: vec![message_id],
+                })
+                .await,
+            None => client
+                .invoke(&grammers_tl_types::functions::messages::DeleteMessages {
+                    revoke,
+                    id: vec![message_id],
+                })
+                .await,
+        };
+        let grammers_tl_types::enums::messages::AffectedMessages::Messages(affected) = self.track(result)?;
+        if affected.pts_count == 0 {
+            anyhow::bail!("Nothing was deleted (you may lack the rights to delete it)");
         }
-
         Ok(())
     }
 
@@ -332,57 +1003,99 @@ impl TelegramClient {
         let client = self.client.lock().await;
         match client.resolve_username(username).await? {
             Some(chat) => {
-                let is_group = matches!(
-                    chat,
-                    grammers_client::types::Chat::Group(_) | grammers_client::types::Chat::Channel(_)
-                );
-                Ok(Some((chat.id(), chat.name().to_string(), is_group)))
+                let resolved = (chat.id(), chat.name().to_string(), ChatKind::of(&chat).is_group());
+                // Remember it so history and sends work before a dialog exists
+                self.chat_cache.lock().await.insert(chat.id(), chat);
+                Ok(Some(resolved))
             }
             None => Ok(None),
         }
     }
 
-    pub async fn create_group(&self, title: &str, user_ids: Vec<i64>) -> Result<i64> {
+    /// Resolve @username to a user and their last seen status: (name, status)
+    pub async fn lookup_user_status(&self, username: &str) -> Result<(String, UserStatus)> {
+        let username = username.trim_start_matches('@');
         let client = self.client.lock().await;
-
-        // Resolve user IDs to InputUser via dialogs
-        let mut input_users = Vec::new();
-        for user_id in user_ids {
-            if let Some(chat) = self.find_chat_inner(&client, user_id).await? {
-                let packed = chat.pack();
-                if let Some(input_user) = packed.try_to_input_user() {
-                    input_users.push(input_user);
-                }
+        match client.resolve_username(username).await? {
+            Some(grammers_client::types::Chat::User(user)) => {
+                Ok((user.full_name(), UserStatus::from_raw(user.status())))
             }
+            Some(_) => anyhow::bail!("@{} is not a user", username),
+            None => anyhow::bail!("User '{}' not found", username),
         }
+    }
 
-        let result = client.invoke(&grammers_tl_types::functions::messages::CreateChat {
-            users: input_users,
-            title: title.to_string(),
-            ttl_period: None,
-        }).await?;
+    /// Fetch the current last seen status of a user we have a chat with
+    pub async fn get_user_status(&self, user_id: i64) -> Result<UserStatus> {
+        let client = self.client.lock().await;
+        let chat = self.find_chat_inner(&client, user_id).await?
+            .ok_or_else(|| anyhow::anyhow!("Chat not found"))?;
+        let input_user = chat.pack().try_to_input_user()
+            .ok_or_else(|| anyhow::anyhow!("Not a user"))?;
 
-        // Extract chat ID from the result
-        use grammers_tl_types::enums::messages::InvitedUsers;
-        let InvitedUsers::Users(invited) = result;
+        let users = self.track(
+            client
+                .invoke(&grammers_tl_types::functions::users::GetUsers { id: vec![input_user] })
+                .await,
+        )?;
+        match users.first() {
+            Some(grammers_tl_types::enums::User::User(user)) if user.bot => {
+                anyhow::bail!("Bots have no last seen status")
+            }
+            Some(grammers_tl_types::enums::User::User(user)) => Ok(user
+                .status
+                .as_ref()
+                .map_or(UserStatus::LongAgo, UserStatus::from_raw)),
+            _ => anyhow::bail!("User not found"),
+        }
+    }
 
-        // Look through chats in the Updates
-        let chats = match &invited.updates {
-            grammers_tl_types::enums::Updates::Updates(u) => &u.chats,
-            grammers_tl_types::enums::Updates::Combined(u) => &u.chats,
-            _ => return Err(anyhow::anyhow!("Unexpected response from CreateChat")),
-        };
+    /// Create a group titled `title` with the given @usernames as initial members.
+    /// Basic groups need at least one invitee, so an empty group is created as a supergroup.
+    pub async fn create_group(&self, title: &str, members: &[String]) -> Result<i64> {
+        let client = self.client.lock().await;
 
-        for chat in chats {
-            use grammers_tl_types::enums::Chat;
-            match chat {
-                Chat::Chat(c) => return Ok(c.id),
-                Chat::Forbidden(c) => return Ok(c.id),
-                _ => {}
-            }
+        let mut input_users = Vec::new();
+        for member in members {
+            let username = member.trim_start_matches('@');
+            let user = client
+                .resolve_username(username)
+                .await?
+                .ok_or_else(|| anyhow::anyhow!("User @{} not found", username))?;
+            let input_user = user
+                .pack()
+                .try_to_input_user()
+                .ok_or_else(|| anyhow::anyhow!("@{} is not a user", username))?;
+            input_users.push(input_user);
         }
 
-        anyhow::bail!("Could not determine new group ID")
+        let updates = if input_users.is_empty() {
+            client
+                .invoke(&grammers_tl_types::functions::channels::CreateChannel {
+                    broadcast: false,
+                    megagroup: true,
+                    for_import: false,
+                    forum: false,
+                    title: title.to_string(),
+                    about: String::new(),
+                    geo_point: None,
+                    address: None,
+                    ttl_period: None,
+                })
+                .await?
+        } else {
+            use grammers_tl_types::enums::messages::InvitedUsers;
+            let InvitedUsers::Users(invited) = client
+                .invoke(&grammers_tl_types::functions::messages::CreateChat {
+                    users: input_users,
+                    title: title.to_string(),
+                    ttl_period: None,
+                })
+                .await?;
+            invited.updates
+        };
+
+        new_chat_id(&updates)
     }
 
     pub async fn add_member(&self, chat_id: i64, username: &str) -> Result<()> {
@@ -398,25 +1111,45 @@ impl TelegramClient {
             .ok_or_else(|| anyhow::anyhow!("Cannot convert to input user"))?;
 
         let chat_packed = chat.pack();
-        if let Some(channel) = chat_packed.try_to_input_channel() {
-            client.invoke(&grammers_tl_types::functions::channels::InviteToChannel {
-                channel,
-                users: vec![input_user],
-            }).await?;
-        } else if let Some(chat_id_inner) = chat_packed.try_to_chat_id() {
-            client.invoke(&grammers_tl_types::functions::messages::AddChatUser {
-                chat_id: chat_id_inner,
-                user_id: input_user,
-                fwd_limit: 100,
-            }).await?;
-        } else {
-            anyhow::bail!("Cannot add members to this chat type");
-        }
+        let invited = match ChatKind::of(&chat) {
+            ChatKind::BasicGroup => {
+                let chat_id_inner = chat_packed.try_to_chat_id()
+                    .ok_or_else(|| anyhow::anyhow!("Cannot resolve group"))?;
+                client.invoke(&grammers_tl_types::functions::messages::AddChatUser {
+                    chat_id: chat_id_inner,
+                    user_id: input_user,
+                    fwd_limit: 100,
+                }).await
+            }
+            ChatKind::Supergroup | ChatKind::Channel => {
+                let channel = chat_packed.try_to_input_channel()
+                    .ok_or_else(|| anyhow::anyhow!("Cannot resolve channel"))?;
+                client.invoke(&grammers_tl_types::functions::channels::InviteToChannel {
+                    channel,
+                    users: vec![input_user],
+                }).await
+            }
+            ChatKind::User => anyhow::bail!("Members can only be added to groups and channels"),
+        };
 
-        Ok(())
+        use grammers_tl_types::enums::messages::InvitedUsers;
+        match invited {
+            // Privacy-restricted users are reported as missing rather than as an error
+            Ok(InvitedUsers::Users(invited)) if !invited.missing_invitees.is_empty() => {
+                anyhow::bail!(PRIVACY_RESTRICTED)
+            }
+            Ok(_) => Ok(()),
+            Err(InvocationError::Rpc(rpc)) => match explain_member_error(&rpc.name) {
+                Some(reason) => anyhow::bail!(reason),
+                None => Err(InvocationError::Rpc(rpc).into()),
+            },
+            Err(e) => Err(e.into()),
+        }
     }
 
-    pub async fn remove_member(&self, chat_id: i64, username: &str) -> Result<()> {
+    /// Remove `username` from a group, returning their display name. Supergroups and
+    /// channels ban them (no view rights) so they can't simply rejoin.
+    pub async fn remove_member(&self, chat_id: i64, username: &str) -> Result<String> {
         let username = username.trim_start_matches('@');
         let client = self.client.lock().await;
         let chat = self.find_chat_inner(&client, chat_id).await?
@@ -424,21 +1157,59 @@ impl TelegramClient {
         let user_chat = client.resolve_username(username).await?
             .ok_or_else(|| anyhow::anyhow!("User '{}' not found", username))?;
 
-        client.kick_participant(&chat, &user_chat).await?;
-        Ok(())
+        let removed = match ChatKind::of(&chat) {
+            ChatKind::BasicGroup => {
+                let chat_id_inner = chat.pack().try_to_chat_id()
+                    .ok_or_else(|| anyhow::anyhow!("Cannot resolve group"))?;
+                let user_id = user_chat.pack().try_to_input_user()
+                    .ok_or_else(|| anyhow::anyhow!("Cannot convert to input user"))?;
+                client.invoke(&grammers_tl_types::functions::messages::DeleteChatUser {
+                    chat_id: chat_id_inner,
+                    user_id,
+                    revoke_history: false,
+                }).await.map(drop)
+            }
+            ChatKind::Supergroup | ChatKind::Channel => {
+                let channel = chat.pack().try_to_input_channel()
+                    .ok_or_else(|| anyhow::anyhow!("Cannot resolve channel"))?;
+                client.invoke(&grammers_tl_types::functions::channels::EditBanned {
+                    channel,
+                    participant: user_chat.pack().to_input_peer(),
+                    banned_rights: banned_rights().into(),
+                }).await.map(drop)
+            }
+            ChatKind::User => anyhow::bail!("Members can only be removed from groups and channels"),
+        };
+
+        match removed {
+            Ok(()) => Ok(user_chat.name().to_string()),
+            Err(InvocationError::Rpc(rpc)) => match explain_member_error(&rpc.name) {
+                Some(reason) => anyhow::bail!(reason),
+                None => Err(InvocationError::Rpc(rpc).into()),
+            },
+            Err(e) => Err(e.into()),
+        }
     }
 
-    pub async fn get_members(&self, chat_id: i64) -> Result<Vec<(i64, String, String)>> {
+    /// Up to `limit` members of a group or channel
+    pub async fn get_participants(&self, chat_id: i64, limit: usize) -> Result<Vec<Member>> {
         let client = self.client.lock().await;
         let chat = self.find_chat_inner(&client, chat_id).await?
             .ok_or_else(|| anyhow::anyhow!("Chat not found"))?;
+        if ChatKind::of(&chat) == ChatKind::User {
+            anyhow::bail!("Not a group");
+        }
 
         let mut members = Vec::new();
         let mut iter = client.iter_participants(&chat);
-        while let Some(participant) = iter.next().await? {
-            let role = format!("{:?}", participant.role);
-            let name = participant.user.first_name().to_string();
-            members.push((participant.user.id(), name, role));
+        while members.len() < limit {
+            let Some(participant) = iter.next().await? else { break };
+            let user = participant.user;
+            members.push(Member {
+                user_id: user.id(),
+                name: user.full_name(),
+                username: user.username().map(str::to_string),
+            });
         }
 
         Ok(members)
@@ -451,13 +1222,14 @@ impl TelegramClient {
         to_chat_id: i64,
     ) -> Result<()> {
         let client = self.client.lock().await;
-        let from_chat = self.find_chat_inner(&client, from_chat_id).await?;
-        let to_chat = self.find_chat_inner(&client, to_chat_id).await?;
-
-        if let (Some(from), Some(to)) = (from_chat, to_chat) {
-            client
-                .forward_messages(&to, &[message_id], &from)
-                .await?;
+        let from = self.find_chat_inner(&client, from_chat_id).await?
+            .ok_or_else(|| anyhow::anyhow!("Source chat not found"))?;
+        let to = self.find_chat_inner(&client, to_chat_id).await?
+            .ok_or_else(|| anyhow::anyhow!("Destination chat not found"))?;
+
+        let forwarded = self.track(client.forward_messages(&to, &[message_id], &from).await)?;
+        if forwarded.iter().all(Option::is_none) {
+            anyhow::bail!("Message was not forwarded (it may have been deleted)");
         }
 
         Ok(())
@@ -658,18 +1430,21 @@ impl TelegramClient {
         anyhow::bail!("Chat not found")
     }
 
+    /// Search a chat, newest first from `offset_id` (0 = latest). Results are returned
+    /// oldest first.
     pub async fn search_messages(
         &self,
         chat_id: i64,
         query: &str,
         limit: usize,
-    ) -> Result<Vec<(i32, i64, String, String, Option<i32>, std::collections::HashMap<String, u32>)>> {
+        offset_id: i32,
+    ) -> Result<Vec<RawMessage>> {
         let client = self.client.lock().await;
         let chat = self.find_chat_inner(&client, chat_id).await?;
 
         if let Some(chat) = chat {
             let mut messages = Vec::new();
-            let mut iter = client.search_messages(&chat).query(query);
+            let mut iter = client.search_messages(&chat).query(query).offset_id(offset_id);
 
             let mut count = 0;
             while let Some(message) = iter.next().await? {
@@ -678,11 +1453,7 @@ impl TelegramClient {
                 }
 
                 let text = message.text();
-                let (sender_id, sender_name) = if let Some(sender) = message.sender() {
-                    (sender.id(), sender.name().to_string())
-                } else {
-                    (0, "Unknown".to_string())
-                };
+                let (sender_id, sender_name) = message_sender(&message);
 
                 let reply_to_id = message.reply_to_message_id();
 
@@ -708,7 +1479,18 @@ impl TelegramClient {
                 }
 
                 if !text.is_empty() {
-                    messages.push((message.id(), sender_id, sender_name, text.to_string(), reply_to_id, reactions));
+                    messages.push(RawMessage {
+                        id: message.id(),
+                        sender_id,
+                        sender_name,
+                        text: text.to_string(),
+                        quote_ranges: quote_ranges(&message),
+                        reply_to_id,
+                        media_type: None,
+                        media_label: None,
+                        reactions,
+                        timestamp: message.date().timestamp(),
+                    });
                 }
                 count += 1;
             }
@@ -720,6 +1502,276 @@ impl TelegramClient {
         Ok(Vec::new())
     }
 
+    /// Show (or stop showing) "typing…" to the other side of a chat. Telegram
+    /// expires the status after a few seconds, so it must be resent while typing.
+    pub async fn set_typing(&self, chat_id: i64, typing: bool) -> Result<()> {
+        let client = self.client.lock().await;
+        let chat = self.find_chat_inner(&client, chat_id).await?
+            .ok_or_else(|| anyhow::anyhow!("Chat not found"))?;
+
+        use grammers_tl_types::enums::SendMessageAction;
+        let action = if typing {
+            SendMessageAction::SendMessageTypingAction
+        } else {
+            SendMessageAction::SendMessageCancelAction
+        };
+        self.track(client.action(&chat).oneshot(action).await)
+    }
+
+    /// Look up specific messages as (msg_id, sender_name, text); deleted ones are
+    /// left out. Media without a caption is described as "[Photo]" etc.
+    pub async fn get_messages_by_id(&self, chat_id: i64, ids: &[i32]) -> Result<Vec<(i32, String, String)>> {
+        let client = self.client.lock().await;
+        let chat = self.find_chat_inner(&client, chat_id).await?
+            .ok_or_else(|| anyhow::anyhow!("Chat not found"))?;
+
+        let messages = self.track(client.get_messages_by_id(&chat, ids).await)?;
+        Ok(messages
+            .into_iter()
+            .flatten()
+            .map(|message| {
+                let (_, sender_name) = message_sender(&message);
+                let text = match message.text().to_string() {
+                    text if text.is_empty() => media_type(&message)
+                        .map(|media| format!("[{}]", media))
+                        .unwrap_or_default(),
+                    text => text,
+                };
+                (message.id(), sender_name, text)
+            })
+            .collect())
+    }
+
+    /// Silence (or restore) notifications for a chat on all devices
+    pub async fn set_mute(&self, chat_id: i64, muted: bool) -> Result<()> {
+        let client = self.client.lock().await;
+        let chat = self.find_chat_inner(&client, chat_id).await?
+            .ok_or_else(|| anyhow::anyhow!("Chat not found"))?;
+
+        let peer = grammers_tl_types::enums::InputNotifyPeer::Peer(grammers_tl_types::types::InputNotifyPeer {
+            peer: chat.pack().to_input_peer(),
+        });
+        let settings = grammers_tl_types::types::InputPeerNotifySettings {
+            show_previews: None,
+            silent: None,
+            // Telegram treats a mute_until far in the future as "muted forever"
+            mute_until: Some(if muted { i32::MAX } else { 0 }),
+            sound: None,
+            stories_muted: None,
+            stories_hide_sender: None,
+            stories_sound: None,
+        };
+        self.track(
+            client
+                .invoke(&grammers_tl_types::functions::account::UpdateNotifySettings {
+                    peer,
+                    settings: settings.into(),
+                })
+                .await,
+        )?;
+        Ok(())
+    }
+
+    /// Tell Telegram we've read a chat up to `max_id`, so other devices drop the badge too
+    pub async fn mark_read(&self, chat_id: i64, max_id: i32) -> Result<()> {
+        let client = self.client.lock().await;
+        let chat = self.find_chat_inner(&client, chat_id).await?
+            .ok_or_else(|| anyhow::anyhow!("Chat not found"))?;
+
+        let packed = chat.pack();
+        let result = match packed.try_to_input_channel() {
+            Some(channel) => client
+                .invoke(&grammers_tl_types::functions::channels::ReadHistory { channel, max_id })
+                .await
+                .map(drop),
+            None => client
+                .invoke(&grammers_tl_types::functions::messages::ReadHistory {
+                    peer: packed.to_input_peer(),
+                    max_id,
+                })
+                .await
+                .map(drop),
+        };
+        self.track(result)
+    }
+
+    /// Flag a chat as unread (the dot Telegram apps show until it's opened again)
+    pub async fn mark_unread(&self, chat_id: i64) -> Result<()> {
+        let client = self.client.lock().await;
+        let chat = self.find_chat_inner(&client, chat_id).await?
+            .ok_or_else(|| anyhow::anyhow!("Chat not found"))?;
+
+        let peer = grammers_tl_types::enums::InputDialogPeer::Peer(grammers_tl_types::types::InputDialogPeer {
+            peer: chat.pack().to_input_peer(),
+        });
+        self.track(
+            client
+                .invoke(&grammers_tl_types::functions::messages::MarkDialogUnread { unread: true, peer })
+                .await,
+        )?;
+        Ok(())
+    }
+
+    /// Emoji reactions the server currently offers (premium-only ones left out)
+    pub async fn get_available_reactions(&self) -> Result<Vec<String>> {
+        use grammers_tl_types::enums::{messages::AvailableReactions, AvailableReaction};
+        let client = self.client.lock().await;
+        let result = client
+            .invoke(&grammers_tl_types::functions::messages::GetAvailableReactions { hash: 0 })
+            .await?;
+        Ok(match result {
+            AvailableReactions::Reactions(r) => r
+                .reactions
+                .into_iter()
+                .filter_map(|AvailableReaction::Reaction(r)| {
+                    (!r.inactive && !r.premium).then_some(r.reaction)
+                })
+                .collect(),
+            AvailableReactions::NotModified => Vec::new(),
+        })
+    }
+
+    /// Set our reaction on a message; an empty `emoji` removes it
+    pub async fn send_reaction(&self, chat_id: i64, message_id: i32, emoji: &str) -> Result<()> {
+        let client = self.client.lock().await;
+        let chat = self.find_chat_inner(&client, chat_id).await?
+            .ok_or_else(|| anyhow::anyhow!("Chat not found"))?;
+
+        let reaction = (!emoji.is_empty()).then(|| {
+            vec![grammers_tl_types::enums::Reaction::Emoji(
+                grammers_tl_types::types::ReactionEmoji { emoticon: emoji.to_string() },
+            )]
+        });
+        self.track(client.invoke(&grammers_tl_types::functions::messages::SendReaction {
+            big: false,
+            add_to_recent: !emoji.is_empty(),
+            peer: chat.pack().to_input_peer(),
+            msg_id: message_id,
+            reaction,
+        }).await)?;
+        Ok(())
+    }
+
+    /// A poll as last fetched, if the message was loaded and is a poll
+    pub async fn cached_poll(&self, chat_id: i64, message_id: i32) -> Option<PollInfo> {
+        self.polls.lock().await.get(&(chat_id, message_id)).cloned()
+    }
+
+    /// Vote for option `option` (0-based) of the poll in a message
+    pub async fn vote_poll(&self, chat_id: i64, message_id: i32, option: usize) -> Result<()> {
+        let client = self.client.lock().await;
+        let chat = self.find_chat_inner(&client, chat_id).await?
+            .ok_or_else(|| anyhow::anyhow!("Chat not found"))?;
+
+        let cached = self.polls.lock().await.get(&(chat_id, message_id)).cloned();
+        let poll = match cached {
+            Some(poll) => poll,
+            None => {
+                let messages = self.track(client.get_messages_by_id(&chat, &[message_id]).await)?;
+                messages
+                    .into_iter()
+                    .flatten()
+                    .find_map(|m| PollInfo::from_message(&m))
+                    .ok_or_else(|| anyhow::anyhow!("Not a poll"))?
+            }
+        };
+        if let Some(reason) = poll.vote_error(option) {
+            anyhow::bail!(reason);
+        }
+
+        self.track(client.invoke(&grammers_tl_types::functions::messages::SendVote {
+            peer: chat.pack().to_input_peer(),
+            msg_id: message_id,
+            options: vec![poll.options[option].option.clone()],
+        }).await)?;
+
+        // Until the message is fetched again, so a second /vote is refused locally
+        let mut poll = poll;
+        poll.apply_vote(option);
+        self.polls.lock().await.insert((chat_id, message_id), poll);
+        Ok(())
+    }
+
+    /// Send a poll with 2-10 options to a chat
+    pub async fn create_poll(&self, chat_id: i64, question: &str, options: &[String]) -> Result<()> {
+        use grammers_tl_types::{enums, types};
+        validate_poll(question, options)?;
+
+        let client = self.client.lock().await;
+        let chat = self.find_chat_inner(&client, chat_id).await?
+            .ok_or_else(|| anyhow::anyhow!("Chat not found"))?;
+
+        let text = |text: &str| {
+            enums::TextWithEntities::Entities(types::TextWithEntities {
+                text: text.to_string(),
+                entities: Vec::new(),
+            })
+        };
+        let poll = types::Poll {
+            id: 0, // Assigned by Telegram
+            closed: false,
+            public_voters: false,
+            multiple_choice: false,
+            quiz: false,
+            question: text(question),
+            answers: options
+                .iter()
+                .enumerate()
+                .map(|(i, option)| {
+                    enums::PollAnswer::Answer(types::PollAnswer {
+                        text: text(option),
+                        option: vec![b'0' + i as u8], // Any bytes unique within the poll
+                    })
+                })
+                .collect(),
+            close_period: None,
+            close_date: None,
+        };
+        let media = grammers_client::types::media::Poll::from_raw_media(types::MessageMediaPoll {
+            poll: enums::Poll::Poll(poll),
+            results: enums::PollResults::Results(types::PollResults {
+                min: false,
+                results: None,
+                total_voters: None,
+                recent_voters: None,
+                solution: None,
+                solution_entities: None,
+            }),
+        });
+
+        use grammers_client::InputMessage;
+        let message = InputMessage::text("").copy_media(&grammers_client::types::Media::Poll(media));
+        self.track(client.send_message(&chat, message).await)?;
+        Ok(())
+    }
+
+    /// Fetch pinned messages of a chat, oldest first: (msg_id, sender_name, text)
+    pub async fn get_pinned_messages(&self, chat_id: i64) -> Result<Vec<(i32, String, String)>> {
+        let client = self.client.lock().await;
+        let chat = self.find_chat_inner(&client, chat_id).await?
+            .ok_or_else(|| anyhow::anyhow!("Chat not found"))?;
+
+        let mut pinned = Vec::new();
+        let mut iter = client
+            .search_messages(&chat)
+            .filter(grammers_tl_types::enums::MessagesFilter::InputMessagesFilterPinned);
+        while let Some(message) = iter.next().await? {
+            let sender_name = message
+                .sender()
+                .map(|s| s.name().to_string())
+                .unwrap_or_else(|| "Unknown".to_string());
+            let text = if message.text().is_empty() && message.media().is_some() {
+                "[media]".to_string()
+            } else {
+                message.text().to_string()
+            };
+            pinned.push((message.id(), sender_name, text));
+        }
+
+        pinned.reverse();
+        Ok(pinned)
+    }
+
     pub async fn get_message_sender(
         &self,
         chat_id: i64,
@@ -748,11 +1800,17 @@ impl TelegramClient {
         client: &Client,
         chat_id: i64,
     ) -> Result<Option<grammers_client::types::Chat>> {
+        if let Some(chat) = self.chat_cache.lock().await.get(&chat_id) {
+            return Ok(Some(chat.clone()));
+        }
+
         let mut dialogs = client.iter_dialogs();
 
         while let Some(dialog) = dialogs.next().await? {
             if dialog.chat().id() == chat_id {
-                return Ok(Some(dialog.chat().clone()));
+                let chat = dialog.chat().clone();
+                self.chat_cache.lock().await.insert(chat_id, chat.clone());
+                return Ok(Some(chat));
             }
         }
 
@@ -776,6 +1834,18 @@ impl TelegramClient {
         if handle.is_none() {
             let client = Arc::clone(&self.client);
             let updates = Arc::clone(&self.pending_updates);
+            let notify = Arc::clone(&self.update_notify);
+            let connection = Arc::clone(&self.connection);
+            let set_state = move |state: ConnectionState| {
+                let mut current = connection.lock().unwrap();
+                if *current != state {
+                    *current = state;
+                    // Wake the UI so the status bar reflects the change
+                    notify.notify_one();
+                }
+            };
+            let notify = Arc::clone(&self.update_notify);
+            let polls = Arc::clone(&self.polls);
 
             let task = tokio::spawn(async move {
                 loop {
@@ -791,36 +1861,31 @@ impl TelegramClient {
                     .await
                     {
                         Ok(Ok(update)) => {
+                            set_state(ConnectionState::Connected);
                             match update {
-                                Update::NewMessage(msg) if !msg.outgoing() => {
-                                    let chat_id = msg.chat().id();
-                                    let sender_name = msg
-                                        .sender()
-                                        .map(|s| s.name().to_string())
-                                        .unwrap_or_else(|| "Unknown".to_string());
-                                    let text = msg.text().to_string();
+                                Update::NewMessage(msg) => {
+                                    let (sender_id, sender_name) = message_sender(&msg);
+                                    let poll = PollInfo::from_message(&msg);
+                                    let update = TelegramUpdate::NewMessage {
+                                        chat_id: msg.chat().id(),
+                                        msg_id: msg.id(),
+                                        sender_id,
+                                        sender_name,
+                                        text: message_text(&msg),
+                                        quote_ranges: quote_ranges(&msg),
+                                        is_outgoing: msg.outgoing(),
+                                        reply_to_id: msg.reply_to_message_id(),
+                                        media_type: media_type(&msg),
+                                        media_label: media_label(&msg),
+                                        timestamp: msg.date().timestamp(),
+                                    };
 
                                     drop(client_lock);
-                                    let mut pending = updates.lock().await;
-                                    pending.push(TelegramUpdate::NewMessage {
-                                        chat_id,
-                                        _sender_name: sender_name,
-                                        text,
-                                        is_outgoing: false,
-                                    });
-                                }
-                                Update::NewMessage(msg) if msg.outgoing() => {
-                                    let chat_id = msg.chat().id();
-                                    let text = msg.text().to_string();
-
-                                    drop(client_lock);
-                                    let mut pending = updates.lock().await;
-                                    pending.push(TelegramUpdate::NewMessage {
-                                        chat_id,
-                                        _sender_name: "You".to_string(),
-                                        text,
-                                        is_outgoing: true,
-                                    });
+                                    if let Some(poll) = poll {
+                                        polls.lock().await.insert((msg.chat().id(), msg.id()), poll);
+                                    }
+                                    updates.lock().await.push(update);
+                                    notify.notify_one();
                                 }
                                 _ => {
                                     drop(client_lock);
@@ -829,7 +1894,20 @@ impl TelegramClient {
                         }
                         Ok(Err(_e)) => {
                             drop(client_lock);
-                            break;
+                            set_state(ConnectionState::Offline);
+                            tokio::time::sleep(RECONNECT_DELAY).await;
+                            set_state(ConnectionState::Connecting);
+                            // Probe with a cheap request; grammers reconnects on demand
+                            let probe = client
+                                .lock()
+                                .await
+                                .invoke(&grammers_tl_types::functions::updates::GetState {})
+                                .await;
+                            set_state(match probe {
+                                Ok(_) | Err(InvocationError::Rpc(_)) => ConnectionState::Connected,
+                                Err(_) => ConnectionState::Offline,
+                            });
+                            continue;
                         }
                         Err(_) => {
                             drop(client_lock);
@@ -851,9 +1929,222 @@ impl TelegramClient {
         Ok(updates)
     }
 
+    /// Queue an update produced outside the listener (e.g. a failed background send)
+    pub async fn queue_update(&self, update: TelegramUpdate) {
+        self.pending_updates.lock().await.push(update);
+        self.update_notify.notify_one();
+    }
+
+    /// Stop the background update listener, e.g. before dropping this client for another account
+    pub async fn stop_updates(&self) {
+        if let Some(handle) = self.update_handle.lock().await.take() {
+            handle.abort();
+        }
+    }
+
+    /// Notified whenever updates are waiting for `poll_updates`
+    pub fn update_notifier(&self) -> Arc<tokio::sync::Notify> {
+        Arc::clone(&self.update_notify)
+    }
+
     pub async fn _save_session(&self, path: &std::path::Path) -> Result<()> {
         let client = self.client.lock().await;
         client.session().save_to_file(path)?;
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use grammers_client::types::{Chat, User};
+    use grammers_tl_types as tl;
+
+    fn channel(broadcast: bool) -> Chat {
+        Chat::from_raw(
+            tl::types::ChannelForbidden {
+                id: 42,
+                broadcast,
+                megagroup: !broadcast,
+                access_hash: 0,
+                title: String::new(),
+                until_date: None,
+            }
+            .into(),
+        )
+    }
+
+    #[test]
+    fn test_is_muted() {
+        let settings = |mute_until| {
+            tl::types::PeerNotifySettings {
+                show_previews: None,
+                silent: None,
+                mute_until,
+                ios_sound: None,
+                android_sound: None,
+                other_sound: None,
+                stories_muted: None,
+                stories_hide_sender: None,
+                stories_ios_sound: None,
+                stories_android_sound: None,
+                stories_other_sound: None,
+            }
+            .into()
+        };
+        assert!(is_muted(&settings(Some(i32::MAX)), 1_700_000_000));
+        assert!(!is_muted(&settings(Some(1_600_000_000)), 1_700_000_000));
+        assert!(!is_muted(&settings(None), 1_700_000_000));
+    }
+
+    #[test]
+    fn test_chat_kind_classification() {
+        let user = Chat::User(User::from_raw(tl::types::UserEmpty { id: 1 }.into()));
+        let basic = Chat::from_raw(tl::types::ChatEmpty { id: 2 }.into());
+
+        assert_eq!(ChatKind::of(&user), ChatKind::User);
+        assert_eq!(ChatKind::of(&basic), ChatKind::BasicGroup);
+        assert_eq!(ChatKind::of(&channel(false)), ChatKind::Supergroup);
+        assert_eq!(ChatKind::of(&channel(true)), ChatKind::Channel);
+    }
+
+    #[test]
+    fn test_user_status_describe() {
+        use tl::enums::UserStatus as Raw;
+        let now = 1_700_000_000;
+        let offline = |secs_ago: i64| {
+            UserStatus::from_raw(&Raw::Offline(tl::types::UserStatusOffline {
+                was_online: (now - secs_ago) as i32,
+            }))
+        };
+
+        let online = UserStatus::from_raw(&Raw::Online(tl::types::UserStatusOnline { expires: 0 }));
+        assert_eq!(online.describe(now), "online");
+        assert_eq!(offline(30).describe(now), "last seen just now");
+        assert_eq!(offline(60).describe(now), "last seen 1m ago");
+        assert_eq!(offline(7200).describe(now), "last seen 2h ago");
+        assert_eq!(
+            UserStatus::from_raw(&Raw::Recently(tl::types::UserStatusRecently { by_me: false })).describe(now),
+            "last seen recently"
+        );
+        assert_eq!(
+            UserStatus::from_raw(&Raw::LastWeek(tl::types::UserStatusLastWeek { by_me: false })).describe(now),
+            "last seen within a week"
+        );
+        assert_eq!(
+            UserStatus::from_raw(&Raw::LastMonth(tl::types::UserStatusLastMonth { by_me: false })).describe(now),
+            "last seen within a month"
+        );
+        assert_eq!(UserStatus::from_raw(&Raw::Empty).describe(now), "last seen a long time ago");
+    }
+
+    #[test]
+    fn test_chat_kind_is_group() {
+        assert!(ChatKind::BasicGroup.is_group());
+        assert!(ChatKind::Supergroup.is_group());
+        assert!(!ChatKind::Channel.is_group());
+        assert!(!ChatKind::User.is_group());
+    }
+
+    #[test]
+    fn test_connection_state_after_result() {
+        assert_eq!(ConnectionState::after(&Ok(())), Some(ConnectionState::Connected));
+
+        let dropped: Result<()> = Err(InvocationError::Dropped.into());
+        assert_eq!(ConnectionState::after(&dropped), Some(ConnectionState::Offline));
+
+        // Errors that never reached the network say nothing about the connection
+        let local: Result<()> = Err(anyhow::anyhow!("Chat not found"));
+        assert_eq!(ConnectionState::after(&local), None);
+    }
+
+    #[test]
+    fn test_sort_by_activity() {
+        let chat = |id: i64, last_message_date: i64| ChatInfo {
+            id,
+            name: format!("Chat {}", id),
+            username: None,
+            unread: 0,
+            kind: ChatKind::User,
+            can_post: true,
+            muted: false,
+            last_message_date,
+        };
+        let mut chats = vec![chat(1, 100), chat(2, 300), chat(3, 100), chat(4, 200)];
+        sort_by_activity(&mut chats);
+        let ids: Vec<i64> = chats.iter().map(|c| c.id).collect();
+        // Ties keep their original order
+        assert_eq!(ids, vec![2, 4, 1, 3]);
+    }
+
+    #[test]
+    fn test_poll_render_and_vote() {
+        let option = |text: &str, id: u8, voters: Option<i32>| PollOption {
+            text: text.to_string(),
+            option: vec![id],
+            voters,
+            chosen: false,
+        };
+        let mut poll = PollInfo {
+            question: "Lunch?".to_string(),
+            options: vec![option("Pizza", 0, Some(2)), option("Sushi", 1, Some(3))],
+            total_voters: Some(5),
+            closed: false,
+        };
+        assert_eq!(poll.render(), "Lunch?\n1) Pizza (40%)\n2) Sushi (60%)");
+
+        assert!(!poll.apply_vote(2));
+        assert!(poll.apply_vote(0));
+        assert_eq!(poll.render(), "Lunch?\n1) Pizza (50%) ✓\n2) Sushi (50%)");
+        assert_eq!(poll.vote_error(1).as_deref(), Some("you already voted in this poll"));
+
+        // Hidden results show no percentages; closed polls say so and take no votes
+        let mut hidden = PollInfo {
+            question: "Q".to_string(),
+            options: vec![option("A", 0, None)],
+            total_voters: None,
+            closed: true,
+        };
+        assert_eq!(hidden.render(), "Q (closed)\n1) A");
+        assert!(!hidden.apply_vote(0));
+    }
+
+    #[test]
+    fn test_poll_cache_drops_oldest() {
+        let poll = |question: &str| PollInfo {
+            question: question.to_string(),
+            options: Vec::new(),
+            total_voters: None,
+            closed: false,
+        };
+        let mut cache = PollCache::default();
+        for id in 0..MAX_CACHED_POLLS as i32 {
+            cache.insert((1, id), poll("Q"));
+        }
+        // Refreshing a poll keeps its place; a new one pushes out the oldest
+        cache.insert((1, 0), poll("Updated"));
+        cache.insert((2, 0), poll("New"));
+        assert_eq!(cache.polls.len(), MAX_CACHED_POLLS);
+        assert!(cache.get(&(1, 0)).is_none());
+        assert_eq!(cache.get(&(1, 1)).map(|p| p.question.as_str()), Some("Q"));
+        assert_eq!(cache.get(&(2, 0)).map(|p| p.question.as_str()), Some("New"));
+    }
+
+    #[test]
+    fn test_validate_poll() {
+        let options = |n: usize| (1..=n).map(|i| format!("Option {}", i)).collect::<Vec<_>>();
+        assert!(validate_poll("Lunch?", &options(2)).is_ok());
+        assert!(validate_poll("Lunch?", &options(10)).is_ok());
+        assert!(validate_poll("Lunch?", &options(1)).is_err());
+        assert!(validate_poll("Lunch?", &options(11)).is_err());
+        assert!(validate_poll(" ", &options(2)).is_err());
+        assert!(validate_poll("Lunch?", &["A".to_string(), "".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_explain_member_error() {
+        assert_eq!(explain_member_error("USER_PRIVACY_RESTRICTED"), Some(PRIVACY_RESTRICTED));
+        assert_eq!(explain_member_error("USER_ALREADY_PARTICIPANT"), Some("already a member"));
+        assert_eq!(explain_member_error("FLOOD_WAIT"), None);
+    }
+}
@@ -0,0 +1,168 @@
+use ratatui::style::Color;
+
+use crate::utils::downgrade_color;
+
+/// Built-in themes, in the order `/theme` lists them
+pub const THEME_NAMES: &[&str] = &["default", "solarized", "mono"];
+
+/// Colors used to draw the chat list and panes
+#[derive(Debug, Clone, PartialEq)]
+pub struct Theme {
+    pub name: &'static str,
+    pub outgoing: Color,       // Own messages
+    pub incoming: Color,       // Others' messages in 1-on-1 chats
+    pub header: Color,         // Pane header of unfocused panes
+    pub header_focused: Color, // Pane header receiving input; also "connected"
+    pub header_target: Color,  // Pane header that the next chat from the list opens in
+    pub border_focused: Color, // Chat list and popups with focus
+    pub highlight: Color,      // Active chat in the list, unread divider
+    pub alert: Color,          // Unread markers, replies to me, offline
+    pub dim: Color,            // Separators, quotes, command output
+    pub reply_preview: Color,  // Bar above the input while replying
+    pub code: Color,           // `inline code` in messages
+    pub user_palette: Vec<Color>, // Sender colors in group chats
+}
+
+impl Theme {
+    /// Look up a built-in theme; None for unknown names
+    pub fn by_name(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "default" => Some(Self::default_theme()),
+            "solarized" => Some(Self::solarized()),
+            "mono" => Some(Self::mono()),
+            _ => None,
+        }
+    }
+
+    /// The theme named in the settings, falling back to "default" for unknown names
+    pub fn from_setting(name: &str) -> Self {
+        Self::by_name(name).unwrap_or_else(Self::default_theme)
+    }
+
+    /// Approximate RGB colors with the 16 basic ones when the terminal lacks truecolor
+    pub fn for_terminal(mut self, truecolor: bool) -> Self {
+        for color in [
+            &mut self.outgoing,
+            &mut self.incoming,
+            &mut self.header,
+            &mut self.header_focused,
+            &mut self.header_target,
+            &mut self.border_focused,
+            &mut self.highlight,
+            &mut self.alert,
+            &mut self.dim,
+            &mut self.reply_preview,
+            &mut self.code,
+        ] {
+            *color = downgrade_color(*color, truecolor);
+        }
+        for color in &mut self.user_palette {
+            *color = downgrade_color(*color, truecolor);
+        }
+        self
+    }
+
+    fn default_theme() -> Self {
+        Self {
+            name: "default",
+            outgoing: Color::Green,
+            incoming: Color::Cyan,
+            header: Color::Cyan,
+            header_focused: Color::Green,
+            header_target: Color::Yellow,
+            border_focused: Color::Green,
+            highlight: Color::Yellow,
+            alert: Color::Red,
+            dim: Color::DarkGray,
+            reply_preview: Color::Magenta,
+            code: Color::LightYellow,
+            user_palette: vec![
+                Color::Cyan, Color::Yellow, Color::Magenta, Color::Blue,
+                Color::Red, Color::Green, Color::White, Color::LightCyan,
+                Color::LightYellow, Color::LightMagenta, Color::LightBlue,
+                Color::LightRed, Color::LightGreen, Color::DarkGray,
+                Color::Rgb(192, 192, 192),
+                Color::Rgb(255, 165, 0),
+                Color::Rgb(255, 192, 203),
+                Color::Rgb(128, 0, 128),
+                Color::Rgb(0, 255, 255),
+                Color::Rgb(255, 20, 147),
+            ],
+        }
+    }
+
+    fn solarized() -> Self {
+        let yellow = Color::Rgb(181, 137, 0);
+        let orange = Color::Rgb(203, 75, 22);
+        let red = Color::Rgb(220, 50, 47);
+        let magenta = Color::Rgb(211, 54, 130);
+        let violet = Color::Rgb(108, 113, 196);
+        let blue = Color::Rgb(38, 139, 210);
+        let cyan = Color::Rgb(42, 161, 152);
+        let green = Color::Rgb(133, 153, 0);
+        Self {
+            name: "solarized",
+            outgoing: green,
+            incoming: blue,
+            header: cyan,
+            header_focused: green,
+            header_target: yellow,
+            border_focused: green,
+            highlight: yellow,
+            alert: red,
+            dim: Color::Rgb(88, 110, 117),
+            reply_preview: magenta,
+            code: orange,
+            user_palette: vec![yellow, orange, red, magenta, violet, blue, cyan, green],
+        }
+    }
+
+    fn mono() -> Self {
+        Self {
+            name: "mono",
+            outgoing: Color::White,
+            incoming: Color::Gray,
+            header: Color::Gray,
+            header_focused: Color::White,
+            header_target: Color::White,
+            border_focused: Color::White,
+            highlight: Color::White,
+            alert: Color::White,
+            dim: Color::DarkGray,
+            reply_preview: Color::Gray,
+            code: Color::Gray,
+            user_palette: vec![Color::White, Color::Gray],
+        }
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::default_theme()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_theme_lookup() {
+        for name in THEME_NAMES {
+            assert_eq!(Theme::by_name(name).unwrap().name, *name);
+        }
+        assert_eq!(Theme::by_name("Solarized").unwrap().name, "solarized");
+        assert!(Theme::by_name("neon").is_none());
+        assert_eq!(Theme::from_setting("neon"), Theme::default());
+    }
+
+    #[test]
+    fn test_theme_for_terminal() {
+        let theme = Theme::by_name("solarized").unwrap().for_terminal(false);
+        assert!(!matches!(theme.outgoing, Color::Rgb(..)));
+        assert!(theme.user_palette.iter().all(|c| !matches!(c, Color::Rgb(..))));
+        // Truecolor terminals keep the exact colors
+        let theme = Theme::by_name("solarized").unwrap().for_terminal(true);
+        assert_eq!(theme.outgoing, Color::Rgb(133, 153, 0));
+    }
+}
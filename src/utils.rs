@@ -17,6 +17,31 @@ pub fn _format_message_time(timestamp: i64) -> String {
     }
 }
 
+/// Short "time ago" text: "just now", "5m ago", "2h ago", "yesterday", else the date.
+/// `now` is passed in (unix seconds) so callers and tests agree on the reference point.
+pub fn format_relative_time(timestamp: i64, now: i64) -> String {
+    let ago = (now - timestamp).max(0);
+    match ago {
+        0..60 => "just now".to_string(),
+        60..3600 => format!("{}m ago", ago / 60),
+        3600..86400 => format!("{}h ago", ago / 3600),
+        _ => {
+            let local = |ts: i64| {
+                DateTime::from_timestamp(ts, 0)
+                    .unwrap_or_default()
+                    .with_timezone(&Local)
+                    .date_naive()
+            };
+            let date = local(timestamp);
+            if local(now).pred_opt() == Some(date) {
+                "yesterday".to_string()
+            } else {
+                date.format("%Y-%m-%d").to_string()
+            }
+        }
+    }
+}
+
 pub fn _log_message(message: &str, level: &str) {
     use std::fs::OpenOptions;
     use std::io::Write;
@@ -120,6 +145,132 @@ pub fn send_desktop_notification(title: &str, message: &str) {
     }
 }
 
+/// Whether `now` falls in the quiet window from `start` to `end` (local hours,
+/// end exclusive). A window with `start > end` crosses midnight, e.g. 22 to 7;
+/// equal hours make an empty window
+pub fn is_quiet_hours(now: DateTime<Local>, start: u8, end: u8) -> bool {
+    use chrono::Timelike;
+
+    let hour = now.hour() as u8;
+    if start <= end {
+        (start..end).contains(&hour)
+    } else {
+        hour >= start || hour < end
+    }
+}
+
+/// A quiet window with both hours in 0..24, or None (e.g. a hand-edited 24 in the config)
+pub fn valid_dnd_hours(start: u8, end: u8) -> Option<(u8, u8)> {
+    (start < 24 && end < 24).then_some((start, end))
+}
+
+/// Play a notification sound: "off", "system" or a path to a sound file (macOS and Linux)
+pub fn play_notification_sound(sound: &str) {
+    use std::process::Command;
+
+    if sound.is_empty() || sound == "off" {
+        return;
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        let path = if sound == "system" {
+            "/System/Library/Sounds/Glass.aiff"
+        } else {
+            sound
+        };
+        // spawn() so playback doesn't block the UI
+        let _ = Command::new("afplay").arg(path).spawn();
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        let path = if sound == "system" {
+            "/usr/share/sounds/freedesktop/stereo/message.oga"
+        } else {
+            sound
+        };
+        // Prefer PulseAudio/PipeWire, fall back to ALSA
+        if Command::new("paplay").arg(path).spawn().is_err() {
+            let _ = Command::new("aplay").arg("-q").arg(path).spawn();
+        }
+    }
+}
+
+/// Coalesces bursts of desktop notifications per chat.
+/// The first message in a quiet chat notifies immediately; anything arriving within
+/// `window` afterwards is counted and reported later as a single summary.
+pub struct NotificationThrottle {
+    window: std::time::Duration,
+    chats: std::collections::HashMap<i64, (std::time::Instant, u32)>, // chat_id -> (last notified, pending)
+}
+
+impl NotificationThrottle {
+    pub fn new(window: std::time::Duration) -> Self {
+        Self {
+            window,
+            chats: std::collections::HashMap::new(),
+        }
+    }
+
+    /// Register a new message. Returns true if a notification should be sent right away.
+    pub fn on_message(&mut self, chat_id: i64, now: std::time::Instant) -> bool {
+        match self.chats.get_mut(&chat_id) {
+            Some((last, pending)) if now.duration_since(*last) < self.window => {
+                *pending += 1;
+                false
+            }
+            _ => {
+                self.chats.insert(chat_id, (now, 0));
+                true
+            }
+        }
+    }
+
+    /// Take chats whose window has passed with coalesced messages: (chat_id, count)
+    pub fn take_due(&mut self, now: std::time::Instant) -> Vec<(i64, u32)> {
+        let mut due = Vec::new();
+        for (chat_id, (last, pending)) in self.chats.iter_mut() {
+            if *pending > 0 && now.duration_since(*last) >= self.window {
+                due.push((*chat_id, *pending));
+                *last = now;
+                *pending = 0;
+            }
+        }
+        due
+    }
+}
+
+/// Limits outgoing "typing…" updates: at most one per chat every `interval`
+/// while typing, plus one cancel when typing stops.
+pub struct TypingThrottle {
+    interval: std::time::Duration,
+    active: Option<(i64, std::time::Instant)>, // chat we last reported typing in, and when
+}
+
+impl TypingThrottle {
+    pub fn new(interval: std::time::Duration) -> Self {
+        Self { interval, active: None }
+    }
+
+    /// Register a keystroke. Returns true if a typing update should be sent now.
+    pub fn on_keystroke(&mut self, chat_id: i64, now: std::time::Instant) -> bool {
+        match self.active {
+            Some((chat, sent)) if chat == chat_id && now.duration_since(sent) < self.interval => false,
+            _ => {
+                self.active = Some((chat_id, now));
+                true
+            }
+        }
+    }
+
+    /// Typing stopped (message sent or input cleared). Returns the chat whose
+    /// typing status should be cancelled, if one was reported.
+    pub fn stop(&mut self) -> Option<i64> {
+        self.active.take().map(|(chat_id, _)| chat_id)
+    }
+}
+
 /// Normalize Telegram chat ID (channels use -100XXX format)
 pub fn normalize_chat_id(raw_id: i64) -> i64 {
     if raw_id < 0 {
@@ -135,6 +286,86 @@ pub fn normalize_chat_id(raw_id: i64) -> i64 {
     raw_id
 }
 
+/// Detect whether the terminal supports 24-bit color (via COLORTERM)
+pub fn detect_truecolor() -> bool {
+    std::env::var("COLORTERM")
+        .map(|v| {
+            let v = v.to_lowercase();
+            v == "truecolor" || v == "24bit"
+        })
+        .unwrap_or(false)
+}
+
+/// Map an RGB color to the nearest of the 16 ANSI colors when truecolor is unavailable
+pub fn downgrade_color(color: ratatui::style::Color, truecolor: bool) -> ratatui::style::Color {
+    use ratatui::style::Color;
+
+    let (r, g, b) = match color {
+        Color::Rgb(r, g, b) if !truecolor => (r as i32, g as i32, b as i32),
+        other => return other,
+    };
+
+    // Reference values from the xterm default palette
+    const ANSI: [(Color, (i32, i32, i32)); 16] = [
+        (Color::Black, (0, 0, 0)),
+        (Color::Red, (205, 0, 0)),
+        (Color::Green, (0, 205, 0)),
+        (Color::Yellow, (205, 205, 0)),
+        (Color::Blue, (0, 0, 238)),
+        (Color::Magenta, (205, 0, 205)),
+        (Color::Cyan, (0, 205, 205)),
+        (Color::Gray, (229, 229, 229)),
+        (Color::DarkGray, (127, 127, 127)),
+        (Color::LightRed, (255, 0, 0)),
+        (Color::LightGreen, (0, 255, 0)),
+        (Color::LightYellow, (255, 255, 0)),
+        (Color::LightBlue, (92, 92, 255)),
+        (Color::LightMagenta, (255, 0, 255)),
+        (Color::LightCyan, (0, 255, 255)),
+        (Color::White, (255, 255, 255)),
+    ];
+
+    ANSI.iter()
+        .min_by_key(|(_, (ar, ag, ab))| (r - ar).pow(2) + (g - ag).pow(2) + (b - ab).pow(2))
+        .map(|(c, _)| *c)
+        .unwrap_or(Color::White)
+}
+
+/// Case-insensitive subsequence match: "jdoe" matches "John Doe"
+pub fn fuzzy_match(query: &str, text: &str) -> bool {
+    let mut chars = text.chars().flat_map(char::to_lowercase);
+    query
+        .chars()
+        .flat_map(char::to_lowercase)
+        .filter(|c| !c.is_whitespace())
+        .all(|q| chars.any(|c| c == q))
+}
+
+/// "@name" with only the characters Telegram allows in usernames
+pub fn looks_like_username(text: &str) -> bool {
+    text.strip_prefix('@').is_some_and(|name| {
+        !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+    })
+}
+
+/// Expand a leading "~" to the home directory
+pub fn expand_tilde(path: &str) -> std::path::PathBuf {
+    match path.strip_prefix('~') {
+        Some(rest) if rest.is_empty() || rest.starts_with('/') => match dirs::home_dir() {
+            Some(home) => home.join(rest.trim_start_matches('/')),
+            None => path.into(),
+        },
+        _ => path.into(),
+    }
+}
+
+/// Files Telegram can show inline as a photo; everything else goes as a document
+pub fn is_image_path(path: &std::path::Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| matches!(ext.to_ascii_lowercase().as_str(), "jpg" | "jpeg" | "png" | "webp"))
+}
+
 /// Available commands for autocomplete
 pub const COMMANDS: &[&str] = &[
     "/reply ",
@@ -147,8 +378,12 @@ pub const COMMANDS: &[&str] = &[
     "/d ",
     "/alias ",
     "/unalias ",
+    "/rename ",
+    "/peek ",
     "/filter ",
     "/search ",
+    "/next",
+    "/prev",
     "/s ",
     "/new ",
     "/newgroup ",
@@ -156,9 +391,35 @@ pub const COMMANDS: &[&str] = &[
     "/kick ",
     "/remove ",
     "/members",
+    "/seen ",
     "/forward ",
     "/fwd ",
     "/f ",
+    "/pins",
+    "/cancel",
+    "/me ",
+    "/debug",
+    "/undo",
+    "/expand ",
+    "/react ",
+    "/poll ",
+    "/vote ",
+    "/log",
+    "/copy ",
+    "/open ",
+    "/upload ",
+    "/goto ",
+    "/theme ",
+    "/export",
+    "/unread",
+    "/pinchat",
+    "/unpinchat",
+    "/profile ",
+    "/mute",
+    "/unmute",
+    "/away ",
+    "/dnd ",
+    "/limit ",
 ];
 
 /// Try to autocomplete a command prefix. Returns (completed_text, options_hint)
@@ -232,6 +493,74 @@ mod tests {
         assert_eq!(normalize_chat_id(1234567), 1234567);
     }
 
+    #[test]
+    fn test_downgrade_color() {
+        use ratatui::style::Color;
+
+        // Truecolor terminals keep RGB as-is
+        assert_eq!(downgrade_color(Color::Rgb(255, 165, 0), true), Color::Rgb(255, 165, 0));
+        // Otherwise map to the nearest ANSI color
+        assert_eq!(downgrade_color(Color::Rgb(255, 165, 0), false), Color::Yellow);
+        assert_eq!(downgrade_color(Color::Rgb(0, 255, 255), false), Color::LightCyan);
+        assert_eq!(downgrade_color(Color::Rgb(192, 192, 192), false), Color::Gray);
+        // Named colors are never touched
+        assert_eq!(downgrade_color(Color::Cyan, false), Color::Cyan);
+    }
+
+    #[test]
+    fn test_notification_throttle() {
+        use std::time::{Duration, Instant};
+
+        let mut throttle = NotificationThrottle::new(Duration::from_secs(10));
+        let start = Instant::now();
+
+        // First message notifies, the burst after it is held back
+        assert!(throttle.on_message(1, start));
+        assert!(!throttle.on_message(1, start + Duration::from_secs(1)));
+        assert!(!throttle.on_message(1, start + Duration::from_secs(2)));
+        // Other chats are tracked independently
+        assert!(throttle.on_message(2, start + Duration::from_secs(2)));
+
+        assert!(throttle.take_due(start + Duration::from_secs(5)).is_empty());
+        assert_eq!(throttle.take_due(start + Duration::from_secs(10)), vec![(1, 2)]);
+        assert!(throttle.take_due(start + Duration::from_secs(30)).is_empty());
+    }
+
+    #[test]
+    fn test_format_relative_time() {
+        let now = Local::now().timestamp();
+        assert_eq!(format_relative_time(now - 30, now), "just now");
+        assert_eq!(format_relative_time(now - 300, now), "5m ago");
+        assert_eq!(format_relative_time(now - 7200, now), "2h ago");
+        // A timestamp ahead of the clock is treated as now
+        assert_eq!(format_relative_time(now + 10, now), "just now");
+
+        let today = Local::now().date_naive();
+        let start_of = |date: chrono::NaiveDate| {
+            date.and_hms_opt(0, 0, 1).unwrap().and_local_timezone(Local).unwrap().timestamp()
+        };
+        let yesterday = today.pred_opt().unwrap();
+        assert_eq!(format_relative_time(start_of(yesterday), now), "yesterday");
+        let older = today - chrono::Days::new(3);
+        assert_eq!(format_relative_time(start_of(older), now), older.format("%Y-%m-%d").to_string());
+    }
+
+    #[test]
+    fn test_typing_throttle() {
+        use std::time::{Duration, Instant};
+
+        let start = Instant::now();
+        let mut throttle = TypingThrottle::new(Duration::from_secs(4));
+        assert!(throttle.on_keystroke(1, start));
+        assert!(!throttle.on_keystroke(1, start + Duration::from_secs(2)));
+        assert!(throttle.on_keystroke(1, start + Duration::from_secs(5)));
+        // Switching chats reports right away
+        assert!(throttle.on_keystroke(2, start + Duration::from_secs(6)));
+        assert_eq!(throttle.stop(), Some(2));
+        assert_eq!(throttle.stop(), None);
+        assert!(throttle.on_keystroke(2, start + Duration::from_secs(7)));
+    }
+
     #[test]
     fn test_autocomplete() {
         let (result, _) = try_autocomplete("/rep");
@@ -241,4 +570,65 @@ mod tests {
         // Multiple matches: /filter, /forward, /fwd, /f
         assert!(result.is_some() || hint.is_some());
     }
+
+    #[test]
+    fn test_fuzzy_match() {
+        assert!(fuzzy_match("jdoe", "John Doe"));
+        assert!(fuzzy_match("JOHN", "john doe"));
+        assert!(fuzzy_match("", "anything"));
+        assert!(!fuzzy_match("doej", "John Doe"));
+        assert!(fuzzy_match("@ali", "Alice @alice_w"));
+    }
+
+    #[test]
+    fn test_looks_like_username() {
+        assert!(looks_like_username("@alice_w"));
+        assert!(!looks_like_username("alice"));
+        assert!(!looks_like_username("@"));
+        assert!(!looks_like_username("@alice w"));
+    }
+
+    #[test]
+    fn test_expand_tilde() {
+        let home = dirs::home_dir().unwrap();
+        assert_eq!(expand_tilde("~/a.png"), home.join("a.png"));
+        assert_eq!(expand_tilde("~"), home);
+        assert_eq!(expand_tilde("/tmp/a.png"), std::path::PathBuf::from("/tmp/a.png"));
+        assert_eq!(expand_tilde("~bob/a"), std::path::PathBuf::from("~bob/a"));
+    }
+
+    #[test]
+    fn test_is_image_path() {
+        use std::path::Path;
+        assert!(is_image_path(Path::new("cat.JPG")));
+        assert!(is_image_path(Path::new("/tmp/x.png")));
+        assert!(!is_image_path(Path::new("report.pdf")));
+        assert!(!is_image_path(Path::new("anim.gif")));
+        assert!(!is_image_path(Path::new("noext")));
+    }
+
+    #[test]
+    fn test_is_quiet_hours() {
+        use chrono::TimeZone;
+        let at = |hour| Local.with_ymd_and_hms(2024, 1, 15, hour, 30, 0).earliest().unwrap();
+
+        // 22 -> 7 wraps past midnight
+        assert!(is_quiet_hours(at(22), 22, 7));
+        assert!(is_quiet_hours(at(23), 22, 7));
+        assert!(is_quiet_hours(at(0), 22, 7));
+        assert!(is_quiet_hours(at(6), 22, 7));
+        assert!(!is_quiet_hours(at(7), 22, 7));
+        assert!(!is_quiet_hours(at(12), 22, 7));
+        assert!(!is_quiet_hours(at(21), 22, 7));
+
+        // Same-day window
+        assert!(is_quiet_hours(at(13), 13, 15));
+        assert!(!is_quiet_hours(at(15), 13, 15));
+        assert!(!is_quiet_hours(at(9), 13, 15));
+
+        assert!(!is_quiet_hours(at(10), 10, 10));
+
+        assert_eq!(valid_dnd_hours(23, 0), Some((23, 0)));
+        assert_eq!(valid_dnd_hours(22, 24), None);
+    }
 }
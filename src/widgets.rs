@@ -1,4 +1,9 @@
-use std::collections::HashMap;
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::Arc;
+
+use crate::formatting::FormattedMessages;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum FilterType {
@@ -7,6 +12,59 @@ pub enum FilterType {
     Link,
 }
 
+impl FilterType {
+    pub fn as_setting(&self) -> &'static str {
+        match self {
+            FilterType::Sender => "sender",
+            FilterType::Media => "media",
+            FilterType::Link => "link",
+        }
+    }
+
+    pub fn from_setting(value: &str) -> Option<Self> {
+        match value {
+            "sender" => Some(FilterType::Sender),
+            "media" => Some(FilterType::Media),
+            "link" => Some(FilterType::Link),
+            _ => None,
+        }
+    }
+}
+
+/// Reactions offered when the server's list can't be fetched
+pub const DEFAULT_REACTIONS: &[&str] = &[
+    "👍", "👎", "❤", "🔥", "🥰", "👏", "😁", "🤔", "🤯", "😱", "😢", "🎉", "🤩", "🙏", "👌", "💯",
+];
+
+/// Popup grid for choosing a reaction to one message
+pub struct ReactionPicker {
+    pub pane_idx: usize,
+    pub chat_id: i64,
+    pub msg_id: i32,
+    pub msg_num: usize, // #N as shown in the pane, for the title and notifications
+    pub options: Vec<String>,
+    pub selected: usize,
+}
+
+impl ReactionPicker {
+    pub const COLUMNS: usize = 8;
+
+    /// Move the selection by whole cells; moves off the grid are ignored
+    pub fn move_by(&mut self, dx: isize, dy: isize) {
+        let target = self.selected as isize + dx + dy * Self::COLUMNS as isize;
+        if target >= 0 && (target as usize) < self.options.len() {
+            self.selected = target as usize;
+        }
+    }
+
+    pub fn current(&self) -> Option<&str> {
+        self.options.get(self.selected).map(String::as_str)
+    }
+}
+
+/// sender_id used for messages with no sender and for anonymous group admins
+pub const ANONYMOUS_SENDER_ID: i64 = 0;
+
 /// Represents a single message with all its metadata for display
 #[derive(Clone, Debug)]
 pub struct MessageData {
@@ -14,42 +72,131 @@ pub struct MessageData {
     pub sender_id: i64,
     pub sender_name: String,
     pub text: String,
+    pub quote_ranges: Vec<(usize, usize)>, // Blockquote entities (UTF-16 offset, length), drawn with QUOTE_PREFIX
     pub is_outgoing: bool,
     pub timestamp: i64,        // Unix timestamp
     pub media_type: Option<String>,
-    pub media_label: Option<String>,  // e.g. "[YouTube: title]"
+    pub media_label: Option<String>,  // Details added to the media_type label, e.g. "1280×720" or "report.pdf (2.3 MB)"
     pub reactions: HashMap<String, u32>,
     pub reply_to_msg_id: Option<i32>,
     pub reply_sender: Option<String>,
     pub reply_text: Option<String>,
+    pub send_failed: bool,            // Optimistic local echo that never reached Telegram
+    pub pending: bool,                // Optimistic local echo with a temporary (negative) msg_id
 }
 
+impl MessageData {
+    /// A fetched message; the reply preview is filled in later
+    pub fn from_raw(raw: crate::telegram::RawMessage, my_user_id: i64) -> Self {
+        Self {
+            msg_id: raw.id,
+            sender_id: raw.sender_id,
+            sender_name: raw.sender_name,
+            text: raw.text,
+            quote_ranges: raw.quote_ranges,
+            is_outgoing: raw.sender_id == my_user_id,
+            timestamp: raw.timestamp,
+            media_type: raw.media_type,
+            media_label: raw.media_label,
+            reactions: raw.reactions,
+            reply_to_msg_id: raw.reply_to_id,
+            reply_sender: None,
+            reply_text: None,
+            send_failed: false,
+            pending: false,
+        }
+    }
+
+    /// A plain incoming text message, for tests to adjust
+    #[cfg(test)]
+    pub fn test(msg_id: i32, sender_id: i64, sender_name: &str, text: &str) -> Self {
+        Self {
+            msg_id,
+            sender_id,
+            sender_name: sender_name.to_string(),
+            text: text.to_string(),
+            quote_ranges: Vec::new(),
+            is_outgoing: false,
+            timestamp: 0,
+            media_type: None,
+            media_label: None,
+            reactions: HashMap::new(),
+            reply_to_msg_id: None,
+            reply_sender: None,
+            reply_text: None,
+            send_failed: false,
+            pending: false,
+        }
+    }
+}
+
+/// A send or upload running in the background for a pane
+pub struct PendingSend {
+    pub label: String,                    // e.g. "Uploading report.pdf"
+    pub text: String,                     // Text of the optimistic local echo, if any
+    pub progress: Option<Arc<AtomicU8>>,  // Percent complete, for uploads that report it
+    pub handle: tokio::task::JoinHandle<()>,
+}
+
+/// Command output shown under the history ("✓ Replied to #5", /pins listings, ...)
+#[derive(Clone, Debug)]
+pub struct StatusLine {
+    pub text: String,
+    pub added: std::time::Instant,
+}
+
+/// Most command output lines kept per pane; older ones are dropped
+const MAX_STATUS_LINES: usize = 100;
+
+/// Command output disappears this long after it was printed
+const STATUS_LINE_TTL: std::time::Duration = std::time::Duration::from_secs(300);
+
 pub struct ChatPane {
     pub chat_id: Option<i64>,
     pub chat_name: String,
     pub username: Option<String>,
-    pub messages: Vec<String>,         // Formatted display lines
+    pub messages: Vec<StatusLine>,     // Command output, shown below the history
     pub msg_data: Vec<MessageData>,    // Raw message data for formatting
-    pub scroll_offset: usize,
+    pub scroll_offset: usize,          // Lines scrolled up from the newest message (0 = follow new)
+    pub scroll_view: Cell<(usize, usize)>, // (max scroll, visible height) from the last render
+    pub message_offsets: RefCell<Vec<Option<usize>>>, // First rendered line of each message, from the last render
+    pub unread_divider_offset: Cell<Option<usize>>, // Rendered line of the unread divider, from the last render
     pub reply_to_message: Option<i32>,  // Telegram message ID to reply to
     pub reply_preview: Option<String>, // Text shown in reply preview bar
     pub filter_type: Option<FilterType>,
     pub filter_value: Option<String>,
     pub typing_indicator: Option<String>, // "Name is typing..."
     pub typing_expire: Option<std::time::Instant>,
-    pub online_status: String,
+    pub user_status: Option<crate::telegram::UserStatus>, // Peer's last seen state in 1-on-1 chats
+    pub user_status_chat: Option<i64>, // Chat `user_status` was fetched for
     pub pinned_message: Option<String>,
-    pub _unread_count: u32,
-    pub unread_count_at_load: u32,
-    pub format_cache: HashMap<FormatCacheKey, Vec<String>>,
+    pub unread_count: u32,             // New messages since the pane was last focused
+    pub first_unread_msg_id: Option<i32>, // Oldest message unread when the chat was opened
+    pub format_cache: FormatCache,
     pub input_buffer: String,          // Per-pane input buffer
     pub input_cursor: usize,           // Cursor byte position in input_buffer
+    pub pending_sends: Vec<PendingSend>, // In-flight sends, most recent last
+    pub editing_message: Option<i32>,  // Telegram message ID being edited ("edit last" mode)
+    pub search_query: Option<String>,  // Active /search (see `search_active`), for "/search more"
+    pub search_offset_id: i32,         // Oldest result so far; the next page starts below it
+    pub search_term: Option<String>,   // Query whose matches are highlighted and stepped through (/next, /prev)
+    pub search_matches: Vec<i32>,      // Message ids of the search results, newest first
+    pub search_match_idx: Option<usize>, // Match last jumped to
+    pub pending_jump: Option<i32>,     // Message to scroll to once the next render has laid it out
+    pub expanded_messages: HashSet<i32>, // Long messages shown in full (/expand)
+    pub log_view: bool,                // One truncated line per message (/log)
+    pub peeking: bool,                 // Opened with /peek: viewing doesn't clear the unread badge
+    pub members_cache: Option<(i64, Vec<crate::telegram::Member>)>, // (chat_id, /members result)
+    pub history_exhausted: bool,       // Scrolled past the first message of the chat; stop fetching
+    next_temp_id: i32,                 // Last temporary id handed to an optimistic message
+    my_reactions: HashMap<(i64, i32), String>, // Reactions we set this session, by (chat_id, msg_id)
 }
 
 #[derive(Hash, Eq, PartialEq, Clone, Debug)]
 pub struct FormatCacheKey {
     pub width: u16,
     pub compact_mode: bool,
+    pub group_messages: bool,
     pub show_emojis: bool,
     pub show_reactions: bool,
     pub show_timestamps: bool,
@@ -57,6 +204,43 @@ pub struct FormatCacheKey {
     pub msg_count: usize,
     pub filter_type: Option<String>,
     pub filter_value: Option<String>,
+    pub first_unread: Option<i32>,
+    pub alignment: &'static str,
+    pub log_view: bool,
+    pub render_markdown: bool,
+    pub search_term: Option<String>,
+}
+
+/// Last formatted message lines of a pane. Rendering only has `&ChatPane`, so the
+/// entry lives in a RefCell; anything that changes `msg_data` must call `clear()`.
+#[derive(Default)]
+pub struct FormatCache {
+    entry: RefCell<Option<(FormatCacheKey, FormattedMessages)>>,
+}
+
+impl FormatCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn clear(&mut self) {
+        *self.entry.get_mut() = None;
+    }
+
+    /// Return the cached lines for `key`, formatting and storing them on a miss
+    pub fn get_or_format(
+        &self,
+        key: FormatCacheKey,
+        format: impl FnOnce() -> FormattedMessages,
+    ) -> FormattedMessages {
+        let mut entry = self.entry.borrow_mut();
+        if let Some((_, formatted)) = entry.as_ref().filter(|(cached_key, _)| *cached_key == key) {
+            return formatted.clone();
+        }
+        let formatted = format();
+        *entry = Some((key, formatted.clone()));
+        formatted
+    }
 }
 
 impl ChatPane {
@@ -68,24 +252,65 @@ impl ChatPane {
             messages: Vec::new(),
             msg_data: Vec::new(),
             scroll_offset: 0,
+            scroll_view: Cell::new((0, 0)),
+            message_offsets: RefCell::new(Vec::new()),
+            unread_divider_offset: Cell::new(None),
             reply_to_message: None,
             reply_preview: None,
             filter_type: None,
             filter_value: None,
             typing_indicator: None,
             typing_expire: None,
-            online_status: String::new(),
+            user_status: None,
+            user_status_chat: None,
             pinned_message: None,
-            _unread_count: 0,
-            unread_count_at_load: 0,
+            unread_count: 0,
+            first_unread_msg_id: None,
             input_buffer: String::new(),
             input_cursor: 0,
-            format_cache: HashMap::new(),
+            format_cache: FormatCache::new(),
+            pending_sends: Vec::new(),
+            editing_message: None,
+            search_query: None,
+            search_offset_id: 0,
+            search_term: None,
+            search_matches: Vec::new(),
+            search_match_idx: None,
+            pending_jump: None,
+            expanded_messages: HashSet::new(),
+            log_view: false,
+            peeking: false,
+            members_cache: None,
+            history_exhausted: false,
+            next_temp_id: 0,
+            my_reactions: HashMap::new(),
         }
     }
 
+    /// Members loaded by /members, if they belong to the chat currently open
+    pub fn cached_members(&self) -> Option<&[crate::telegram::Member]> {
+        self.members_cache
+            .as_ref()
+            .filter(|(chat_id, _)| Some(*chat_id) == self.chat_id)
+            .map(|(_, members)| members.as_slice())
+    }
+
     pub fn add_message(&mut self, message: String) {
-        self.messages.push(message);
+        self.messages.push(StatusLine {
+            text: message,
+            added: std::time::Instant::now(),
+        });
+        if self.messages.len() > MAX_STATUS_LINES {
+            let excess = self.messages.len() - MAX_STATUS_LINES;
+            self.messages.drain(..excess);
+        }
+    }
+
+    /// Drop command output older than the TTL. Returns true if anything was removed.
+    pub fn expire_status_lines(&mut self) -> bool {
+        let before = self.messages.len();
+        self.messages.retain(|line| line.added.elapsed() < STATUS_LINE_TTL);
+        self.messages.len() != before
     }
 
     pub fn clear(&mut self) {
@@ -96,12 +321,206 @@ impl ChatPane {
         self.format_cache.clear();
     }
 
-    pub fn scroll_up(&mut self) {
-        self.scroll_offset = self.scroll_offset.saturating_sub(3);
+    /// Scroll towards older messages, stopping at the first line
+    pub fn scroll_up(&mut self, lines: usize) {
+        let (max_scroll, _) = self.scroll_view.get();
+        self.scroll_offset = self.scroll_offset.saturating_add(lines).min(max_scroll);
+    }
+
+    /// Show a message we are about to send right away, under a temporary negative id
+    /// until the server's copy arrives
+    pub fn push_optimistic(&mut self, sender_id: i64, text: String, reply_to: Option<i32>) {
+        self.next_temp_id -= 1;
+        self.msg_data.push(MessageData {
+            msg_id: self.next_temp_id,
+            sender_id,
+            sender_name: "You".to_string(),
+            text,
+            quote_ranges: Vec::new(),
+            is_outgoing: true,
+            timestamp: chrono::Utc::now().timestamp(),
+            media_type: None,
+            media_label: None,
+            reactions: HashMap::new(),
+            reply_to_msg_id: reply_to,
+            reply_sender: None,
+            reply_text: None,
+            send_failed: false,
+            pending: true,
+        });
+        self.format_cache.clear();
+    }
+
+    /// Add a message delivered by a live update. Our own messages replace their
+    /// optimistic local echo (see `push_optimistic`) instead of showing up twice.
+    pub fn append_incoming(&mut self, message: MessageData) {
+        if self.msg_data.iter().any(|m| m.msg_id == message.msg_id) {
+            return;
+        }
+        if message.text.is_empty() && message.media_type.is_none() {
+            return;
+        }
+        if message.is_outgoing {
+            // Uploads are echoed as "📎 name\ncaption"; the server sends just the caption
+            fn upload_caption(text: &str) -> Option<&str> {
+                text.strip_prefix("📎 ")
+                    .map(|rest| rest.split_once('\n').map_or("", |(_, caption)| caption))
+            }
+            let is_echo = |m: &MessageData| {
+                m.pending
+                    && !m.send_failed
+                    && m.sender_id == message.sender_id
+                    && (m.text == message.text
+                        || message.media_type.is_some()
+                            && upload_caption(&m.text) == Some(message.text.as_str()))
+            };
+            // Anything else (e.g. sent from another device) is a new message
+            if let Some(pos) = self.msg_data.iter().position(is_echo) {
+                // Take the server's id so /reply, /edit etc. can target it
+                let echo = &mut self.msg_data[pos];
+                echo.msg_id = message.msg_id;
+                echo.timestamp = message.timestamp;
+                echo.pending = false;
+                // Uploads are echoed as a placeholder; show what the server actually sent
+                if message.media_type.is_some() {
+                    echo.text = message.text;
+                    echo.quote_ranges = message.quote_ranges;
+                    echo.media_type = message.media_type;
+                    echo.media_label = message.media_label;
+                }
+                self.format_cache.clear();
+                return;
+            }
+        }
+        self.msg_data.push(message);
+        self.format_cache.clear();
+    }
+
+    /// Our reaction on a message, as far as this session knows
+    pub fn my_reaction(&self, msg_id: i32) -> Option<&str> {
+        self.my_reactions.get(&(self.chat_id?, msg_id)).map(String::as_str)
+    }
+
+    /// Replace our reaction on a message (None clears it), adjusting the shown
+    /// counts right away. Returns the previous reaction so a failed send can undo it.
+    pub fn set_my_reaction(&mut self, msg_id: i32, emoji: Option<&str>) -> Option<String> {
+        let key = (self.chat_id.unwrap_or_default(), msg_id);
+        let previous = match emoji {
+            Some(emoji) => self.my_reactions.insert(key, emoji.to_string()),
+            None => self.my_reactions.remove(&key),
+        };
+        if let Some(msg) = self.msg_data.iter_mut().find(|m| m.msg_id == msg_id) {
+            if let Some(old) = &previous
+                && let Some(count) = msg.reactions.get_mut(old)
+            {
+                *count = count.saturating_sub(1);
+                if *count == 0 {
+                    msg.reactions.remove(old);
+                }
+            }
+            if let Some(emoji) = emoji {
+                *msg.reactions.entry(emoji.to_string()).or_insert(0) += 1;
+            }
+            self.format_cache.clear();
+        }
+        previous
+    }
+
+    /// Scrolled as far up as the loaded messages go
+    pub fn at_top(&self) -> bool {
+        self.scroll_offset >= self.scroll_view.get().0
+    }
+
+    /// Telegram ID of the oldest loaded message; older history is fetched below it
+    pub fn oldest_msg_id(&self) -> Option<i32> {
+        self.msg_data.iter().map(|m| m.msg_id).filter(|&id| id > 0).min()
+    }
+
+    /// Scroll so message #`msg_num` (1-based) starts at the top of the view, using
+    /// the layout measured by the last render. Returns false if it isn't shown.
+    pub fn scroll_to_message(&mut self, msg_num: usize) -> bool {
+        let start = msg_num
+            .checked_sub(1)
+            .and_then(|idx| self.message_offsets.borrow().get(idx).copied().flatten());
+        let Some(start) = start else {
+            return false;
+        };
+        self.scroll_to_line(start);
+        true
+    }
+
+    /// Scroll so the unread divider is at the top of the view. Returns false if
+    /// the last render didn't show one.
+    pub fn scroll_to_unread_divider(&mut self) -> bool {
+        let Some(start) = self.unread_divider_offset.get() else {
+            return false;
+        };
+        self.scroll_to_line(start);
+        true
+    }
+
+    fn scroll_to_line(&mut self, line: usize) {
+        let (max_scroll, _) = self.scroll_view.get();
+        self.scroll_offset = max_scroll - line.min(max_scroll);
+    }
+
+    /// Place the unread divider above the last `unread` loaded messages. It stays
+    /// there while newer messages arrive, until the chat is opened again.
+    pub fn set_unread_divider(&mut self, unread: u32) {
+        self.first_unread_msg_id = match unread as usize {
+            0 => None,
+            n => self.msg_data.get(self.msg_data.len().saturating_sub(n)).map(|m| m.msg_id),
+        };
+        self.format_cache.clear();
+    }
+
+    /// Scroll towards newer messages; reaching the bottom follows new messages again
+    pub fn scroll_down(&mut self, lines: usize) {
+        self.scroll_offset = self.scroll_offset.saturating_sub(lines);
     }
 
-    pub fn scroll_down(&mut self) {
-        self.scroll_offset = self.scroll_offset.saturating_add(3);
+    /// Forget the matches of the last search; false if there were none
+    pub fn clear_search_matches(&mut self) -> bool {
+        let had_matches = self.search_term.is_some();
+        self.search_term = None;
+        self.search_matches.clear();
+        self.search_match_idx = None;
+        self.pending_jump = None;
+        self.format_cache.clear();
+        had_matches
+    }
+
+    /// Scroll to `pending_jump` if the last render showed it. True if the view moved.
+    pub fn apply_pending_jump(&mut self) -> bool {
+        let Some(msg_id) = self.pending_jump.take() else {
+            return false;
+        };
+        self.msg_data
+            .iter()
+            .position(|m| m.msg_id == msg_id)
+            .is_some_and(|idx| self.scroll_to_message(idx + 1))
+    }
+
+    /// The pane is showing /search results rather than the live chat
+    pub fn search_active(&self) -> bool {
+        self.search_query.is_some()
+    }
+
+    /// Chat name without the " | Search: ..." suffix added while searching
+    pub fn base_chat_name(&self) -> &str {
+        self.chat_name.split(" | Search:").next().unwrap_or(&self.chat_name)
+    }
+
+    /// Keep the offset within the range measured by the last render
+    pub fn clamp_scroll(&mut self) {
+        let (max_scroll, _) = self.scroll_view.get();
+        self.scroll_offset = self.scroll_offset.min(max_scroll);
+    }
+
+    /// One screen minus a line of overlap, so context isn't lost between pages
+    pub fn page_height(&self) -> usize {
+        let (_, height) = self.scroll_view.get();
+        height.saturating_sub(1).max(1)
     }
 
     pub fn show_typing_indicator(&mut self, name: &str) {
@@ -130,12 +549,85 @@ impl ChatPane {
         self.reply_preview = None;
     }
 
-    /// Build the header text including online status, username, pinned message, typing indicator
-    pub fn header_text(&self) -> String {
-        let mut header = self.chat_name.clone();
+    /// Load my most recent sent message into the input and enter edit mode.
+    /// Returns false if there is nothing to edit.
+    pub fn start_edit_last(&mut self) -> bool {
+        let Some(last) = self
+            .msg_data
+            .iter()
+            .rev()
+            .find(|m| m.is_outgoing && !m.pending && !m.text.is_empty())
+        else {
+            return false;
+        };
+        self.editing_message = Some(last.msg_id);
+        self.input_buffer = last.text.clone();
+        self.input_cursor = self.input_buffer.len();
+        true
+    }
 
-        if !self.online_status.is_empty() {
-            header.push_str(&format!(" [{}]", self.online_status));
+    /// Leave edit mode and discard the loaded text
+    pub fn cancel_edit(&mut self) {
+        if self.editing_message.take().is_some() {
+            self.input_buffer.clear();
+            self.input_cursor = 0;
+        }
+    }
+
+    /// Whether the input still holds the untouched text of the message being edited
+    pub fn edit_unchanged(&self) -> bool {
+        self.editing_message.is_some_and(|id| {
+            self.msg_data
+                .iter()
+                .any(|m| m.msg_id == id && m.text == self.input_buffer)
+        })
+    }
+
+    pub fn track_send(
+        &mut self,
+        label: String,
+        text: String,
+        handle: tokio::task::JoinHandle<()>,
+        progress: Option<Arc<AtomicU8>>,
+    ) {
+        self.pending_sends.push(PendingSend {
+            label,
+            text,
+            progress,
+            handle,
+        });
+    }
+
+    pub fn prune_finished_sends(&mut self) {
+        self.pending_sends.retain(|p| !p.handle.is_finished());
+    }
+
+    /// Abort the most recent in-flight send and drop its optimistic message.
+    /// Returns the label of the cancelled send.
+    pub fn cancel_last_send(&mut self) -> Option<String> {
+        self.prune_finished_sends();
+        let pending = self.pending_sends.pop()?;
+        pending.handle.abort();
+        if let Some(pos) = self.msg_data.iter().rposition(|m| {
+            !pending.text.is_empty() && m.pending && m.text == pending.text
+        }) {
+            self.msg_data.remove(pos);
+            self.format_cache.clear();
+        }
+        Some(pending.label)
+    }
+
+    /// Build the header text including online status, username, pinned message, typing indicator.
+    /// `chat_name` is the name to show, which may be a local /rename override.
+    pub fn header_text(&self, chat_name: &str) -> String {
+        let mut header = chat_name.to_string();
+
+        if self.peeking {
+            header.push_str(" (peek)");
+        }
+
+        if let Some(status) = self.user_status.filter(|_| self.user_status_chat == self.chat_id) {
+            header.push_str(&format!(" [{}]", status.describe(chrono::Utc::now().timestamp())));
         }
 
         if let Some(ref username) = self.username {
@@ -152,6 +644,17 @@ impl ChatPane {
             header.push_str(&format!(" {}", typing));
         }
 
+        if let Some(pending) = self.pending_sends.last() {
+            match pending.progress {
+                Some(ref progress) => header.push_str(&format!(
+                    " | {}… {}%",
+                    pending.label,
+                    progress.load(Ordering::Relaxed)
+                )),
+                None => header.push_str(&format!(" | {}…", pending.label)),
+            }
+        }
+
         header
     }
 
@@ -187,3 +690,62 @@ impl Default for ChatPane {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sent(msg_id: i32, text: &str) -> MessageData {
+        MessageData {
+            is_outgoing: true,
+            ..MessageData::test(msg_id, 1, "You", text)
+        }
+    }
+
+    #[test]
+    fn test_append_incoming_confirms_echo() {
+        let mut pane = ChatPane::new();
+        pane.push_optimistic(1, "first".to_string(), None);
+        pane.push_optimistic(1, "second".to_string(), None);
+        assert_eq!(pane.msg_data[0].msg_id, -1);
+        assert_eq!(pane.msg_data[1].msg_id, -2);
+        assert!(pane.msg_data.iter().all(|m| m.pending));
+
+        // Confirmations arriving out of order still land on their own echo
+        pane.append_incoming(sent(11, "second"));
+        pane.append_incoming(sent(10, "first"));
+        let ids: Vec<(i32, &str, bool)> =
+            pane.msg_data.iter().map(|m| (m.msg_id, m.text.as_str(), m.pending)).collect();
+        assert_eq!(ids, vec![(10, "first", false), (11, "second", false)]);
+
+        // Duplicate delivery is ignored
+        pane.append_incoming(sent(11, "second"));
+        assert_eq!(pane.msg_data.len(), 2);
+    }
+
+    #[test]
+    fn test_append_incoming_keeps_unmatched_echo() {
+        let mut pane = ChatPane::new();
+        pane.push_optimistic(1, "still sending".to_string(), None);
+
+        // Sent from another device: a new message, the echo stays pending
+        pane.append_incoming(sent(20, "from my phone"));
+        assert_eq!(pane.msg_data.len(), 2);
+        assert!(pane.msg_data[0].pending);
+        assert_eq!(pane.msg_data[1].msg_id, 20);
+    }
+
+    #[test]
+    fn test_append_incoming_confirms_upload() {
+        let mut pane = ChatPane::new();
+        pane.push_optimistic(1, "📎 cat.jpg\nlook".to_string(), None);
+
+        let mut upload = sent(30, "look");
+        upload.media_type = Some("photo".to_string());
+        pane.append_incoming(upload);
+        assert_eq!(pane.msg_data.len(), 1);
+        assert_eq!(pane.msg_data[0].msg_id, 30);
+        assert_eq!(pane.msg_data[0].text, "look");
+        assert_eq!(pane.msg_data[0].media_type.as_deref(), Some("photo"));
+    }
+}